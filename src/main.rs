@@ -1,31 +1,32 @@
 use anyhow::anyhow;
 use clap::Parser;
 use crossterm::ExecutableCommand;
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::Terminal;
 use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
 use ratatui::prelude::Backend;
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Text};
+use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph};
 use regex::Regex;
 use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
 use std::cmp::min;
-use std::collections::HashSet;
 use std::fmt::Display;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
 use std::str::FromStr;
+use std::sync::LazyLock;
 use std::time::Duration;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
 struct BoardId(i64);
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Serialize)]
 struct CardId(i64);
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 struct ExternalCardId(i64);
 
 struct StatusId(i64);
@@ -84,16 +85,310 @@ impl rusqlite::types::FromSql for StatusId {
     }
 }
 
-#[derive(Debug)]
+/// what a column means for the flow of work across a board.
+/// cards get `doing_at` and `done_at` stamped when they move into these columns
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Stage {
+    Doing,
+    Done,
+}
+
+impl Stage {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Doing => "doing",
+            Stage::Done => "done",
+        }
+    }
+}
+
+impl FromStr for Stage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "doing" => Ok(Stage::Doing),
+            "done" => Ok(Stage::Done),
+            _ => Err(anyhow!("unknown column stage: {}", s)),
+        }
+    }
+}
+
+impl rusqlite::ToSql for Stage {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl rusqlite::types::FromSql for Stage {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: anyhow::Error| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+/// how urgent a card is. `P0` is the most urgent
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Priority(u8);
+
+impl Priority {
+    const LEVELS: u8 = 4;
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "P{}", self.0)
+    }
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(level) = s.strip_prefix(['P', 'p'])
+            && let Ok(level) = level.parse()
+            && level < Self::LEVELS
+        {
+            Ok(Priority(level))
+        } else {
+            Err(anyhow!(
+                "priorities go from P0 to P{}, not {}",
+                Self::LEVELS - 1,
+                s
+            ))
+        }
+    }
+}
+
+impl Serialize for Priority {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl rusqlite::ToSql for Priority {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.0.into())
+    }
+}
+
+impl rusqlite::types::FromSql for Priority {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        u8::column_result(value).map(Priority)
+    }
+}
+
+/// the order cards are shown in within each column of a board
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortMode {
+    /// the order cards were put in by hand
+    #[default]
+    Manual,
+    /// most urgent first, then manual order
+    Priority,
+    /// most recently updated first
+    Updated,
+    /// most recently created first
+    Inserted,
+    /// by card number, lowest first
+    Id,
+}
+
+impl SortMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortMode::Manual => "manual",
+            SortMode::Priority => "priority",
+            SortMode::Updated => "updated",
+            SortMode::Inserted => "inserted",
+            SortMode::Id => "id",
+        }
+    }
+
+    fn next(&self) -> SortMode {
+        match self {
+            SortMode::Manual => SortMode::Priority,
+            SortMode::Priority => SortMode::Updated,
+            SortMode::Updated => SortMode::Inserted,
+            SortMode::Inserted => SortMode::Id,
+            SortMode::Id => SortMode::Manual,
+        }
+    }
+
+    fn order_by(&self) -> &'static str {
+        match self {
+            SortMode::Manual => "cards.rank desc, cards.id desc",
+            SortMode::Priority => {
+                "cards.priority is null, cards.priority asc, cards.rank desc, cards.id desc"
+            }
+            SortMode::Updated => "cards.updated_at desc, cards.id desc",
+            SortMode::Inserted => "cards.inserted_at desc, cards.id desc",
+            SortMode::Id => "cards.external_id asc",
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            SortMode::Manual => "in manual order",
+            SortMode::Priority => "by priority",
+            SortMode::Updated => "by last updated",
+            SortMode::Inserted => "by newest",
+            SortMode::Id => "by number",
+        }
+    }
+}
+
+impl FromStr for SortMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manual" => Ok(SortMode::Manual),
+            "priority" => Ok(SortMode::Priority),
+            "updated" => Ok(SortMode::Updated),
+            "inserted" => Ok(SortMode::Inserted),
+            "id" => Ok(SortMode::Id),
+            _ => Err(anyhow!("unknown sort mode: {}", s)),
+        }
+    }
+}
+
+impl rusqlite::ToSql for SortMode {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl rusqlite::types::FromSql for SortMode {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: anyhow::Error| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+/// what happened to a card, as recorded in `card_events`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CardEventKind {
+    Created,
+    Moved,
+    Edited,
+    Deleted,
+    Restored,
+    Sent,
+    Received,
+    Imported,
+}
+
+impl CardEventKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CardEventKind::Created => "created",
+            CardEventKind::Moved => "moved",
+            CardEventKind::Edited => "edited",
+            CardEventKind::Deleted => "deleted",
+            CardEventKind::Restored => "restored",
+            CardEventKind::Sent => "sent",
+            CardEventKind::Received => "received",
+            CardEventKind::Imported => "imported",
+        }
+    }
+}
+
+impl FromStr for CardEventKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(CardEventKind::Created),
+            "moved" => Ok(CardEventKind::Moved),
+            "edited" => Ok(CardEventKind::Edited),
+            "deleted" => Ok(CardEventKind::Deleted),
+            "restored" => Ok(CardEventKind::Restored),
+            "sent" => Ok(CardEventKind::Sent),
+            "received" => Ok(CardEventKind::Received),
+            "imported" => Ok(CardEventKind::Imported),
+            _ => Err(anyhow!("unknown card event: {}", s)),
+        }
+    }
+}
+
+impl rusqlite::ToSql for CardEventKind {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl rusqlite::types::FromSql for CardEventKind {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: anyhow::Error| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+#[derive(Debug, Serialize)]
 struct BoardMeta {
     id: BoardId,
     name: String,
     columns: Vec<String>,
+    /// the stage of each column in `columns`
+    stages: Vec<Option<Stage>>,
     inserted_at: String,
     updated_at: String,
     viewed_at: String,
 }
 
+/// a change to a board's name and columns.
+///
+/// if removing columns would strand cards,
+/// this waits in `Model::board_edit` while the user picks where those cards go
+#[derive(Debug)]
+struct BoardEdit {
+    board_id: BoardId,
+    name: String,
+    /// the board's columns after the edit, in order
+    columns: Vec<String>,
+    /// the stage of each column in `columns`
+    stages: Vec<Option<Stage>>,
+    /// (old name, new name)
+    renames: Vec<(String, String)>,
+    removals: Vec<ColumnRemoval>,
+    /// the column highlighted in the destination picker
+    destination_index: usize,
+}
+
+#[derive(Debug)]
+struct ColumnRemoval {
+    column: String,
+    has_cards: bool,
+    /// the column the removed column's cards move to
+    destination: Option<String>,
+}
+
+impl BoardEdit {
+    /// the next removed column whose cards still need somewhere to go
+    fn undecided_removal(&self) -> Option<&ColumnRemoval> {
+        self.removals
+            .iter()
+            .find(|removal| removal.has_cards && removal.destination.is_none())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum ConfirmationState {
     Yes,
@@ -118,8 +413,40 @@ struct Model {
     mode: Mode,
     running_state: RunningState,
     confirmation_state: ConfirmationState,
+    showing_archived_boards: bool,
+    board_edit: Option<BoardEdit>,
+    metrics: Option<BoardMetrics>,
+    activity: Option<Activity>,
+    /// the most recent operation is at the back
+    undo_stack: std::collections::VecDeque<Operation>,
+    redo_stack: Vec<Operation>,
+    /// only cards with this tag are shown
+    tag_filter: Option<String>,
+    /// cards that don't contain this are dimmed
+    board_filter: Option<String>,
+    /// what has been typed after `:` or `#`
+    card_number: Option<String>,
+    send_card: Option<SendCard>,
+    /// cards marked in `Mode::SelectingCards`, in the order they were marked
+    marked: Vec<CardId>,
+    /// what has been typed in `Mode::TaggingCards`
+    new_tag: Option<String>,
+    tag_picker: Option<TagPicker>,
+    conflict: Option<EditConflict>,
+    due_thresholds: DueThresholds,
+    agenda: Option<Agenda>,
+    search: Option<Search>,
+    /// the format chosen in `Mode::ExportingBoard`, as an index into `ExportFormat::ALL`
+    export_format: Option<usize>,
+    /// where `Mode::ExportingBoard` writes files.
+    /// `None` is whatever the current directory is at the time of the export
+    export_dir: Option<PathBuf>,
     repo: Repo,
+    /// `Repo::data_version` as of the last time the board was read
+    data_version: i64,
     error: Option<String>,
+    /// what the last thing done did, shown until the next key press
+    notice: Option<String>,
     highlight_color: Color,
     internal_event_tx: std::sync::mpsc::Sender<Event>,
     internal_event_rx: std::sync::mpsc::Receiver<Event>,
@@ -156,12 +483,33 @@ impl Model {
             board_metas: vec![],
             board,
             confirmation_state: ConfirmationState::No,
+            showing_archived_boards: false,
+            board_edit: None,
+            metrics: None,
+            activity: None,
+            undo_stack: std::collections::VecDeque::new(),
+            redo_stack: vec![],
+            tag_filter: None,
+            board_filter: None,
+            card_number: None,
+            send_card: None,
+            marked: vec![],
+            new_tag: None,
+            tag_picker: None,
+            conflict: None,
+            due_thresholds: repo.get_due_thresholds()?,
+            agenda: None,
+            search: None,
+            export_format: None,
+            export_dir: None,
             selected,
             mode,
             running_state: RunningState::Running,
+            data_version: repo.data_version()?,
             repo,
             highlight_color: Color::from_str(&options.highlight_color)?,
             error: None,
+            notice: None,
             internal_event_tx: tx,
             internal_event_rx: rx,
         })
@@ -169,7 +517,14 @@ impl Model {
 
     fn switch_to_viewing_boards_mode(&mut self) -> anyhow::Result<()> {
         self.mode = Mode::ViewingBoards;
-        self.board_metas = self.repo.get_board_metas()?;
+        self.refresh_board_metas()?;
+
+        // operations only make sense on the board they were done on
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.tag_filter = None;
+        self.board_filter = None;
+        self.marked.clear();
 
         self.board = None;
         self.selected.card_index = None;
@@ -192,12 +547,10 @@ impl Model {
         }
     }
 
+    /// new cards are ranked above everything else in their column
     fn add_card_to_selected_column(&mut self, card: Card) {
         if let Some(current_column) = self.selected_column_mut() {
-            current_column.cards.push(card);
-            current_column
-                .cards
-                .sort_unstable_by(|a, b| b.id.cmp(&a.id));
+            current_column.cards.insert(0, card);
         }
     }
 
@@ -220,61 +573,124 @@ impl Model {
     }
 
     fn move_selected_card_left(&mut self) -> anyhow::Result<()> {
+        if let Some(column_index) = self.selected.column_index {
+            self.move_selected_card_to_column(column_index.saturating_sub(1))?;
+        }
+
+        Ok(())
+    }
+
+    fn move_selected_card_right(&mut self) -> anyhow::Result<()> {
+        if let Some(column_index) = self.selected.column_index {
+            self.move_selected_card_to_column(column_index + 1)?;
+        }
+
+        Ok(())
+    }
+
+    /// to the top of the column at `column_index`, if there is one
+    fn move_selected_card_to_column(&mut self, column_index: usize) -> anyhow::Result<()> {
         if let Some(board) = &mut self.board
             && let Some(selected_column_index) = self.selected.column_index
             && let Some(selected_card_index) = self.selected.card_index
+            && column_index != selected_column_index
+            && column_index < board.columns.len()
         {
-            let left_column_id = selected_column_index.saturating_sub(1);
+            let card = board.columns[selected_column_index]
+                .cards
+                .remove(selected_card_index);
 
-            if left_column_id != selected_column_index {
-                let card = board.columns[selected_column_index]
-                    .cards
-                    .remove(selected_card_index);
+            self.repo
+                .set_card_status(board.id, card.id, &board.columns[column_index].name)?;
 
-                self.repo.set_card_status(
-                    board.id,
-                    card.id,
-                    &board.columns[left_column_id].name,
-                )?;
+            let operation = Operation::Move {
+                card_id: card.id,
+                from: board.columns[selected_column_index].name.clone(),
+                to: board.columns[column_index].name.clone(),
+            };
 
-                board.columns[left_column_id].cards.insert(0, card);
+            board.columns[column_index].cards.insert(0, card);
 
-                self.selected.card_index = Some(0);
+            self.record_operation(operation);
 
-                self.selected.column_index = Some(left_column_id);
-            }
+            self.selected.card_index = Some(0);
+
+            self.selected.column_index = Some(column_index);
+
+            self.resort_board()?;
         }
 
         Ok(())
     }
 
-    fn move_selected_card_right(&mut self) -> anyhow::Result<()> {
-        if let Some(board) = &mut self.board
-            && let Some(selected_column_index) = self.selected.column_index
-            && let Some(selected_card_index) = self.selected.card_index
+    fn ensure_manual_sort(&self) -> anyhow::Result<()> {
+        if let Some(board) = &self.board
+            && board.sort_mode != SortMode::Manual
         {
-            let right_column_index = min(
-                selected_column_index + 1,
-                board.columns.len().saturating_sub(1),
-            );
+            Err(anyhow!(
+                "cards are sorted {}, press o until they are in manual order to reorder them",
+                board.sort_mode.describe()
+            ))
+        } else {
+            Ok(())
+        }
+    }
 
-            if right_column_index != selected_column_index {
-                let card = board.columns[selected_column_index]
-                    .cards
-                    .remove(selected_card_index);
+    fn move_selected_card_up(&mut self) -> anyhow::Result<()> {
+        self.ensure_manual_sort()?;
 
-                self.repo.set_card_status(
-                    board.id,
-                    card.id,
-                    &board.columns[right_column_index].name,
-                )?;
+        if let Some(card_index) = self.selected.card_index
+            && card_index > 0
+        {
+            self.swap_selected_card_with(card_index - 1)?;
+        }
+
+        Ok(())
+    }
+
+    fn move_selected_card_down(&mut self) -> anyhow::Result<()> {
+        self.ensure_manual_sort()?;
+
+        if let Some(card_index) = self.selected.card_index
+            && let Some(column) = self.selected_column()
+            && card_index + 1 < column.cards.len()
+        {
+            self.swap_selected_card_with(card_index + 1)?;
+        }
 
-                board.columns[right_column_index].cards.insert(0, card);
+        Ok(())
+    }
 
-                self.selected.card_index = Some(0);
+    fn swap_selected_card_with(&mut self, other_card_index: usize) -> anyhow::Result<()> {
+        if let Some(card_index) = self.selected.card_index
+            && let Some(board) = &mut self.board
+            && let Some(column_index) = self.selected.column_index
+            && let Some(column) = board.columns.get_mut(column_index)
+        {
+            // the column may be filtered, so swap the two cards
+            // within the whole column, not just what is shown
+            let mut card_ids = self
+                .repo
+                .cards_for_column(board.id, &column.name)?
+                .iter()
+                .map(|card| card.id)
+                .collect::<Vec<_>>();
 
-                self.selected.column_index = Some(right_column_index);
+            if let Some(i) = card_ids
+                .iter()
+                .position(|id| *id == column.cards[card_index].id)
+                && let Some(j) = card_ids
+                    .iter()
+                    .position(|id| *id == column.cards[other_card_index].id)
+            {
+                card_ids.swap(i, j);
             }
+
+            self.repo.rank_cards(&card_ids)?;
+
+            column.cards.swap(card_index, other_card_index);
+
+            self.selected.card_index = Some(other_card_index);
         }
 
         Ok(())
@@ -302,6 +718,21 @@ impl Model {
         }
     }
 
+    fn navigate_down(&mut self) {
+        self.selected.card_index = self.selected.card_index.map(|i| {
+            min(
+                i.saturating_add(1),
+                self.selected_column()
+                    .map(|column| column.cards.len().saturating_sub(1))
+                    .unwrap_or(usize::MAX),
+            )
+        })
+    }
+
+    fn navigate_up(&mut self) {
+        self.selected.card_index = self.selected.card_index.map(|i| i.saturating_sub(1))
+    }
+
     fn navigate_left(&mut self) {
         if let Some(board) = &mut self.board
             && let Some(selected_column_index) = self.selected.column_index
@@ -364,32 +795,30 @@ impl Model {
     fn load_selected_board(&mut self) -> anyhow::Result<()> {
         if let Some(board_index) = self.selected.board_index {
             let board_id = self.board_metas[board_index].id;
-            let board = self.repo.load_board(board_id)?;
-
-            self.selected.board_index = None;
-            self.selected.column_index = Some(0);
-            self.selected.card_index = if !board.columns[0].cards.is_empty() {
-                Some(0)
-            } else {
-                None
-            };
-            self.board_metas = vec![];
-
-            self.board = Some(board);
+            self.open_board(board_id, None)?;
         }
         Ok(())
     }
 
+    /// columns may be marked with a stage, like `Doing [doing]`
     fn create_board(&mut self, name: &str, column_names: &[&str]) -> anyhow::Result<()> {
         if !column_names.is_empty() {
-            self.repo.create_board(name, column_names)?;
-            self.board_metas = self.repo.get_board_metas()?;
+            let (column_names, stages): (Vec<_>, Vec<_>) = column_names
+                .iter()
+                .map(|column_name| parse_column_stage(column_name))
+                .unzip();
+
+            self.repo.create_board(name, &column_names, &stages)?;
+            self.refresh_board_metas()?;
             Ok(())
         } else {
             Err(anyhow!("Board must have at least 1 column"))
         }
     }
 
+    /// columns written as `- Old -> New` are renamed, keeping their cards.
+    /// columns left out are removed. if any of them have cards,
+    /// we ask where those cards should go before changing anything
     fn update_selected_board(
         &mut self,
         new_board_name: &str,
@@ -397,23 +826,151 @@ impl Model {
     ) -> anyhow::Result<()> {
         let selected_board = &self.board_metas[self.selected.board_index.unwrap()];
 
-        let current_names_set: HashSet<_> = selected_board.columns.iter().cloned().collect();
+        let mut columns = vec![];
+        let mut stages = vec![];
+        let mut renames = vec![];
+
+        for column_name in new_column_names {
+            let (column_name, stage) = parse_column_stage(column_name);
 
-        let new_names_set: HashSet<_> = new_column_names.iter().map(|s| s.to_string()).collect();
+            stages.push(stage);
 
-        // TODO figure out adding/removing/etc
-        if new_names_set.is_superset(&current_names_set) {
-            let _new_board = self.repo.update_board_columns_order(
-                selected_board.id,
-                new_board_name,
-                new_column_names,
-            )?;
+            if let Some((from, to)) = column_name.split_once("->") {
+                let (from, to) = (from.trim(), to.trim());
+
+                if !selected_board.columns.iter().any(|c| c == from) {
+                    return Err(anyhow!(
+                        "Could not update board: no column named {} to rename",
+                        from
+                    ));
+                }
+
+                renames.push((from.to_string(), to.to_string()));
+                columns.push(to.to_string());
+            } else {
+                columns.push(column_name.to_string());
+            }
+        }
+
+        let mut removals = vec![];
+
+        for column in &selected_board.columns {
+            if !columns.contains(column) && !renames.iter().any(|(from, _to)| from == column) {
+                removals.push(ColumnRemoval {
+                    column: column.to_string(),
+                    has_cards: self.repo.count_cards_in_column(selected_board.id, column)? > 0,
+                    destination: None,
+                });
+            }
+        }
+
+        let board_edit = BoardEdit {
+            board_id: selected_board.id,
+            name: new_board_name.to_string(),
+            columns,
+            stages,
+            renames,
+            removals,
+            destination_index: 0,
+        };
+
+        if board_edit.undecided_removal().is_some() {
+            self.board_edit = Some(board_edit);
+            self.mode = Mode::ChoosingColumnDestination;
+        } else {
+            self.repo.update_board(&board_edit)?;
+            self.refresh_board_metas()?;
+        }
+
+        Ok(())
+    }
+
+    /// sends the cards of the removed column currently being asked about
+    /// to the highlighted column, and applies the edit once every removed column has an answer
+    fn choose_column_destination(&mut self) -> anyhow::Result<()> {
+        if let Some(board_edit) = &mut self.board_edit {
+            let destination = board_edit.columns[board_edit.destination_index].clone();
+
+            if let Some(removal) = board_edit
+                .removals
+                .iter_mut()
+                .find(|removal| removal.has_cards && removal.destination.is_none())
+            {
+                removal.destination = Some(destination);
+            }
+
+            board_edit.destination_index = 0;
+
+            if board_edit.undecided_removal().is_none() {
+                let board_edit = self.board_edit.take().unwrap();
+                self.mode = Mode::ViewingBoards;
+                self.repo.update_board(&board_edit)?;
+                self.refresh_board_metas()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn refresh_board_metas(&mut self) -> anyhow::Result<()> {
+        self.board_metas = if self.showing_archived_boards {
+            self.repo.get_archived_board_metas()?
+        } else {
+            self.repo.get_board_metas()?
+        };
+
+        Ok(())
+    }
 
-            self.board_metas = self.repo.get_board_metas()?;
+    /// keep the board selection in bounds after boards disappear from the list
+    fn clamp_selected_board_index(&mut self) {
+        self.selected.board_index = if self.board_metas.is_empty() {
+            None
+        } else {
+            Some(min(
+                self.selected.board_index.unwrap_or(0),
+                self.board_metas.len() - 1,
+            ))
+        };
+    }
+
+    fn toggle_archived_boards_view(&mut self) -> anyhow::Result<()> {
+        self.showing_archived_boards = !self.showing_archived_boards;
+        self.refresh_board_metas()?;
+        self.selected.board_index = if self.board_metas.is_empty() {
+            None
         } else {
-            return Err(anyhow!("Could not update board: columns do not match"));
+            Some(0)
+        };
+        Ok(())
+    }
+
+    /// archives the selected board, or unarchives it if we are looking at archived boards
+    fn toggle_selected_board_archived(&mut self) -> anyhow::Result<()> {
+        if let Some(board_index) = self.selected.board_index {
+            self.repo.set_board_archived(
+                self.board_metas[board_index].id,
+                !self.showing_archived_boards,
+            )?;
+            self.refresh_board_metas()?;
+            self.clamp_selected_board_index();
+        }
+        Ok(())
+    }
+
+    fn confirm_board_delete(&mut self) -> anyhow::Result<()> {
+        if self.selected.board_index.is_some() {
+            self.mode = Mode::ConfirmBoardDeletion;
         }
+        Ok(())
+    }
 
+    fn delete_selected_board(&mut self) -> anyhow::Result<()> {
+        if let Some(board_index) = self.selected.board_index {
+            self.repo.delete_board(self.board_metas[board_index].id)?;
+            self.refresh_board_metas()?;
+            self.clamp_selected_board_index();
+        }
         Ok(())
     }
 
@@ -430,2752 +987,13656 @@ impl Model {
             && let Some(card_index) = self.selected.card_index.as_mut()
         {
             self.repo.delete_card(card_id)?;
-            column.cards.remove(*card_index);
+            let card = column.cards.remove(*card_index);
+
+            let operation = Operation::Delete {
+                card,
+                column: column.name.clone(),
+                index: *card_index,
+            };
+
             if column.cards.len().saturating_sub(1) < *card_index {
                 *card_index = column.cards.len().saturating_sub(1);
             }
+
+            self.record_operation(operation);
         }
 
         Ok(())
     }
-}
-
-#[derive(Debug)]
-struct Repo {
-    conn: Connection,
-}
 
-impl Repo {
-    fn new(override_database_path: Option<PathBuf>) -> anyhow::Result<Self> {
-        let database_path = if let Some(database_path) = override_database_path {
-            database_path.to_owned()
+    /// the selected card as it is in the database,
+    /// which is newer than what is on screen if it changed somewhere else
+    fn selected_card_from_repo(&self) -> anyhow::Result<Option<Card>> {
+        if let Some(card_id) = self.selected_card_id() {
+            self.repo.get_card(card_id)
         } else {
-            let mut database_path = directories::ProjectDirs::from("", "", "kk")
-            .expect("unable to find home directory. if you like, you can provide a database path directly by passing the -d option.")
-            .data_local_dir()
-            .to_path_buf();
-
-            std::fs::create_dir_all(&database_path)?;
-
-            database_path.push("kk.db");
+            Ok(None)
+        }
+    }
 
-            database_path
-        };
+    /// saves an edit of `base`, unless what the card says was changed somewhere else
+    /// while it was being edited. then `Mode::ResolvingConflict` asks which to keep
+    fn save_card_edit(&mut self, base: &Card, text: CardText) -> anyhow::Result<()> {
+        let current = self.repo.get_card(base.id)?.ok_or_else(|| {
+            anyhow!(
+                "card {} was deleted while you were editing it",
+                base.external_id
+            )
+        })?;
 
-        let mut conn = rusqlite::Connection::open(database_path)?;
+        // only what the card says counts, so a move somewhere else doesn't conflict with an edit
+        if current.text() != base.text() && current.text() != text {
+            self.conflict = Some(EditConflict {
+                mine: text,
+                theirs: current,
+                mode: self.mode,
+                selected: 0,
+            });
+            self.mode = Mode::ResolvingConflict;
+            return Ok(());
+        }
 
-        conn.pragma_update(None, "foreign_keys", "on")?;
-        conn.pragma_update(None, "synchronous", "extra")?;
+        self.reload_board(Some(base.id))?;
 
-        #[cfg(target_os = "macos")]
-        conn.pragma_update(None, "fullfsync", true)?;
+        if self.selected_card_id() != Some(base.id) {
+            return Err(anyhow!(
+                "card {} is not on this board anymore",
+                base.external_id
+            ));
+        }
 
-        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        self.edit_selected_card(text)
+    }
 
-        Self::setup_database(&mut conn)?;
+    fn resolve_conflict<F, B>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        run_editor_fn: F,
+    ) -> anyhow::Result<()>
+    where
+        F: Fn(&mut Terminal<B>, &str) -> anyhow::Result<String>,
+        B: Backend,
+    {
+        if let Some(conflict) = self.conflict.take() {
+            self.mode = conflict.mode;
+
+            match EditConflict::CHOICES[conflict.selected] {
+                "keep mine" => self.save_card_edit(&conflict.theirs, conflict.mine)?,
+                "keep theirs" => self.reload_board(Some(conflict.theirs.id))?,
+                _ => {
+                    let mut text = edit_until_parsed(
+                        &conflict.to_editor_text(),
+                        |text| run_editor_fn(terminal, text),
+                        parse_raw_card_text,
+                    )?;
+                    text.body = EditConflict::remove_theirs(&text.body);
+                    self.save_card_edit(&conflict.theirs, text)?;
+                }
+            }
+        }
 
-        Ok(Self { conn })
+        Ok(())
     }
 
-    fn setup_database(conn: &mut Connection) -> anyhow::Result<()> {
-        conn.execute_batch(
-            "
-            create table if not exists boards (
-                id integer primary key,
-                name text not null,
-                card_id integer not null default 1,
-                inserted_at timestamp not null default current_timestamp,
-                updated_at timestamp not null default current_timestamp,
-                viewed_at timestamp not null default current_timestamp
-            );
+    fn edit_selected_card(&mut self, text: CardText) -> anyhow::Result<()> {
+        if let Some(board) = &self.board
+            && let Some(card) = self.selected_card()
+        {
+            let board_id = board.id;
+            let card_id = card.id;
+            let before = card.text();
+
+            let updated_at = self.repo.edit_card(board_id, card_id, &text)?;
+
+            if let Some(card) = self.selected_card_mut() {
+                card.title = text.title.clone();
+                card.body = text.body.clone();
+                card.tags = text.tags.clone();
+                card.due_on = text.due_on.clone();
+                card.priority = text.priority;
+                card.updated_at = updated_at;
+            }
 
-            create unique index if not exists boards_name on boards (name);
+            if before != text {
+                self.record_operation(Operation::Edit {
+                    card_id,
+                    before,
+                    after: text,
+                });
+            }
 
-            create table if not exists statuses (
-                id integer primary key,
-                name text not null,
-                column_order integer not null,
-                board_id integer not null,
-                inserted_at timestamp not null default current_timestamp,
-                updated_at timestamp not null default current_timestamp,
+            self.resort_board()?;
+        }
 
-                foreign key(board_id) references boards(id) on delete cascade
-            );
+        Ok(())
+    }
 
-            create unique index if not exists statuses_name_board_id on statuses (name, board_id);
-            -- not possible to do this while updating orders that could be the same
-            -- during a transaction
-            -- create unique index if not exists statuses_column_order_board_id on statuses (column_order, board_id);
-            create index if not exists statuses_board_id on statuses (board_id);
+    const UNDO_LIMIT: usize = 100;
 
-            create table if not exists cards (
-                id integer primary key,
-                external_id integer not null,
-                board_id integer not null,
-                title text not null,
-                status_id integer not null,
-                body text not null,
-                doing_at timestamp,
-                done_at timestamp,
-                inserted_at timestamp not null default current_timestamp,
-                updated_at timestamp not null default current_timestamp,
+    /// anything new makes whatever was undone before it unredoable
+    fn record_operation(&mut self, operation: Operation) {
+        self.undo_stack.push_back(operation);
 
-                foreign key(board_id) references boards(id) on delete cascade,
-                foreign key(status_id) references statuses(id) on delete cascade
-            );
+        if self.undo_stack.len() > Self::UNDO_LIMIT {
+            self.undo_stack.pop_front();
+        }
 
-            create index if not exists cards_board_id on cards (board_id);
-            create index if not exists cards_status_id on cards (status_id);
+        self.redo_stack.clear();
+    }
 
-            create trigger if not exists cards_updated after update on cards
-            for each row
-            begin
-                update cards
-                set updated_at = current_timestamp
-                where cards.id = NEW.id;
+    /// an operation only leaves the stack once it has been undone,
+    /// so one that fails can be tried again
+    fn undo(&mut self) -> anyhow::Result<()> {
+        if let Some(mut operation) = self.undo_stack.back().cloned() {
+            self.apply_operation(&mut operation, true)?;
+            self.undo_stack.pop_back();
+            self.redo_stack.push(operation);
+        }
 
-                update boards
-                set updated_at = current_timestamp
-                where boards.id = NEW.board_id;
-            end
-    ",
-        )?;
         Ok(())
     }
 
-    fn get_board_metas(&self) -> anyhow::Result<Vec<BoardMeta>> {
-        let mut s = self.conn.prepare(
-            "
-        select
-            boards.id,
-            boards.name,
-            group_concat(statuses.name, '|' order by statuses.column_order),
-            boards.inserted_at,
-            boards.updated_at,
-            boards.viewed_at
-        from boards
-        inner join statuses
-            on statuses.board_id = boards.id
-        group by boards.id, boards.name
-        order by boards.viewed_at desc
-        ",
-        )?;
+    fn redo(&mut self) -> anyhow::Result<()> {
+        if let Some(mut operation) = self.redo_stack.last().cloned() {
+            self.apply_operation(&mut operation, false)?;
+            self.redo_stack.pop();
+            self.undo_stack.push_back(operation);
+        }
 
-        let boards_iter = s.query_map([], |row| {
-            let column_names: String = row.get(2)?;
-            let columns_names = column_names.split('|').map(|s| s.to_string()).collect();
+        Ok(())
+    }
 
-            Ok(BoardMeta {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                columns: columns_names,
-                inserted_at: row.get(3)?,
-                updated_at: row.get(4)?,
-                viewed_at: row.get(5)?,
-            })
-        })?;
+    /// does `operation`, or its opposite when `reverse` is true,
+    /// then reloads the board with the card it touched selected.
+    /// cards it restores may get new ids, which `operation` is updated with
+    fn apply_operation(&mut self, operation: &mut Operation, reverse: bool) -> anyhow::Result<()> {
+        let Some(board_id) = self.board.as_ref().map(|board| board.id) else {
+            return Ok(());
+        };
 
-        let mut boards = vec![];
+        let restored_cards = self.repo.apply_operation(board_id, operation, reverse)?;
 
-        for board in boards_iter {
-            boards.push(board?);
+        self.reload_board(operation.card_to_select(reverse))?;
+
+        for restored_card in &restored_cards {
+            // the original id may have been taken by a newer card
+            if restored_card.old_card_id != restored_card.card_id {
+                for operation in self.undo_stack.iter_mut().chain(self.redo_stack.iter_mut()) {
+                    operation.replace_card_id(restored_card.old_card_id, restored_card.card_id);
+                }
+            }
         }
 
-        Ok(boards)
+        self.put_restored_cards_back(&restored_cards)?;
+
+        Ok(())
     }
 
-    fn load_board(&mut self, board_id: BoardId) -> anyhow::Result<Board> {
-        let tx = self
-            .conn
-            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+    /// restored cards come back at the top of their columns.
+    /// move them to where they were when they were deleted,
+    /// in the order they were restored, so each index means what it did then.
+    /// when the board is filtered, where they were is ambiguous, so they stay on top
+    fn put_restored_cards_back(&mut self, restored_cards: &[RestoredCard]) -> anyhow::Result<()> {
+        let Some(last_restored) = restored_cards.last() else {
+            return Ok(());
+        };
 
-        let board_name = {
-            let mut board_s = tx.prepare(
-                "
-        select
-            name
-        from boards
-        where id = ?
-        ",
-            )?;
+        if self.tag_filter.is_none()
+            && let Some(board) = &mut self.board
+            && board.sort_mode == SortMode::Manual
+        {
+            let mut taken = vec![];
 
-            let board_name: String = board_s.query_one([board_id], |row| row.get(0))?;
+            for restored_card in restored_cards {
+                if let Some(column) = board
+                    .columns
+                    .iter_mut()
+                    .find(|column| column.name == restored_card.column)
+                    && let Some(position) = column
+                        .cards
+                        .iter()
+                        .position(|card| card.id == restored_card.card_id)
+                {
+                    taken.push((column.cards.remove(position), restored_card));
+                }
+            }
 
-            board_name
-        };
+            let mut changed_columns = vec![];
 
-        tx.execute(
-            "
-        update boards
-        set viewed_at = current_timestamp
-        where id = ?
-        ",
-            [board_id],
-        )?;
+            for (card, restored_card) in taken {
+                if let Some(column) = board
+                    .columns
+                    .iter_mut()
+                    .find(|column| column.name == restored_card.column)
+                {
+                    let index = min(restored_card.index, column.cards.len());
+                    column.cards.insert(index, card);
+
+                    if !changed_columns.contains(&restored_card.column) {
+                        changed_columns.push(restored_card.column.clone());
+                    }
+                }
+            }
 
-        tx.commit()?;
+            let rankings = board
+                .columns
+                .iter()
+                .filter(|column| changed_columns.contains(&column.name))
+                .map(|column| column.cards.iter().map(|card| card.id).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
 
-        let columns = self.get_cards_for_board(board_id)?;
+            for card_ids in rankings {
+                self.repo.rank_cards(&card_ids)?;
+            }
+        }
 
-        Ok(Board {
-            id: board_id,
-            name: board_name,
-            columns,
-        })
+        self.select_card(Some(last_restored.card_id));
+
+        Ok(())
     }
 
-    fn get_cards_for_board(&self, board_id: BoardId) -> anyhow::Result<Vec<Column>> {
-        let mut statuses_s = self.conn.prepare(
-            "
-            select
-                name
-            from statuses
-            where board_id = ?
-            order by column_order asc
-            ",
-        )?;
+    /// keeps a sorted board in order after one of its cards changes.
+    /// manually sorted boards stay the way they were left
+    fn resort_board(&mut self) -> anyhow::Result<()> {
+        if let Some(board) = &self.board
+            && board.sort_mode != SortMode::Manual
+        {
+            self.reload_board(self.selected_card_id())?;
+        }
 
-        let statuses_iter = statuses_s.query_map([board_id], |row| row.get(0))?;
+        Ok(())
+    }
 
-        let mut columns = vec![];
+    fn cycle_sort_mode(&mut self) -> anyhow::Result<()> {
+        let select = self.selected_card_id();
 
-        for status in statuses_iter {
-            let status: String = status?;
-            let cards = self.cards_for_column(board_id, &status)?;
-            columns.push(Column {
-                name: status,
-                cards,
-            })
+        if let Some(board) = &mut self.board {
+            board.sort_mode = board.sort_mode.next();
+            self.repo.set_board_sort_mode(board.id, board.sort_mode)?;
+            self.reload_board(select)?;
         }
 
-        Ok(columns)
+        Ok(())
     }
 
-    fn insert_card(&mut self, board_id: BoardId, title: &str, body: &str) -> anyhow::Result<Card> {
-        let tx = self
-            .conn
-            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
-
-        let status_id: StatusId = tx.query_one(
-            "
-        select
-            id
-        from statuses
-        where board_id = ?
-        order by column_order asc
-        limit 1
-        ",
-            [board_id],
-            |row| row.get(0),
-        )?;
+    /// another kk, or a script, changed the database, so what is on screen is read again.
+    /// the selected card stays selected wherever it ends up, if it is still on the board
+    fn reload_changed_data(&mut self) -> anyhow::Result<()> {
+        self.data_version = self.repo.data_version()?;
+
+        if matches!(
+            self.mode,
+            Mode::ViewingBoards | Mode::ConfirmBoardDeletion | Mode::ChoosingColumnDestination
+        ) {
+            self.refresh_board_metas()?;
+            self.clamp_selected_board_index();
+            return Ok(());
+        }
 
-        let external_card_id: ExternalCardId = tx.query_one(
-            "
-        select
-            card_id
-        from boards
-        where id = ?
-        ",
-            [board_id],
-            |row| row.get(0),
-        )?;
+        if let Some(board) = &self.board {
+            let select = self.selected_card_id();
 
-        let card = tx.query_row(
-            "
-        insert into cards (external_id, board_id, status_id, title, body) values (?, ?, ?, ?, ?)
-        returning id, inserted_at, updated_at;
-        ",
-            params![external_card_id, board_id, status_id, title, body],
-            |row| {
-                Ok(Card {
-                    id: row.get(0)?,
-                    external_id: external_card_id,
-                    title: title.to_string(),
-                    body: body.to_string(),
-                    inserted_at: row.get(1)?,
-                    updated_at: row.get(2)?,
-                })
-            },
-        )?;
+            let mut board = match self.repo.get_board(board.id) {
+                Ok(board) => board,
+                Err(e)
+                    if matches!(e.downcast_ref(), Some(rusqlite::Error::QueryReturnedNoRows)) =>
+                {
+                    self.switch_to_viewing_boards_mode()?;
+                    return Err(anyhow!("this board was deleted somewhere else"));
+                }
+                Err(e) => return Err(e),
+            };
 
-        tx.execute(
-            "
-            update boards
-            set card_id = card_id + 1
-            where id = ?;
-        ",
-            [board_id],
-        )?;
+            if let Some(tag) = &self.tag_filter {
+                board.retain_tagged(tag);
+            }
 
-        tx.commit()?;
+            let on_board = |card_id: &CardId| {
+                board
+                    .columns
+                    .iter()
+                    .any(|column| column.cards.iter().any(|card| card.id == *card_id))
+            };
 
-        Ok(card)
-    }
-
-    fn cards_for_column(&self, board_id: BoardId, column_name: &str) -> anyhow::Result<Vec<Card>> {
-        let mut s = self.conn.prepare(
-            "
-            select
-                cards.id,
-                cards.external_id,
-                cards.title,
-                cards.body,
-                cards.inserted_at,
-                cards.updated_at
-            from cards
-            inner join statuses
-                on statuses.id = cards.status_id
-                and statuses.board_id = ?1
-                and statuses.name = ?2
-            order by cards.id desc;
-            ",
-        )?;
-
-        let cards_iter = s.query_map(params![board_id, column_name], |row| {
-            Ok(Card {
-                id: row.get(0)?,
-                external_id: row.get(1)?,
-                title: row.get(2)?,
-                body: row.get(3)?,
-                inserted_at: row.get(4)?,
-                updated_at: row.get(5)?,
-            })
-        })?;
+            // these modes are about to act on the marked cards, or the selected card,
+            // and must not move on to another one when those are gone
+            let targets_gone = if !matches!(
+                self.mode,
+                Mode::ConfirmCardDeletion | Mode::MovingCard | Mode::SendingCard
+            ) {
+                None
+            } else if self.marked.is_empty() {
+                select
+                    .is_some_and(|card_id| !on_board(&card_id))
+                    .then_some("the card was deleted somewhere else")
+            } else {
+                (!self.marked.iter().any(on_board))
+                    .then_some("the marked cards were deleted somewhere else")
+            };
 
-        let mut cards = vec![];
+            self.marked.retain(on_board);
 
-        for card in cards_iter {
-            cards.push(card?);
+            self.board = Some(board);
+            self.due_thresholds = self.repo.get_due_thresholds()?;
+            self.select_card(select);
+
+            if let Some(e) = targets_gone {
+                self.send_card = None;
+                self.confirmation_state = ConfirmationState::No;
+                self.mode = Mode::ViewingBoard;
+                return Err(anyhow!(e));
+            }
         }
 
-        Ok(cards)
+        Ok(())
     }
 
-    fn update_card(&mut self, card_id: CardId, title: &str, body: &str) -> anyhow::Result<String> {
-        self.conn.execute(
-            "
-        update cards
-        set
-            title = ?2,
-            body = ?3
-        where id = ?1
-        ",
-            params![card_id, title, body],
-        )?;
+    /// re-reads the current board from the database,
+    /// selecting `select` if it is still on the board
+    fn reload_board(&mut self, select: Option<CardId>) -> anyhow::Result<()> {
+        if let Some(board) = &mut self.board {
+            board.columns = self.repo.get_cards_for_board(board.id)?;
 
-        let mut updated_at_s = self.conn.prepare(
-            "
-        select
-            updated_at
-        from cards
-        where id = ?
-        ",
-        )?;
+            if let Some(tag) = &self.tag_filter {
+                board.retain_tagged(tag);
+            }
+        }
 
-        let updated_at = updated_at_s.query_one([card_id], |row| row.get(0))?;
+        self.due_thresholds = self.repo.get_due_thresholds()?;
 
-        Ok(updated_at)
+        self.select_card(select);
+
+        Ok(())
     }
 
-    fn set_card_status(
-        &self,
-        board_id: BoardId,
-        card_id: CardId,
-        column_name: &str,
-    ) -> anyhow::Result<()> {
-        self.conn.execute(
-            "
-        update cards
-        set status_id = (
-            select
-                id
-            from statuses
-            where board_id = ?1
-            and name = ?2
-        )
-        where id = ?3
-        ",
-            params![board_id, column_name, card_id],
-        )?;
+    /// selects `select` if it is on the board.
+    /// otherwise the selection stays where it was, as far as it can
+    fn select_card(&mut self, select: Option<CardId>) {
+        if let Some(board) = &self.board {
+            let position = select.and_then(|card_id| {
+                board
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .find_map(|(column_index, column)| {
+                        column
+                            .cards
+                            .iter()
+                            .position(|card| card.id == card_id)
+                            .map(|card_index| (column_index, card_index))
+                    })
+            });
 
-        Ok(())
+            if let Some((column_index, card_index)) = position {
+                self.selected.column_index = Some(column_index);
+                self.selected.card_index = Some(card_index);
+            } else {
+                let column_index = min(
+                    self.selected.column_index.unwrap_or(0),
+                    board.columns.len().saturating_sub(1),
+                );
+
+                self.selected.column_index = Some(column_index);
+                self.selected.card_index = board.columns.get(column_index).and_then(|column| {
+                    if column.cards.is_empty() {
+                        None
+                    } else {
+                        Some(min(
+                            self.selected.card_index.unwrap_or(0),
+                            column.cards.len() - 1,
+                        ))
+                    }
+                });
+            }
+        }
     }
 
-    fn create_board(&mut self, name: &str, column_names: &[&str]) -> anyhow::Result<i64> {
-        let tx = self
-            .conn
-            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+    /// switches to viewing `board_id`, from anywhere, with `select` selected
+    fn open_board(&mut self, board_id: BoardId, select: Option<CardId>) -> anyhow::Result<()> {
+        if self.board.as_ref().map(|board| board.id) != Some(board_id) {
+            self.undo_stack.clear();
+            self.redo_stack.clear();
+            self.tag_filter = None;
+            self.board_filter = None;
+            self.marked.clear();
+        }
 
-        let board_id = {
-            let mut board_s = tx.prepare(
-                "
-                insert into boards (name) values (?)
-                returning id;
-                ",
-            )?;
+        let mut board = self.repo.load_board(board_id)?;
 
-            let mut columns_s = tx.prepare(
-                "
-                insert into statuses (name, column_order, board_id)
-                values (?, ?, ?);
-                ",
-            )?;
+        if let Some(tag) = &self.tag_filter {
+            board.retain_tagged(tag);
+        }
 
-            let board_id: i64 = board_s.query_row([name], |row| row.get(0))?;
+        self.board = Some(board);
+        self.board_metas = vec![];
+        self.selected.board_index = None;
+        self.selected.column_index = Some(0);
+        self.selected.card_index = Some(0);
+        self.due_thresholds = self.repo.get_due_thresholds()?;
+        self.select_card(select);
+        self.mode = Mode::ViewingBoard;
 
-            for (column_order, column_name) in column_names.iter().enumerate() {
-                columns_s.execute(params![
-                    column_name,
-                    i64::try_from(column_order).expect("must be less than i64::MAX columns"),
-                    board_id
-                ])?;
+        Ok(())
+    }
+
+    fn choose_tag_filter(&mut self) -> anyhow::Result<()> {
+        if let Some(board) = &self.board {
+            let tags = self.repo.get_board_tags(board.id)?;
+
+            if tags.is_empty() {
+                return Err(anyhow!("no cards on this board have tags"));
             }
 
-            board_id
-        };
+            let selected = self
+                .tag_filter
+                .as_ref()
+                .and_then(|tag_filter| tags.iter().position(|tag| tag == tag_filter))
+                .unwrap_or(0);
 
-        tx.commit()?;
+            self.tag_picker = Some(TagPicker { tags, selected });
+            self.mode = Mode::ChoosingTagFilter;
+        }
 
-        Ok(board_id)
+        Ok(())
     }
 
-    fn update_board_columns_order(
-        &mut self,
-        board_id: BoardId,
-        board_name: &str,
-        column_names: Vec<&str>,
-    ) -> anyhow::Result<Board> {
-        let tx = self
-            .conn
-            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+    fn set_tag_filter(&mut self, tag: Option<String>) -> anyhow::Result<()> {
+        self.tag_filter = tag;
+        self.reload_board(self.selected_card_id())
+    }
+
+    fn start_export(&mut self) {
+        if self.board.is_some() {
+            self.export_format = Some(0);
+            self.mode = Mode::ExportingBoard;
+        }
+    }
 
+    /// writes the whole board, whatever is filtered out, to a new file in `export_dir`
+    fn export_board(&mut self) -> anyhow::Result<()> {
+        self.mode = Mode::ViewingBoard;
+
+        if let Some(board) = &self.board
+            && let Some(format_index) = self.export_format.take()
         {
-            let mut change_column_order_s = tx.prepare(
-                "
-                insert into statuses (name, column_order, board_id)
-                values (?, ?, ?)
-                on conflict(name, board_id) do update set column_order = excluded.column_order;
-                ",
-            )?;
+            let format = ExportFormat::ALL[format_index];
+            let board = self.repo.get_board(board.id)?;
+
+            let file_stem = board
+                .name
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect::<String>();
 
-            let mut change_board_name_s = tx.prepare(
-                "
-            update boards
-            set name = ?
-            where id = ?
-            ",
-            )?;
+            let export_dir = match &self.export_dir {
+                Some(export_dir) => export_dir.clone(),
+                None => std::env::current_dir()?,
+            };
 
-            for (i, column_name) in column_names.iter().enumerate() {
-                change_column_order_s.execute(params![
-                    column_name,
-                    i64::try_from(i).unwrap(),
-                    board_id
-                ])?;
+            // earlier exports are kept
+            let mut path = export_dir.join(format!("{}.{}", file_stem, format.extension()));
+            let mut n = 1;
+            while path.exists() {
+                n += 1;
+                path = export_dir.join(format!("{}-{}.{}", file_stem, n, format.extension()));
             }
 
-            change_board_name_s.execute(params![board_name, board_id])?;
+            let mut f = std::io::BufWriter::new(std::fs::File::create_new(&path)?);
+            board.export(format, &mut f)?;
+            f.flush()?;
+
+            self.notice = Some(format!("exported to {}", path.display()));
         }
 
-        tx.commit()?;
+        Ok(())
+    }
 
-        self.load_board(board_id)
+    fn start_board_filter(&mut self) {
+        self.board_filter = Some(String::new());
+        self.mode = Mode::FilteringBoard;
     }
 
-    fn load_most_recently_viewed_board(&self) -> anyhow::Result<Option<Board>> {
-        let mut board_s = self.conn.prepare(
-            "
-        select
-            id,
-            name
-        from boards
-        order by viewed_at desc
-        limit 1
-        ",
-        )?;
+    /// filters as the pattern is typed, moving the selection
+    /// to the next match if the selected card stops matching
+    fn edit_board_filter(&mut self, edit: impl FnOnce(&mut String)) {
+        if let Some(pattern) = &mut self.board_filter {
+            edit(pattern);
+        }
 
-        let board_meta: Option<(BoardId, String)> = board_s
-            .query_one([], |row| Ok((row.get(0)?, row.get(1)?)))
-            .optional()?;
+        if let Some(pattern) = &self.board_filter
+            && !self
+                .selected_card()
+                .is_some_and(|card| card.matches(pattern))
+        {
+            self.jump_to_match(true, true);
+        }
+    }
 
-        if let Some((board_id, board_name)) = board_meta {
-            let columns = self.get_cards_for_board(board_id)?;
+    /// an empty pattern filters nothing, so it goes away
+    fn finish_board_filter(&mut self) {
+        if self
+            .board_filter
+            .as_ref()
+            .is_some_and(|pattern| pattern.is_empty())
+        {
+            self.board_filter = None;
+        }
 
-            Ok(Some(Board {
-                id: board_id,
-                name: board_name,
-                columns,
-            }))
+        self.mode = Mode::ViewingBoard;
+    }
+
+    fn clear_board_filter(&mut self) {
+        self.board_filter = None;
+        self.mode = Mode::ViewingBoard;
+    }
+
+    /// selects the next (or previous) matching card, reading the columns
+    /// left to right and top to bottom and wrapping around at the ends.
+    /// returns whether there was a match to select
+    fn jump_to_match(&mut self, forward: bool, include_selected: bool) -> bool {
+        if let Some(board) = &self.board
+            && let Some(pattern) = &self.board_filter
+        {
+            let matches = board
+                .columns
+                .iter()
+                .enumerate()
+                .flat_map(|(column_index, column)| {
+                    column
+                        .cards
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, card)| card.matches(pattern))
+                        .map(move |(card_index, _)| (column_index, card_index))
+                })
+                .collect::<Vec<_>>();
+
+            let selected = (
+                self.selected.column_index.unwrap_or(0),
+                self.selected.card_index.unwrap_or(0),
+            );
+
+            let next = if forward {
+                matches
+                    .iter()
+                    .find(|m| **m > selected || (include_selected && **m == selected))
+                    .or(matches.first())
+            } else {
+                matches
+                    .iter()
+                    .rev()
+                    .find(|m| **m < selected || (include_selected && **m == selected))
+                    .or(matches.last())
+            };
+
+            if let Some(&(column_index, card_index)) = next {
+                self.selected.column_index = Some(column_index);
+                self.selected.card_index = Some(card_index);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn jump_to_next_match(&mut self, forward: bool) -> anyhow::Result<()> {
+        if self.jump_to_match(forward, false) {
+            Ok(())
         } else {
-            Ok(None)
+            Err(anyhow!(
+                "no cards match {}",
+                self.board_filter.as_deref().unwrap_or_default()
+            ))
         }
     }
 
-    fn delete_card(&self, card_id: CardId) -> anyhow::Result<()> {
-        let mut s = self.conn.prepare(
-            "
-        delete from cards
-        where id = ?",
-        )?;
+    fn start_jump_to_card(&mut self) {
+        self.card_number = Some(String::new());
+        self.mode = Mode::JumpingToCard;
+    }
 
-        s.execute([card_id])?;
+    /// selects the card with the typed number,
+    /// clearing the tag filter if it hides the card
+    fn jump_to_card(&mut self) -> anyhow::Result<()> {
+        self.mode = Mode::ViewingBoard;
+
+        if let Some(card_number) = self.card_number.take()
+            && !card_number.is_empty()
+            && let Some(board) = &self.board
+        {
+            let external_id = ExternalCardId(card_number.parse()?);
+
+            let (card, _column) = self
+                .repo
+                .find_card_by_external_id(board.id, external_id)?
+                .ok_or_else(|| anyhow!("there is no card {} on this board", external_id))?;
+
+            if let Some(tag) = &self.tag_filter
+                && !card.tags.contains(tag)
+            {
+                self.set_tag_filter(None)?;
+            }
+
+            self.select_card(Some(card.id));
+        }
 
         Ok(())
     }
-}
-
-#[derive(Debug)]
-struct Board {
-    id: BoardId,
-    name: String,
-    columns: Vec<Column>,
-}
 
-#[derive(Debug, Default, PartialEq)]
-struct SelectedState {
-    board_index: Option<usize>,
-    column_index: Option<usize>,
-    card_index: Option<usize>,
-}
+    fn choose_send_destination(&mut self) -> anyhow::Result<()> {
+        if let Some(board) = &self.board
+            && self.selected_card().is_some()
+        {
+            let board_id = board.id;
 
-enum Event {
-    KeyEvent(crossterm::event::KeyEvent),
-    InternalEvent(InternalEvent),
-}
+            let boards = self
+                .repo
+                .get_board_metas()?
+                .into_iter()
+                .filter(|board_meta| board_meta.id != board_id)
+                .collect::<Vec<_>>();
 
-enum InternalEvent {
-    ClearError,
-}
+            if boards.is_empty() {
+                return Err(anyhow!("there are no other boards to send cards to"));
+            }
 
-#[derive(Debug)]
-struct Column {
-    name: String,
-    cards: Vec<Card>,
-}
+            self.send_card = Some(SendCard {
+                boards,
+                board_index: 0,
+                column_index: None,
+            });
+            self.mode = Mode::SendingCard;
+        }
 
-impl Display for Column {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.name)
+        Ok(())
     }
-}
 
-#[derive(Debug)]
-struct Card {
-    id: CardId,
-    external_id: ExternalCardId,
-    title: String,
-    body: String,
-    inserted_at: String,
-    updated_at: String,
-}
+    /// the marked cards, or the selected card if none are marked
+    fn send_selected_card(&mut self) -> anyhow::Result<()> {
+        let card_ids = if self.marked.is_empty() {
+            self.selected_card_id().into_iter().collect()
+        } else {
+            self.marked_card_ids()
+        };
 
-#[derive(Debug, Default, PartialEq, Eq)]
-enum RunningState {
-    #[default]
-    Running,
-    Done,
-}
+        self.mode = Mode::ViewingBoard;
 
-#[derive(Debug, Default, PartialEq)]
-enum Mode {
-    #[default]
-    ViewingBoard,
-    ViewingCardDetail,
-    MovingCard,
-    ViewingBoards,
-    ConfirmCardDeletion,
-}
+        if let Some(send_card) = self.send_card.take()
+            && let Some(board_meta) = send_card.boards.get(send_card.board_index)
+            && !card_ids.is_empty()
+        {
+            let column = send_card
+                .column_index
+                .and_then(|column_index| board_meta.columns.get(column_index))
+                .ok_or_else(|| anyhow!("{} has no columns to send cards to", board_meta.name))?;
+
+            self.repo
+                .send_cards_to_board(&card_ids, board_meta.id, column)?;
+
+            // undoing anything done to the cards here would reach onto the other board
+            for card_id in card_ids {
+                self.undo_stack
+                    .retain(|operation| !operation.involves(card_id));
+                self.redo_stack
+                    .retain(|operation| !operation.involves(card_id));
+            }
 
-#[derive(Debug, PartialEq)]
-enum Message {
-    NavigateLeft,
-    NavigateDown,
-    NavigateUp,
-    NavigateRight,
-    Quit,
-    NewCard,
-    MoveCardMode,
-    MoveCardLeft,
-    // MoveCardDown,
-    // MoveCardUp,
-    MoveCardRight,
-    EditCard,
-    ViewBoardMode,
-    ViewCardDetailMode,
-    SetError(Option<String>),
-    ViewBoardsMode,
-    EditBoard,
-    NewBoard,
-    DeleteCard,
-    ConfirmChoice,
-}
+            self.marked.clear();
 
-fn run_editor<B>(terminal: &mut Terminal<B>, template_text: &str) -> anyhow::Result<String>
-where
-    B: Backend,
-    B::Error: Send + Sync + 'static,
-{
-    std::io::stdout().execute(crossterm::terminal::LeaveAlternateScreen)?;
-    crossterm::terminal::disable_raw_mode()?;
+            self.reload_board(None)?;
+        }
 
-    let path = {
-        let tempfile = tempfile::Builder::new();
-        let mut f = tempfile.tempfile()?;
-        f.write_all(template_text.as_bytes())?;
-        f.into_temp_path()
-    };
+        Ok(())
+    }
 
-    let editor = std::env::var("EDITOR")?;
+    /// where to go back to after leaving a popup on the board
+    fn board_mode(&self) -> Mode {
+        if self.marked.is_empty() {
+            Mode::ViewingBoard
+        } else {
+            Mode::SelectingCards
+        }
+    }
 
-    Command::new(editor).arg(&path).status()?;
+    fn start_selecting_cards(&mut self) {
+        if self.selected_card().is_some() {
+            self.marked.clear();
+            self.toggle_mark();
+            self.mode = Mode::SelectingCards;
+        }
+    }
 
-    let edited_text = std::fs::read_to_string(&path)?;
+    fn stop_selecting_cards(&mut self) {
+        self.marked.clear();
+        self.mode = Mode::ViewingBoard;
+    }
 
-    path.close()?;
+    fn toggle_mark(&mut self) {
+        if let Some(card_id) = self.selected_card_id() {
+            if let Some(i) = self.marked.iter().position(|id| *id == card_id) {
+                self.marked.remove(i);
+            } else {
+                self.marked.push(card_id);
+            }
+        }
+    }
 
-    std::io::stdout().execute(crossterm::terminal::EnterAlternateScreen)?;
-    crossterm::terminal::enable_raw_mode()?;
-    terminal.clear()?;
+    fn ensure_marked(&self) -> anyhow::Result<()> {
+        if self.marked.is_empty() {
+            Err(anyhow!("no cards are marked, press v to mark one"))
+        } else {
+            Ok(())
+        }
+    }
 
-    Ok(edited_text)
-}
+    /// the column and card indexes of the marked cards, in display order
+    fn marked_positions(&self) -> Vec<(usize, usize)> {
+        if let Some(board) = &self.board {
+            board
+                .columns
+                .iter()
+                .enumerate()
+                .flat_map(|(column_index, column)| {
+                    column
+                        .cards
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, card)| self.marked.contains(&card.id))
+                        .map(move |(card_index, _)| (column_index, card_index))
+                })
+                .collect()
+        } else {
+            vec![]
+        }
+    }
 
-fn view(model: &mut Model, frame: &mut ratatui::Frame) {
-    match model.mode {
-        Mode::ViewingBoard
-        | Mode::ViewingCardDetail
-        | Mode::MovingCard
-        | Mode::ConfirmCardDeletion => view_board(model, frame),
-        Mode::ViewingBoards => view_boards(model, frame),
+    fn marked_card_ids(&self) -> Vec<CardId> {
+        if let Some(board) = &self.board {
+            self.marked_positions()
+                .into_iter()
+                .map(|(column_index, card_index)| board.columns[column_index].cards[card_index].id)
+                .collect()
+        } else {
+            vec![]
+        }
     }
-}
 
-fn view_boards(model: &mut Model, frame: &mut ratatui::Frame<'_>) {
-    let [title_layout, boards_layout, modeline_layout] = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Max(1), Constraint::Min(1), Constraint::Max(3)])
-        .areas(frame.area());
+    fn move_marked_cards(&mut self, column_index: usize) -> anyhow::Result<()> {
+        if let Some(board) = &self.board
+            && let Some(to) = board.columns.get(column_index)
+        {
+            let board_id = board.id;
+            let mut card_ids = vec![];
+            let mut moves = vec![];
+
+            for (from_index, card_index) in self.marked_positions() {
+                if from_index != column_index {
+                    let card_id = board.columns[from_index].cards[card_index].id;
+
+                    card_ids.push(card_id);
+                    moves.push(Operation::Move {
+                        card_id,
+                        from: board.columns[from_index].name.clone(),
+                        to: to.name.clone(),
+                    });
+                }
+            }
 
-    let mut state = ListState::default().with_selected(model.selected.board_index);
+            if !card_ids.is_empty() {
+                self.repo
+                    .set_cards_status(board_id, &card_ids, &to.name.clone())?;
+                self.record_operation(Operation::Batch(moves));
+            }
 
-    let list_items = model
-        .board_metas
-        .iter()
-        .map(|board| {
-            ListItem::new(format!(
-                "{:<30}{:<30}{:<30}{:<30}",
-                &*board.name, &*board.updated_at, &*board.viewed_at, &*board.inserted_at
-            ))
-        })
-        .collect::<Vec<_>>();
+            self.stop_selecting_cards();
+            self.reload_board(card_ids.first().copied())?;
+        }
 
-    let list = List::new(list_items)
-        .highlight_symbol("> ")
-        .highlight_style(Style::default().fg(model.highlight_color))
-        .block(
-            Block::new()
-                .border_type(ratatui::widgets::BorderType::Rounded)
-                .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
-                .border_style(Style::default().fg(Color::Black))
-                .title(
-                    "──name──────────────────────────last updated──────────────────last viewed───────────────────created",
-                ),
-        );
+        Ok(())
+    }
 
-    frame.render_widget(Paragraph::new("Boards"), title_layout);
-    frame.render_stateful_widget(list, boards_layout, &mut state);
+    fn delete_marked_cards(&mut self) -> anyhow::Result<()> {
+        if let Some(board) = &self.board {
+            let mut removed_from_column = vec![0; board.columns.len()];
+            let mut card_ids = vec![];
+            let mut deletes = vec![];
+
+            for (column_index, card_index) in self.marked_positions() {
+                let card = board.columns[column_index].cards[card_index].clone();
+
+                card_ids.push(card.id);
+                // where it is once the cards above it are gone,
+                // so undoing in reverse order puts everything back where it was
+                deletes.push(Operation::Delete {
+                    card,
+                    column: board.columns[column_index].name.clone(),
+                    index: card_index - removed_from_column[column_index],
+                });
+
+                removed_from_column[column_index] += 1;
+            }
 
-    let modeline_block = Block::new()
-        .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
-        .title(
-            Line::from(match model.mode {
-                Mode::ViewingBoards => "VIEWING BOARDS",
-                _ => unreachable!(),
-            })
-            .left_aligned(),
-        );
+            self.repo.delete_cards(&card_ids)?;
+            self.record_operation(Operation::Batch(deletes));
 
-    let modeline_text = {
-        let mut modeline_text = String::new();
+            self.stop_selecting_cards();
+            self.reload_board(None)?;
+        }
 
-        if let Some(e) = &model.error {
-            modeline_text.push_str(" - Error: ");
-            modeline_text.push_str(&e.replace("\n", " "));
-        } else {
-            let formatted = match model.mode {
-                Mode::ViewingBoards => [
-                    ("[j/down]", "down"),
-                    ("[k/up]", "up"),
-                    ("[enter]", "view board"),
-                    ("[n]", "new board"),
-                    ("[e]", "edit board"),
-                    ("[q]", "quit"),
-                ]
-                .iter()
-                .map(|(k, action)| format!("{} - {}", k, action))
-                .collect::<Vec<_>>(),
-                _ => unreachable!(),
-            };
+        Ok(())
+    }
 
-            modeline_text.push_str(&formatted.join(" │ "));
+    fn tag_marked_cards(&mut self, tag: &str) -> anyhow::Result<()> {
+        let tag = tag.trim();
+
+        if tag.is_empty() {
+            return Err(anyhow!("type a tag to add to the marked cards"));
         }
 
-        modeline_text
-    };
+        if tag.contains(',') {
+            return Err(anyhow!("tags can't have commas in them"));
+        }
 
-    let modeline = Paragraph::new(modeline_text).block(modeline_block);
+        if let Some(board) = &self.board {
+            let board_id = board.id;
+            let mut texts = vec![];
+            let mut edits = vec![];
+
+            for (column_index, card_index) in self.marked_positions() {
+                let card = &board.columns[column_index].cards[card_index];
+
+                if !card.tags.iter().any(|t| t == tag) {
+                    let before = card.text();
+                    let mut after = card.text();
+                    after.tags.push(tag.to_string());
+                    after.tags.sort();
+
+                    texts.push((card.id, after.clone()));
+                    edits.push(Operation::Edit {
+                        card_id: card.id,
+                        before,
+                        after,
+                    });
+                }
+            }
 
-    frame.render_widget(modeline, modeline_layout);
-}
+            if !texts.is_empty() {
+                self.repo.edit_cards(board_id, &texts)?;
+                self.record_operation(Operation::Batch(edits));
+            }
+        }
 
-fn view_board(model: &mut Model, frame: &mut ratatui::Frame) {
-    if let Some(board) = &model.board {
-        let [columns_layout, modeline_layout] = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Max(3)])
-            .areas(frame.area());
+        self.stop_selecting_cards();
+        self.reload_board(self.selected_card_id())?;
 
-        let columns_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(std::iter::repeat_n(
-                Constraint::Ratio(1, board.columns.len().try_into().unwrap()),
-                board.columns.len(),
-            ))
-            .split(columns_layout);
+        Ok(())
+    }
 
-        for (i, column) in board.columns.iter().enumerate() {
-            let column_layout = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Max(1), Constraint::Min(5)])
-                .split(columns_layout[i]);
+    fn view_agenda(&mut self) -> anyhow::Result<()> {
+        self.due_thresholds = self.repo.get_due_thresholds()?;
+        self.agenda = Some(Agenda {
+            items: self.repo.get_agenda()?,
+            selected: 0,
+        });
+        self.mode = Mode::ViewingAgenda;
+        Ok(())
+    }
 
-            frame.render_widget(Paragraph::new(&*column.name), column_layout[0]);
+    /// does nothing on an empty agenda.
+    /// the agenda stays open if the board can't be opened
+    fn open_selected_agenda_item(&mut self) -> anyhow::Result<()> {
+        if let Some(agenda) = &self.agenda
+            && let Some(item) = agenda.items.get(agenda.selected)
+        {
+            let (board_id, card_id) = (item.board_id, item.card.id);
+            self.open_board(board_id, Some(card_id))?;
+            self.agenda = None;
+        }
 
-            let mut state = if i == model.selected.column_index.unwrap() {
-                ListState::default().with_selected(model.selected.card_index)
-            } else {
-                ListState::default().with_selected(None)
-            };
+        Ok(())
+    }
 
-            let list_items = column
-                .cards
-                .iter()
-                .map(|card| {
-                    let s = format!("{} {}", card.external_id, card.title);
-                    ListItem::new(Text::from(textwrap::fill(
-                        &s,
-                        (column_layout[1].width as usize).saturating_sub(4),
-                    )))
-                })
-                .collect::<Vec<_>>();
+    /// back to wherever the agenda was opened from
+    fn close_agenda(&mut self) -> anyhow::Result<()> {
+        self.agenda = None;
 
-            let list = List::new(list_items)
-                .highlight_symbol("> ")
-                .highlight_style(Style::default().fg(model.highlight_color))
-                .block(
-                    Block::new()
-                        .border_type(ratatui::widgets::BorderType::Rounded)
-                        .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
-                        .border_style(Style::default().fg(Color::Black)),
-                );
+        if self.board.is_some() {
+            self.mode = Mode::ViewingBoard;
+        } else {
+            self.switch_to_viewing_boards_mode()?;
+        }
 
-            frame.render_stateful_widget(list, column_layout[1], &mut state);
+        Ok(())
+    }
+
+    fn start_search(&mut self) {
+        self.search = Some(Search::default());
+        self.mode = Mode::Searching;
+    }
+
+    /// searches again on every keystroke
+    fn edit_search_query(&mut self, edit: impl FnOnce(&mut String)) -> anyhow::Result<()> {
+        if let Some(search) = &mut self.search {
+            edit(&mut search.query);
+            search.results = self.repo.search_cards(&search.query)?;
+            search.selected = 0;
         }
 
-        if model.mode == Mode::ViewingCardDetail
-            && let Some(card) = model.selected_card()
+        Ok(())
+    }
+
+    fn open_selected_search_result(&mut self) -> anyhow::Result<()> {
+        if let Some(search) = self.search.take()
+            && let Some(result) = search.results.get(search.selected)
         {
-            let block = Block::bordered()
-                .title(Line::from(card.external_id.to_string()).left_aligned())
-                .title(
-                    Line::from(format!(
-                        "created {}, updated {}",
-                        card.inserted_at, card.updated_at
-                    ))
-                    .right_aligned(),
-                )
-                .padding(Padding::uniform(1));
+            // don't open the board on a card the tag filter hides
+            if let Some(tag) = &self.tag_filter
+                && !result.card.tags.contains(tag)
+            {
+                self.tag_filter = None;
+            }
 
-            let title_style = Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+            self.open_board(result.board_id, Some(result.card.id))?;
+        }
 
-            let area = popup_area(frame.area(), 60, 50);
+        self.mode = Mode::ViewingBoard;
 
-            let wrapped = textwrap::wrap(&card.body, area.width as usize);
+        Ok(())
+    }
 
-            let body = wrapped.iter().map(|line| Line::from(line.to_string()));
+    fn close_search(&mut self) {
+        self.search = None;
+        self.mode = Mode::ViewingBoard;
+    }
 
-            let mut lines = vec![Line::styled(&*card.title, title_style)];
-            lines.push(Line::from("\n\n"));
-            lines.extend(body);
+    fn view_card_history(&mut self) -> anyhow::Result<()> {
+        if let Some(card) = self.selected_card() {
+            self.activity = Some(Activity {
+                title: format!("{} {}", card.external_id, card.title),
+                events: self.repo.get_card_events(card.id)?,
+                selected: 0,
+                previous_mode: self.mode,
+            });
+            self.mode = Mode::ViewingActivity;
+        }
 
-            let paragraph = Paragraph::new(lines).block(block);
+        Ok(())
+    }
 
-            frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
-            frame.render_widget(paragraph, area);
+    fn view_board_activity(&mut self) -> anyhow::Result<()> {
+        if let Some(board) = &self.board {
+            self.activity = Some(Activity {
+                title: board.name.clone(),
+                events: self.repo.get_board_events(board.id)?,
+                selected: 0,
+                previous_mode: self.mode,
+            });
+            self.mode = Mode::ViewingActivity;
+        }
 
-            /// helper function to create a centered rect using up certain percentage of the available rect `r`
-            fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
-                let vertical =
-                    Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
-                let horizontal =
-                    Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
-                let [area] = vertical.areas(area);
-                let [area] = horizontal.areas(area);
-                area
-            }
+        Ok(())
+    }
+
+    /// back to the board or card the activity was opened from
+    fn close_activity(&mut self) {
+        if let Some(activity) = self.activity.take() {
+            self.mode = activity.previous_mode;
         }
+    }
+}
 
-        if model.mode == Mode::ConfirmCardDeletion
-            && let Some(card) = model.selected_card()
-        {
-            let title_style = Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+#[derive(Debug)]
+struct Repo {
+    conn: Connection,
+}
 
-            let block = Block::bordered()
-                .title(format!("Delete {}", &card.title))
-                .padding(Padding::uniform(1))
-                .title_style(title_style);
+impl Repo {
+    fn new(override_database_path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let database_path = if let Some(database_path) = override_database_path {
+            database_path.to_owned()
+        } else {
+            let mut database_path = directories::ProjectDirs::from("", "", "kk")
+            .expect("unable to find home directory. if you like, you can provide a database path directly by passing the -d option.")
+            .data_local_dir()
+            .to_path_buf();
 
-            let area = popup_area(frame.area(), 30, 20);
+            std::fs::create_dir_all(&database_path)?;
 
-            let [left, right] = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
-                .areas(area);
+            database_path.push("kk.db");
 
-            let left_text = {
-                let text = if model.confirmation_state == ConfirmationState::Yes {
-                    "[ Delete ]"
-                } else {
-                    "Delete"
-                };
+            database_path
+        };
 
-                Text::from(text).centered()
-            };
+        let mut conn = rusqlite::Connection::open(database_path)?;
 
-            let right_text = {
-                let text = if model.confirmation_state == ConfirmationState::No {
-                    "[ Cancel ]"
-                } else {
-                    "Cancel"
-                };
+        conn.pragma_update(None, "foreign_keys", "on")?;
+        conn.pragma_update(None, "synchronous", "extra")?;
 
-                Text::from(text).centered()
-            };
+        #[cfg(target_os = "macos")]
+        conn.pragma_update(None, "fullfsync", true)?;
 
-            // Text::from("Delete").centered();
-            // let right_text = Text::from("Cancel").centered();
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
 
-            let left = center(
-                left,
-                Constraint::Length(left_text.width() as u16),
-                Constraint::Length(1),
-            );
+        Self::setup_database(&mut conn)?;
+
+        Ok(Self { conn })
+    }
 
-            let right = center(
-                right,
-                Constraint::Length(right_text.width() as u16),
-                Constraint::Length(1),
+    fn setup_database(conn: &mut Connection) -> anyhow::Result<()> {
+        conn.execute_batch(
+            "
+            create table if not exists boards (
+                id integer primary key,
+                name text not null,
+                card_id integer not null default 1,
+                inserted_at timestamp not null default current_timestamp,
+                updated_at timestamp not null default current_timestamp,
+                viewed_at timestamp not null default current_timestamp
             );
 
-            frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
-            frame.render_widget(left_text, left);
-            frame.render_widget(right_text, right);
-            frame.render_widget(block, area);
+            create unique index if not exists boards_name on boards (name);
 
-            fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
-                let vertical =
-                    Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
-                let horizontal =
-                    Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
-                let [area] = vertical.areas(area);
-                let [area] = horizontal.areas(area);
-                area
-            }
+            create table if not exists statuses (
+                id integer primary key,
+                name text not null,
+                column_order integer not null,
+                board_id integer not null,
+                inserted_at timestamp not null default current_timestamp,
+                updated_at timestamp not null default current_timestamp,
 
-            fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
-                let [area] = Layout::horizontal([horizontal])
-                    .flex(Flex::Center)
-                    .areas(area);
-                let [area] = Layout::vertical([vertical]).flex(Flex::Center).areas(area);
-                area
-            }
-        }
+                foreign key(board_id) references boards(id) on delete cascade
+            );
 
-        let modeline_block = Block::new()
-            .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
-            .title(
-                Line::from(match model.mode {
-                    Mode::ViewingBoard => "VIEWING BOARD",
-                    Mode::ViewingCardDetail => "VIEWING CARD",
-                    Mode::MovingCard => "MOVING CARD",
-                    Mode::ConfirmCardDeletion => "DELETING CARD",
-                    Mode::ViewingBoards => "VIEWING BOARDS",
-                })
-                .left_aligned(),
-            )
-            .title(Line::from(&*board.name).right_aligned());
+            create unique index if not exists statuses_name_board_id on statuses (name, board_id);
+            -- not possible to do this while updating orders that could be the same
+            -- during a transaction
+            -- create unique index if not exists statuses_column_order_board_id on statuses (column_order, board_id);
+            create index if not exists statuses_board_id on statuses (board_id);
 
-        let modeline_text = {
-            let mut modeline_text = String::new();
+            create table if not exists cards (
+                id integer primary key,
+                external_id integer not null,
+                board_id integer not null,
+                title text not null,
+                status_id integer not null,
+                body text not null,
+                doing_at timestamp,
+                done_at timestamp,
+                inserted_at timestamp not null default current_timestamp,
+                updated_at timestamp not null default current_timestamp,
 
-            if let Some(e) = &model.error {
-                modeline_text.push_str(" - Error: ");
-                modeline_text.push_str(&e.replace("\n", " "));
-            } else {
-                let formatted = match model.mode {
-                    Mode::ViewingBoard => [
-                        ("[h,j,k,l/arrows]", "move"),
-                        ("[q]", "quit"),
-                        ("[enter]", "view card"),
-                        ("[m]", "move card"),
-                        ("[n]", "new card"),
-                        ("[e]", "edit card"),
-                        ("[d]", "delete card"),
-                        ("[b]", "view boards"),
-                    ]
-                    .iter()
-                    .map(|(k, action)| format!("{} - {}", k, action))
-                    .collect::<Vec<_>>(),
-                    Mode::ViewingCardDetail => [
-                        ("[enter/esc]", "close detail view"),
-                        ("[e]", "edit card"),
-                        ("[q]", "quit"),
-                    ]
-                    .iter()
-                    .map(|(k, action)| format!("{} - {}", k, action))
-                    .collect::<Vec<_>>(),
-                    Mode::MovingCard => [
-                        ("[h/left]", "move card left"),
-                        ("[l/right]", "move card right"),
-                        ("[q]", "quit"),
-                        ("[m|enter|esc]", "close card detail view"),
-                    ]
-                    .iter()
-                    .map(|(k, action)| format!("{} - {}", k, action))
-                    .collect::<Vec<_>>(),
-                    Mode::ViewingBoards => [
-                        ("[j/down]", "down"),
-                        ("[k/up]", "up"),
-                        ("[enter]", "view board"),
-                        ("[n]", "new board"),
-                        ("[e]", "edit board"),
-                        ("[q]", "quit"),
-                    ]
-                    .iter()
-                    .map(|(k, action)| format!("{} - {}", k, action))
-                    .collect::<Vec<_>>(),
-                    Mode::ConfirmCardDeletion => [
-                        ("[h/left]", "left"),
-                        ("[l/right]", "right"),
-                        ("[enter]", "confirm selection"),
-                    ]
-                    .iter()
-                    .map(|(k, action)| format!("{} - {}", k, action))
-                    .collect::<Vec<_>>(),
-                };
+                foreign key(board_id) references boards(id) on delete cascade,
+                foreign key(status_id) references statuses(id) on delete cascade
+            );
 
-                modeline_text.push_str(&formatted.join(" │ "));
-            }
+            create index if not exists cards_board_id on cards (board_id);
+            create index if not exists cards_status_id on cards (status_id);
 
-            modeline_text
-        };
+            create trigger if not exists cards_updated
+            after update of external_id, board_id, title, status_id, body on cards
+            for each row
+            begin
+                update cards
+                set updated_at = current_timestamp
+                where cards.id = NEW.id;
 
-        let modeline = Paragraph::new(modeline_text).block(modeline_block);
+                update boards
+                set updated_at = current_timestamp
+                where boards.id = NEW.board_id;
+            end
+    ",
+        )?;
 
-        frame.render_widget(modeline, modeline_layout);
-    }
-}
+        Self::migrate(conn)?;
 
-/// Convert Event to Message
-///
-/// We don't need to pass in a `model` to this function in this example
-/// but you might need it as your project evolves
-fn receive_event(model: &Model) -> anyhow::Result<Option<Message>> {
-    if crossterm::event::poll(Duration::from_millis(1000))?
-        && let crossterm::event::Event::Key(key) = crossterm::event::read()?
-        && key.kind == crossterm::event::KeyEventKind::Press
-    {
-        return Ok(handle_event(Event::KeyEvent(key), model));
+        Ok(())
     }
 
-    if let Ok(event) = model.internal_event_rx.try_recv() {
-        return Ok(handle_event(event, model));
-    }
+    /// schema changes that `create table if not exists` can't express,
+    /// like adding a column to a table that already exists.
+    /// each migration runs once, in order, and `user_version` records how many have run
+    const MIGRATIONS: &[&str] = &[
+        "
+        alter table boards add column archived_at timestamp;
+        ",
+        // cards are shown in descending rank order.
+        // seeding rank from id keeps existing boards in the order they had before ranks existed.
+        // reordering a column should not count as updating its cards,
+        // so cards_updated only fires for the columns that matter.
+        // it is replaced before seeding rank, or the old trigger would touch every card
+        "
+        alter table cards add column rank integer not null default 0;
+        create index if not exists cards_status_id_rank on cards (status_id, rank);
+
+        drop trigger if exists cards_updated;
+
+        create trigger cards_updated
+        after update of external_id, board_id, title, status_id, body on cards
+        for each row
+        begin
+            update cards
+            set updated_at = current_timestamp
+            where cards.id = NEW.id;
 
-    Ok(None)
-}
+            update boards
+            set updated_at = current_timestamp
+            where boards.id = NEW.board_id;
+        end;
 
-fn handle_event(event: Event, model: &Model) -> Option<Message> {
-    match event {
-        Event::KeyEvent(key) => match model.mode {
-            Mode::ViewingBoard => match key.code {
-                KeyCode::Char('h') | KeyCode::Left => Some(Message::NavigateLeft),
-                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
-                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
-                KeyCode::Char('l') | KeyCode::Right => Some(Message::NavigateRight),
-                KeyCode::Char('q') => Some(Message::Quit),
-                KeyCode::Char('m') => Some(Message::MoveCardMode),
-                KeyCode::Char('n') => Some(Message::NewCard),
-                KeyCode::Char('e') => Some(Message::EditCard),
-                KeyCode::Char('d') => Some(Message::DeleteCard),
-                KeyCode::Char('b') => Some(Message::ViewBoardsMode),
-                KeyCode::Enter => Some(Message::ViewCardDetailMode),
-                _ => None,
-            },
-            Mode::MovingCard => match key.code {
-                KeyCode::Char('h') | KeyCode::Left => Some(Message::MoveCardLeft),
-                KeyCode::Char('l') | KeyCode::Right => Some(Message::MoveCardRight),
-                KeyCode::Char('q') => Some(Message::Quit),
-                KeyCode::Char('m') | KeyCode::Enter | KeyCode::Esc => Some(Message::ViewBoardMode),
-                _ => None,
-            },
-            Mode::ConfirmCardDeletion => match key.code {
-                KeyCode::Char('h') | KeyCode::Left => Some(Message::NavigateLeft),
-                KeyCode::Char('l') | KeyCode::Right => Some(Message::NavigateRight),
-                KeyCode::Enter => Some(Message::ConfirmChoice),
-                _ => None,
-            },
-            Mode::ViewingCardDetail => match key.code {
-                KeyCode::Enter | KeyCode::Esc => Some(Message::ViewBoardMode),
-                KeyCode::Char('e') => Some(Message::EditCard),
-                KeyCode::Char('q') => Some(Message::Quit),
-                _ => None,
-            },
-            Mode::ViewingBoards => match key.code {
-                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
-                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
-                KeyCode::Char('n') => Some(Message::NewBoard),
-                KeyCode::Char('e') => Some(Message::EditBoard),
-                KeyCode::Char('q') => Some(Message::Quit),
-                KeyCode::Enter => Some(Message::ViewBoardMode),
-                _ => None,
+        update cards set rank = id;
+        ",
+        "
+        alter table statuses add column stage text;
+        ",
+        // append-only. events keep a copy of the card as it was,
+        // so they outlive the card itself, but not its board
+        "
+        create table if not exists card_events (
+            id integer primary key,
+            card_id integer not null,
+            board_id integer not null,
+            external_id integer not null,
+            kind text not null,
+            title text not null,
+            body text not null,
+            previous_status text,
+            status text not null,
+            inserted_at timestamp not null default current_timestamp,
+
+            foreign key(board_id) references boards(id) on delete cascade
+        );
+
+        create index if not exists card_events_card_id on card_events (card_id);
+        create index if not exists card_events_board_id on card_events (board_id);
+        ",
+        "
+        create table if not exists tags (
+            id integer primary key,
+            board_id integer not null,
+            name text not null,
+
+            foreign key(board_id) references boards(id) on delete cascade
+        );
+
+        create unique index if not exists tags_board_id_name on tags (board_id, name);
+
+        create table if not exists card_tags (
+            card_id integer not null,
+            tag_id integer not null,
+
+            primary key(card_id, tag_id),
+            foreign key(card_id) references cards(id) on delete cascade,
+            foreign key(tag_id) references tags(id) on delete cascade
+        );
+
+        create index if not exists card_tags_tag_id on card_tags (tag_id);
+        ",
+        // due dates are local dates, like 2024-12-31
+        "
+        alter table cards add column due_on text;
+
+        create index if not exists cards_due_on on cards (due_on) where due_on is not null;
+
+        drop trigger if exists cards_updated;
+
+        create trigger cards_updated
+        after update of external_id, board_id, title, status_id, body, due_on on cards
+        for each row
+        begin
+            update cards
+            set updated_at = current_timestamp
+            where cards.id = NEW.id;
+
+            update boards
+            set updated_at = current_timestamp
+            where boards.id = NEW.board_id;
+        end;
+        ",
+        // priorities run from 0, the most urgent, to 3
+        "
+        alter table cards add column priority integer;
+        alter table boards add column sort_mode text not null default 'manual';
+
+        drop trigger if exists cards_updated;
+
+        create trigger cards_updated
+        after update of external_id, board_id, title, status_id, body, due_on, priority on cards
+        for each row
+        begin
+            update cards
+            set updated_at = current_timestamp
+            where cards.id = NEW.id;
+
+            update boards
+            set updated_at = current_timestamp
+            where boards.id = NEW.board_id;
+        end;
+        ",
+        // the search index reads titles and bodies out of `cards`,
+        // so these triggers are all that keep it up to date
+        "
+        create virtual table cards_search using fts5(
+            title,
+            body,
+            content = 'cards',
+            content_rowid = 'id'
+        );
+
+        create trigger cards_search_inserted
+        after insert on cards
+        begin
+            insert into cards_search (rowid, title, body)
+            values (NEW.id, NEW.title, NEW.body);
+        end;
+
+        create trigger cards_search_deleted
+        after delete on cards
+        begin
+            insert into cards_search (cards_search, rowid, title, body)
+            values ('delete', OLD.id, OLD.title, OLD.body);
+        end;
+
+        create trigger cards_search_updated
+        after update of title, body on cards
+        begin
+            insert into cards_search (cards_search, rowid, title, body)
+            values ('delete', OLD.id, OLD.title, OLD.body);
+
+            insert into cards_search (rowid, title, body)
+            values (NEW.id, NEW.title, NEW.body);
+        end;
+
+        insert into cards_search (cards_search) values ('rebuild');
+        ",
+        // `sent_from` says where a card sent between boards came from, like `Inbox #7`,
+        // and `other_board` is the board on the other end of a send
+        "
+        alter table cards add column sent_from text;
+        alter table card_events add column other_board text;
+        ",
+        // what a markdown file said the last time `kk sync` left it and its board the same,
+        // so cards deleted from the file can be told apart from cards new to the board
+        "
+        create table if not exists synced_files (
+            board_id integer not null,
+            path text not null,
+            contents text not null,
+
+            primary key(board_id, path),
+            foreign key(board_id) references boards(id) on delete cascade
+        );
+        ",
+    ];
+
+    fn migrate(conn: &mut Connection) -> anyhow::Result<()> {
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        let user_version: i64 = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        for (i, migration) in Self::MIGRATIONS
+            .iter()
+            .enumerate()
+            .skip(usize::try_from(user_version)?)
+        {
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", i64::try_from(i + 1)?)?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// changes whenever another connection commits to the database,
+    /// but not when this one does
+    fn data_version(&self) -> anyhow::Result<i64> {
+        Ok(self
+            .conn
+            .pragma_query_value(None, "data_version", |row| row.get(0))?)
+    }
+
+    /// boards that have not been archived
+    fn get_board_metas(&self) -> anyhow::Result<Vec<BoardMeta>> {
+        self.board_metas_where_archived(false)
+    }
+
+    fn get_archived_board_metas(&self) -> anyhow::Result<Vec<BoardMeta>> {
+        self.board_metas_where_archived(true)
+    }
+
+    fn board_metas_where_archived(&self, archived: bool) -> anyhow::Result<Vec<BoardMeta>> {
+        let mut s = self.conn.prepare(
+            "
+        select
+            boards.id,
+            boards.name,
+            group_concat(statuses.name, '|' order by statuses.column_order),
+            group_concat(coalesce(statuses.stage, ''), '|' order by statuses.column_order),
+            boards.inserted_at,
+            boards.updated_at,
+            boards.viewed_at
+        from boards
+        inner join statuses
+            on statuses.board_id = boards.id
+        where (boards.archived_at is not null) = ?
+        group by boards.id, boards.name
+        order by boards.viewed_at desc
+        ",
+        )?;
+
+        let boards_iter = s.query_map([archived], |row| {
+            let column_names: String = row.get(2)?;
+            let columns_names = column_names.split('|').map(|s| s.to_string()).collect();
+
+            let column_stages: String = row.get(3)?;
+            let column_stages = column_stages.split('|').map(|s| s.parse().ok()).collect();
+
+            Ok(BoardMeta {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                columns: columns_names,
+                stages: column_stages,
+                inserted_at: row.get(4)?,
+                updated_at: row.get(5)?,
+                viewed_at: row.get(6)?,
+            })
+        })?;
+
+        let mut boards = vec![];
+
+        for board in boards_iter {
+            boards.push(board?);
+        }
+
+        Ok(boards)
+    }
+
+    fn load_board(&mut self, board_id: BoardId) -> anyhow::Result<Board> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        tx.execute(
+            "
+        update boards
+        set viewed_at = current_timestamp
+        where id = ?
+        ",
+            [board_id],
+        )?;
+
+        tx.commit()?;
+
+        self.get_board(board_id)
+    }
+
+    /// like `load_board`, but without counting as a view
+    fn get_board(&self, board_id: BoardId) -> anyhow::Result<Board> {
+        let (name, sort_mode) = self.conn.query_one(
+            "
+        select
+            name,
+            sort_mode
+        from boards
+        where id = ?
+        ",
+            [board_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let columns = self.get_cards_for_board(board_id)?;
+
+        Ok(Board {
+            id: board_id,
+            name,
+            sort_mode,
+            columns,
+        })
+    }
+
+    fn get_cards_for_board(&self, board_id: BoardId) -> anyhow::Result<Vec<Column>> {
+        let mut statuses_s = self.conn.prepare(
+            "
+            select
+                name,
+                stage
+            from statuses
+            where board_id = ?
+            order by column_order asc
+            ",
+        )?;
+
+        let statuses_iter =
+            statuses_s.query_map([board_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut columns = vec![];
+
+        for status in statuses_iter {
+            let (status, stage): (String, Option<Stage>) = status?;
+            let cards = self.cards_for_column(board_id, &status)?;
+            columns.push(Column {
+                name: status,
+                stage,
+                cards,
+            })
+        }
+
+        Ok(columns)
+    }
+
+    /// a card with everything in its template,
+    /// in `column_name` rather than the board's first column if there is one.
+    /// nothing is written unless all of it is
+    fn add_card(
+        &mut self,
+        board_id: BoardId,
+        column_name: Option<&str>,
+        text: &CardText,
+    ) -> anyhow::Result<Card> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        let card = Self::write_new_card(&tx, board_id, &text.title, &text.body)?;
+
+        if let Some(column_name) = column_name {
+            Self::move_card(&tx, board_id, card.id, column_name)?;
+        }
+
+        Self::write_card_fields(&tx, board_id, card.id, text)?;
+
+        let card = Self::read_card(&tx, card.id)?
+            .ok_or_else(|| anyhow!("card {} was not saved", card.external_id))?;
+
+        tx.commit()?;
+
+        Ok(card)
+    }
+
+    /// at the top of the board's first column,
+    /// stamped like `set_card_status` would if that column has a stage
+    fn write_new_card(
+        conn: &Connection,
+        board_id: BoardId,
+        title: &str,
+        body: &str,
+    ) -> anyhow::Result<Card> {
+        let status_id: StatusId = conn.query_one(
+            "
+        select
+            id
+        from statuses
+        where board_id = ?
+        order by column_order asc
+        limit 1
+        ",
+            [board_id],
+            |row| row.get(0),
+        )?;
+
+        let external_card_id: ExternalCardId = conn.query_one(
+            "
+        select
+            card_id
+        from boards
+        where id = ?
+        ",
+            [board_id],
+            |row| row.get(0),
+        )?;
+
+        let card = conn.query_row(
+            "
+        insert into cards (external_id, board_id, status_id, title, body, rank, doing_at, done_at)
+        values (
+            ?1, ?2, ?3, ?4, ?5,
+            (select coalesce(max(rank), 0) + 1 from cards where status_id = ?3),
+            case (select stage from statuses where id = ?3)
+                when 'doing' then current_timestamp
+            end,
+            case (select stage from statuses where id = ?3)
+                when 'done' then current_timestamp
+            end
+        )
+        returning id, inserted_at, updated_at, doing_at, done_at;
+        ",
+            params![external_card_id, board_id, status_id, title, body],
+            |row| {
+                Ok(Card {
+                    id: row.get(0)?,
+                    external_id: external_card_id,
+                    title: title.to_string(),
+                    body: body.to_string(),
+                    inserted_at: row.get(1)?,
+                    updated_at: row.get(2)?,
+                    doing_at: row.get(3)?,
+                    done_at: row.get(4)?,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                })
             },
-        },
-        Event::InternalEvent(e) => match e {
-            InternalEvent::ClearError => Some(Message::SetError(None)),
-        },
+        )?;
+
+        Self::record_card_event(conn, card.id, CardEventKind::Created, None, None)?;
+
+        conn.execute(
+            "
+            update boards
+            set card_id = card_id + 1
+            where id = ?;
+        ",
+            [board_id],
+        )?;
+
+        Ok(card)
+    }
+
+    /// in the board's `SortMode`
+    fn cards_for_column(&self, board_id: BoardId, column_name: &str) -> anyhow::Result<Vec<Card>> {
+        let sort_mode = self.get_board_sort_mode(board_id)?;
+
+        let mut s = self.conn.prepare(&format!(
+            "
+            select
+                {}
+            from cards
+            inner join statuses
+                on statuses.id = cards.status_id
+                and statuses.board_id = ?1
+                and statuses.name = ?2
+            order by {};
+            ",
+            Card::SQL_COLUMNS,
+            sort_mode.order_by()
+        ))?;
+
+        let cards_iter = s.query_map(params![board_id, column_name], Card::from_row)?;
+
+        let mut cards = vec![];
+
+        for card in cards_iter {
+            cards.push(card?);
+        }
+
+        Ok(cards)
+    }
+
+    /// edits that don't change anything still bump `updated_at`,
+    /// but are left out of the card's history
+    fn update_card(&mut self, card_id: CardId, title: &str, body: &str) -> anyhow::Result<String> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        Self::write_card_text(&tx, card_id, title, body)?;
+
+        let updated_at = tx.query_one(
+            "
+        select
+            updated_at
+        from cards
+        where id = ?
+        ",
+            [card_id],
+            |row| row.get(0),
+        )?;
+
+        tx.commit()?;
+
+        Ok(updated_at)
+    }
+
+    fn write_card_text(
+        conn: &Connection,
+        card_id: CardId,
+        title: &str,
+        body: &str,
+    ) -> anyhow::Result<()> {
+        let (old_title, old_body): (String, String) = conn.query_one(
+            "
+        select
+            title,
+            body
+        from cards
+        where id = ?
+        ",
+            [card_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        conn.execute(
+            "
+        update cards
+        set
+            title = ?2,
+            body = ?3
+        where id = ?1
+        ",
+            params![card_id, title, body],
+        )?;
+
+        if old_title != title || old_body != body {
+            Self::record_card_event(conn, card_id, CardEventKind::Edited, None, None)?;
+        }
+
+        Ok(())
+    }
+
+    /// moves a card to the top of a column.
+    ///
+    /// a card's `doing_at` is stamped the first time it enters a `Stage::Doing` column.
+    /// its `done_at` is stamped when it enters a `Stage::Done` column and cleared when it leaves
+    fn set_card_status(
+        &mut self,
+        board_id: BoardId,
+        card_id: CardId,
+        column_name: &str,
+    ) -> anyhow::Result<()> {
+        self.set_cards_status(board_id, &[card_id], column_name)
+    }
+
+    /// `card_ids` are in display order, and they stay in that order at the top of the column
+    fn set_cards_status(
+        &mut self,
+        board_id: BoardId,
+        card_ids: &[CardId],
+        column_name: &str,
+    ) -> anyhow::Result<()> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        for card_id in card_ids.iter().rev() {
+            Self::move_card(&tx, board_id, *card_id, column_name)?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn move_card(
+        conn: &Connection,
+        board_id: BoardId,
+        card_id: CardId,
+        column_name: &str,
+    ) -> anyhow::Result<()> {
+        let previous_status: String = conn
+            .query_one(
+                "
+        select
+            statuses.name
+        from cards
+        inner join statuses
+            on statuses.id = cards.status_id
+        where cards.id = ?
+        ",
+                [card_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| Self::missing_card_error(conn, card_id))?;
+
+        // already there, so it keeps its place and nothing is recorded
+        if previous_status == column_name {
+            return Ok(());
+        }
+
+        conn.execute(
+            "
+        update cards
+        set
+            status_id = (
+                select
+                    id
+                from statuses
+                where board_id = ?1
+                and name = ?2
+            ),
+            rank = (
+                select
+                    coalesce(max(cards.rank), 0) + 1
+                from cards
+                inner join statuses
+                    on statuses.id = cards.status_id
+                where statuses.board_id = ?1
+                and statuses.name = ?2
+            ),
+            doing_at = case (select stage from statuses where board_id = ?1 and name = ?2)
+                when 'doing' then coalesce(doing_at, current_timestamp)
+                else doing_at
+            end,
+            done_at = case (select stage from statuses where board_id = ?1 and name = ?2)
+                when 'done' then coalesce(done_at, current_timestamp)
+                else null
+            end
+        where id = ?3
+        ",
+            params![board_id, column_name, card_id],
+        )?;
+
+        Self::record_card_event(
+            conn,
+            card_id,
+            CardEventKind::Moved,
+            Some(&previous_status),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    /// names a card that has gone, e.g. deleted from another terminal,
+    /// by the number it had, which its history still knows
+    fn missing_card_error(conn: &Connection, card_id: CardId) -> anyhow::Error {
+        let external_id: Option<ExternalCardId> = conn
+            .query_one(
+                "
+        select
+            external_id
+        from card_events
+        where card_id = ?
+        order by id desc
+        limit 1
+        ",
+                [card_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()
+            .flatten();
+
+        match external_id {
+            Some(external_id) => anyhow!("card {} no longer exists", external_id),
+            None => anyhow!("the card no longer exists"),
+        }
+    }
+
+    /// snapshots a card into `card_events` as it is right now,
+    /// so this must run after the change it records, except for deletions
+    /// `other_board` is the board on the other end
+    /// of a `CardEventKind::Sent` or `CardEventKind::Received`
+    fn record_card_event(
+        conn: &Connection,
+        card_id: CardId,
+        kind: CardEventKind,
+        previous_status: Option<&str>,
+        other_board: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            "
+        insert into card_events (
+            card_id,
+            board_id,
+            external_id,
+            kind,
+            title,
+            body,
+            previous_status,
+            status,
+            other_board
+        )
+        select
+            cards.id,
+            cards.board_id,
+            cards.external_id,
+            ?2,
+            cards.title,
+            cards.body,
+            ?3,
+            statuses.name,
+            ?4
+        from cards
+        inner join statuses
+            on statuses.id = cards.status_id
+        where cards.id = ?1
+        ",
+            params![card_id, kind, previous_status, other_board],
+        )?;
+
+        Ok(())
+    }
+
+    /// `card_ids` are in display order. they keep it on the other board,
+    /// which numbers them from the bottom up, like it would new cards
+    fn send_cards_to_board(
+        &mut self,
+        card_ids: &[CardId],
+        to_board_id: BoardId,
+        column_name: &str,
+    ) -> anyhow::Result<()> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        for card_id in card_ids.iter().rev() {
+            Self::transfer_card(&tx, *card_id, to_board_id, column_name)?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// moves a card onto another board, where it gets that board's next card number.
+    /// its tags come along with it
+    fn transfer_card(
+        conn: &Connection,
+        card_id: CardId,
+        to_board_id: BoardId,
+        column_name: &str,
+    ) -> anyhow::Result<ExternalCardId> {
+        let (from_board_id, from_board_name, from_external_id): (BoardId, String, ExternalCardId) =
+            conn.query_one(
+                "
+        select
+            boards.id,
+            boards.name,
+            cards.external_id
+        from cards
+        inner join boards
+            on boards.id = cards.board_id
+        where cards.id = ?
+        ",
+                [card_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+
+        if from_board_id == to_board_id {
+            return Err(anyhow!("the card is already on {}", from_board_name));
+        }
+
+        let (to_board_name, external_id): (String, ExternalCardId) = conn.query_one(
+            "
+        select
+            name,
+            card_id
+        from boards
+        where id = ?
+        ",
+            [to_board_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let status_id: StatusId = conn
+            .query_one(
+                "
+        select
+            id
+        from statuses
+        where board_id = ?1
+        and name = ?2
+        ",
+                params![to_board_id, column_name],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("{} has no column named {}", to_board_name, column_name))?;
+
+        let tags = {
+            let mut tags_s = conn.prepare(
+                "
+        select
+            tags.name
+        from card_tags
+        inner join tags
+            on tags.id = card_tags.tag_id
+        where card_tags.card_id = ?
+        ",
+            )?;
+
+            tags_s
+                .query_map([card_id], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?
+        };
+
+        Self::record_card_event(
+            conn,
+            card_id,
+            CardEventKind::Sent,
+            None,
+            Some(&to_board_name),
+        )?;
+
+        conn.execute(
+            "
+        update cards
+        set
+            board_id = ?1,
+            external_id = ?2,
+            status_id = ?3,
+            rank = (select coalesce(max(rank), 0) + 1 from cards where status_id = ?3),
+            doing_at = case (select stage from statuses where id = ?3)
+                when 'doing' then coalesce(doing_at, current_timestamp)
+                else doing_at
+            end,
+            done_at = case (select stage from statuses where id = ?3)
+                when 'done' then coalesce(done_at, current_timestamp)
+                else null
+            end,
+            sent_from = ?4
+        where id = ?5
+        ",
+            params![
+                to_board_id,
+                external_id,
+                status_id,
+                format!("{} #{}", from_board_name, from_external_id),
+                card_id
+            ],
+        )?;
+
+        conn.execute(
+            "
+        update boards
+        set card_id = card_id + 1
+        where id = ?
+        ",
+            [to_board_id],
+        )?;
+
+        Self::replace_card_tags(conn, to_board_id, card_id, &tags)?;
+        Self::delete_unused_tags(conn, from_board_id)?;
+
+        Self::record_card_event(
+            conn,
+            card_id,
+            CardEventKind::Received,
+            None,
+            Some(&from_board_name),
+        )?;
+
+        Ok(external_id)
+    }
+
+    /// newest first
+    fn get_card_events(&self, card_id: CardId) -> anyhow::Result<Vec<CardEvent>> {
+        let mut s = self.conn.prepare(&format!(
+            "
+        select
+            {}
+        from card_events
+        where card_id = ?
+        order by id desc
+        ",
+            CardEvent::SQL_COLUMNS
+        ))?;
+
+        let events = s
+            .query_map([card_id], CardEvent::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    const BOARD_ACTIVITY_LIMIT: i64 = 500;
+
+    /// the most recent `BOARD_ACTIVITY_LIMIT` events for every card on a board,
+    /// including cards that have since been deleted. newest first
+    fn get_board_events(&self, board_id: BoardId) -> anyhow::Result<Vec<CardEvent>> {
+        let mut s = self.conn.prepare(&format!(
+            "
+        select
+            {}
+        from card_events
+        where board_id = ?
+        order by id desc
+        limit ?
+        ",
+            CardEvent::SQL_COLUMNS
+        ))?;
+
+        let events = s
+            .query_map(
+                params![board_id, Self::BOARD_ACTIVITY_LIMIT],
+                CardEvent::from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    /// persists the order of a column.
+    /// `card_ids` are in display order, so the first card gets the highest rank
+    fn rank_cards(&mut self, card_ids: &[CardId]) -> anyhow::Result<()> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        {
+            let mut rank_s = tx.prepare(
+                "
+            update cards
+            set rank = ?
+            where id = ?
+            ",
+            )?;
+
+            for (i, card_id) in card_ids.iter().enumerate() {
+                rank_s.execute(params![i64::try_from(card_ids.len() - i)?, card_id])?;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// `stages` lines up with `column_names`. columns without a stage can be left off the end
+    fn create_board(
+        &mut self,
+        name: &str,
+        column_names: &[&str],
+        stages: &[Option<Stage>],
+    ) -> anyhow::Result<i64> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        let board_id = Self::insert_board(&tx, name, column_names, stages)?;
+
+        tx.commit()?;
+
+        Ok(board_id.0)
+    }
+
+    fn insert_board(
+        conn: &Connection,
+        name: &str,
+        column_names: &[&str],
+        stages: &[Option<Stage>],
+    ) -> anyhow::Result<BoardId> {
+        let mut board_s = conn.prepare(
+            "
+            insert into boards (name) values (?)
+            returning id;
+            ",
+        )?;
+
+        let mut columns_s = conn.prepare(
+            "
+            insert into statuses (name, column_order, board_id, stage)
+            values (?, ?, ?, ?);
+            ",
+        )?;
+
+        let board_id: BoardId = board_s.query_row([name], |row| row.get(0))?;
+
+        for (column_order, column_name) in column_names.iter().enumerate() {
+            columns_s.execute(params![
+                column_name,
+                i64::try_from(column_order).expect("must be less than i64::MAX columns"),
+                board_id,
+                stages.get(column_order).copied().flatten()
+            ])?;
+        }
+
+        Ok(board_id)
+    }
+
+    /// columns the board doesn't have yet go after the ones it does.
+    /// the stage of a column the board already has is left alone
+    fn add_missing_column(
+        conn: &Connection,
+        board_id: BoardId,
+        column_name: &str,
+        stage: Option<Stage>,
+    ) -> anyhow::Result<()> {
+        conn.execute(
+            "
+        insert into statuses (name, column_order, board_id, stage)
+        select
+            ?2,
+            (select coalesce(max(column_order), -1) + 1 from statuses where board_id = ?1),
+            ?1,
+            ?3
+        where not exists (select 1 from statuses where board_id = ?1 and name = ?2)
+        ",
+            params![board_id, column_name, stage],
+        )?;
+
+        Ok(())
+    }
+
+    /// puts a card from a file at the top of `column_name`, without its tags.
+    /// stage timestamps the file doesn't have are stamped like `insert_card` does
+    fn insert_imported_card(
+        conn: &Connection,
+        board_id: BoardId,
+        column_name: &str,
+        card: &ImportedCard,
+        external_id: ExternalCardId,
+    ) -> anyhow::Result<CardId> {
+        let card_id = conn.query_one(
+            "
+        insert into cards (
+            external_id,
+            board_id,
+            status_id,
+            title,
+            body,
+            rank,
+            doing_at,
+            done_at,
+            due_on,
+            priority,
+            sent_from,
+            inserted_at,
+            updated_at
+        )
+        select
+            ?1,
+            ?2,
+            statuses.id,
+            ?4,
+            ?5,
+            (select coalesce(max(rank), 0) + 1 from cards where status_id = statuses.id),
+            coalesce(?6, case statuses.stage when 'doing' then current_timestamp end),
+            coalesce(?7, case statuses.stage when 'done' then current_timestamp end),
+            ?8,
+            ?9,
+            ?10,
+            coalesce(?11, current_timestamp),
+            coalesce(?12, current_timestamp)
+        from statuses
+        where statuses.board_id = ?2
+        and statuses.name = ?3
+        returning id
+        ",
+            params![
+                external_id,
+                board_id,
+                column_name,
+                card.title,
+                card.body,
+                card.doing_at,
+                card.done_at,
+                card.due_on,
+                card.priority,
+                card.sent_from,
+                card.inserted_at,
+                card.updated_at
+            ],
+            |row| row.get(0),
+        )?;
+
+        Ok(card_id)
+    }
+
+    /// creates a board from a file, keeping the card numbers it has.
+    /// with `merge`, a board that already has the same name gets the file's cards,
+    /// and any of its cards that have a number from the file are replaced by the file's version
+    fn import_board(&mut self, board: &ImportedBoard, merge: bool) -> anyhow::Result<BoardId> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        let existing_board: Option<(BoardId, bool)> = tx
+            .query_one(
+                "
+        select
+            id,
+            archived_at is not null
+        from boards
+        where name = ?
+        ",
+                [&board.name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        // an archived board is never merged into without being looked at first,
+        // but it still has the name
+        let existing_board_id = match existing_board {
+            Some((_, true)) => {
+                return Err(anyhow!(
+                    "there is already an archived board named {}. use --board to give this one a different name",
+                    board.name
+                ));
+            }
+            Some((board_id, false)) => Some(board_id),
+            None => None,
+        };
+
+        let board_id = match existing_board_id {
+            Some(_) if !merge => {
+                return Err(anyhow!(
+                    "there is already a board named {}. use --merge to import into it, or --board to give this one a different name",
+                    board.name
+                ));
+            }
+            Some(board_id) => board_id,
+            None => {
+                let column_names = board
+                    .columns
+                    .iter()
+                    .map(|column| column.name.as_str())
+                    .collect::<Vec<_>>();
+
+                let stages = board
+                    .columns
+                    .iter()
+                    .map(|column| column.stage)
+                    .collect::<Vec<_>>();
+
+                let board_id = Self::insert_board(&tx, &board.name, &column_names, &stages)?;
+
+                if let Some(sort_mode) = board.sort_mode {
+                    tx.execute(
+                        "
+        update boards
+        set sort_mode = ?2
+        where id = ?1
+        ",
+                        params![board_id, sort_mode],
+                    )?;
+                }
+
+                board_id
+            }
+        };
+
+        for column in &board.columns {
+            Self::add_missing_column(&tx, board_id, &column.name, column.stage)?;
+        }
+
+        // cards without a number get the next ones after every number already in use
+        let mut next_external_id = {
+            let card_counter: i64 = tx.query_one(
+                "
+        select
+            card_id
+        from boards
+        where id = ?
+        ",
+                [board_id],
+                |row| row.get(0),
+            )?;
+
+            board
+                .columns
+                .iter()
+                .flat_map(|column| &column.cards)
+                .filter_map(|card| card.external_id)
+                .map(|external_id| external_id.0 + 1)
+                .fold(card_counter, i64::max)
+        };
+
+        let mut cards = vec![];
+        let mut seen = std::collections::HashSet::new();
+
+        for column in &board.columns {
+            for card in &column.cards {
+                let external_id = card.external_id.unwrap_or_else(|| {
+                    next_external_id += 1;
+                    ExternalCardId(next_external_id - 1)
+                });
+
+                if !seen.insert(external_id) {
+                    return Err(anyhow!("there is more than one card {}", external_id));
+                }
+
+                if let Some(due_on) = &card.due_on
+                    && !is_valid_date(due_on)
+                {
+                    return Err(anyhow!(
+                        "card {} is due on {}, but due dates look like 2024-12-31",
+                        external_id,
+                        due_on
+                    ));
+                }
+
+                if let Some(tag) = card.tags.iter().find(|tag| tag.contains(',')) {
+                    return Err(anyhow!(
+                        "card {} has a tag with a comma in it: {}",
+                        external_id,
+                        tag
+                    ));
+                }
+
+                cards.push((column.name.as_str(), card, external_id));
+            }
+        }
+
+        // each card goes on top of its column,
+        // so going from the bottom up keeps the order they had in the file
+        for (column_name, card, external_id) in cards.into_iter().rev() {
+            let existing_card_id: Option<CardId> = tx
+                .query_one(
+                    "
+        select
+            id
+        from cards
+        where board_id = ?
+        and external_id = ?
+        ",
+                    params![board_id, external_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            let card_id = if let Some(card_id) = existing_card_id {
+                tx.execute(
+                    "
+        update cards
+        set
+            status_id = statuses.id,
+            title = ?4,
+            body = ?5,
+            rank = (select coalesce(max(rank), 0) + 1 from cards where status_id = statuses.id),
+            doing_at = ?6,
+            done_at = ?7,
+            due_on = ?8,
+            priority = ?9
+        from statuses
+        where cards.id = ?1
+        and statuses.board_id = ?2
+        and statuses.name = ?3
+        ",
+                    params![
+                        card_id,
+                        board_id,
+                        column_name,
+                        card.title,
+                        card.body,
+                        card.doing_at,
+                        card.done_at,
+                        card.due_on,
+                        card.priority
+                    ],
+                )?;
+
+                card_id
+            } else {
+                Self::insert_imported_card(&tx, board_id, column_name, card, external_id)?
+            };
+
+            Self::replace_card_tags(&tx, board_id, card_id, &card.tags)?;
+
+            Self::record_card_event(&tx, card_id, CardEventKind::Imported, None, None)?;
+        }
+
+        tx.execute(
+            "
+        update boards
+        set card_id = max(card_id, ?2)
+        where id = ?1
+        ",
+            params![board_id, next_external_id],
+        )?;
+
+        tx.commit()?;
+
+        Ok(board_id)
+    }
+
+    /// makes a board look like a markdown file that was edited outside of kk.
+    /// only what the file changed since `last_synced`, the file as kk last wrote it, is written,
+    /// so edits and moves made in kk since then stay.
+    /// cards that were in the file at the last sync and aren't anymore are deleted.
+    /// cards deleted from the board since the last sync stay deleted,
+    /// and cards added to the board since then, which the file doesn't know about yet, stay
+    fn merge_synced_file(
+        &mut self,
+        board_id: BoardId,
+        file_board: &ImportedBoard,
+        last_synced: Option<&ImportedBoard>,
+    ) -> anyhow::Result<()> {
+        /// which of `indices` are in their longest increasing run.
+        /// those cards are still in the order they were, and the rest were moved around them
+        fn in_order(indices: &[usize]) -> Vec<bool> {
+            let mut lengths = vec![1; indices.len()];
+            let mut previous = vec![None; indices.len()];
+
+            for i in 0..indices.len() {
+                for j in 0..i {
+                    if indices[j] < indices[i] && lengths[j] + 1 > lengths[i] {
+                        lengths[i] = lengths[j] + 1;
+                        previous[i] = Some(j);
+                    }
+                }
+            }
+
+            let mut keep = vec![false; indices.len()];
+            let mut i = (0..indices.len()).max_by_key(|&i| lengths[i]);
+            while let Some(j) = i {
+                keep[j] = true;
+                i = previous[j];
+            }
+
+            keep
+        }
+
+        /// where a card from the file ends up in its column
+        enum Place {
+            /// it was in this column at the last sync, this far down
+            Synced(usize),
+            /// it is new to this column, in the file or in kk
+            New,
+            /// kk moved it to another column since the last sync, and the file didn't move it
+            Elsewhere,
+        }
+
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        for column in &file_board.columns {
+            Self::add_missing_column(&tx, board_id, &column.name, None)?;
+        }
+
+        let synced_cards = last_synced
+            .iter()
+            .flat_map(|board| &board.columns)
+            .flat_map(|column| {
+                column
+                    .cards
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(i, card)| {
+                        card.external_id
+                            .map(|external_id| (external_id, (column.name.as_str(), i, card)))
+                    })
+            })
+            .collect::<std::collections::HashMap<_, _>>();
+
+        let mut next_external_id: i64 = tx.query_one(
+            "
+        select
+            card_id
+        from boards
+        where id = ?
+        ",
+            [board_id],
+            |row| row.get(0),
+        )?;
+
+        let mut cards = vec![];
+
+        for column in &file_board.columns {
+            for card in &column.cards {
+                let existing_card: Option<(Card, String)> =
+                    if let Some(external_id) = card.external_id {
+                        tx.query_one(
+                            &format!(
+                                "
+        select
+            {},
+            statuses.name
+        from cards
+        inner join statuses
+            on statuses.id = cards.status_id
+        where cards.board_id = ?
+        and cards.external_id = ?
+        ",
+                                Card::SQL_COLUMNS
+                            ),
+                            params![board_id, external_id],
+                            |row| Ok((Card::from_row(row)?, row.get(Card::SQL_COLUMNS_LEN)?)),
+                        )
+                        .optional()?
+                    } else {
+                        None
+                    };
+
+                match (existing_card, card.external_id) {
+                    (Some(existing_card), _) => cards.push((
+                        column.name.as_str(),
+                        card,
+                        existing_card.0.external_id,
+                        Some(existing_card),
+                    )),
+                    (None, Some(external_id)) if synced_cards.contains_key(&external_id) => {}
+                    (None, external_id) => {
+                        let external_id = external_id.unwrap_or_else(|| {
+                            next_external_id += 1;
+                            ExternalCardId(next_external_id - 1)
+                        });
+                        next_external_id = next_external_id.max(external_id.0 + 1);
+                        cards.push((column.name.as_str(), card, external_id, None));
+                    }
+                }
+            }
+        }
+
+        let mut in_file = std::collections::HashSet::new();
+        let mut places = vec![];
+
+        for (column_name, card, external_id, existing_card) in cards {
+            let synced = synced_cards.get(&external_id).copied();
+            let synced_card = synced.map(|(_, _, synced_card)| synced_card);
+
+            let (card_id, place) = match existing_card {
+                Some((existing_card, existing_column)) => {
+                    // a field the file didn't change keeps what kk has
+                    let mut text = existing_card.text();
+                    if synced_card.is_none_or(|synced_card| card.title != synced_card.title) {
+                        text.title = card.title.clone();
+                    }
+                    if synced_card.is_none_or(|synced_card| card.body != synced_card.body) {
+                        text.body = card.body.clone();
+                    }
+                    if synced_card.is_none_or(|synced_card| card.tags != synced_card.tags) {
+                        text.tags = card.tags.clone();
+                    }
+                    if synced_card.is_none_or(|synced_card| card.due_on != synced_card.due_on) {
+                        text.due_on = card.due_on.clone();
+                    }
+
+                    if text != existing_card.text() {
+                        Self::write_card(&tx, board_id, existing_card.id, &text)?;
+                    }
+
+                    let place = match synced {
+                        Some((synced_column, i, _)) if synced_column == column_name => {
+                            if existing_column == column_name {
+                                Place::Synced(i)
+                            } else {
+                                Place::Elsewhere
+                            }
+                        }
+                        _ => {
+                            if existing_column != column_name {
+                                Self::move_card(&tx, board_id, existing_card.id, column_name)?;
+                            }
+                            Place::New
+                        }
+                    };
+
+                    (existing_card.id, place)
+                }
+                None => {
+                    let card_id =
+                        Self::insert_imported_card(&tx, board_id, column_name, card, external_id)?;
+                    Self::replace_card_tags(&tx, board_id, card_id, &card.tags)?;
+                    Self::record_card_event(&tx, card_id, CardEventKind::Created, None, None)?;
+                    (card_id, Place::New)
+                }
+            };
+
+            in_file.insert(external_id);
+            places.push((column_name, card_id, place));
+        }
+
+        // cards that are in the same order as at the last sync stay where they are,
+        // and the rest go under the card above them in the file
+        for column in &file_board.columns {
+            let places = places
+                .iter()
+                .filter(|(column_name, _, _)| *column_name == column.name)
+                .collect::<Vec<_>>();
+
+            let synced = places
+                .iter()
+                .filter_map(|(_, _, place)| match place {
+                    Place::Synced(i) => Some(*i),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let mut keep = in_order(&synced).into_iter();
+
+            let mut above = None;
+
+            for (_, card_id, place) in places {
+                match place {
+                    Place::Elsewhere => continue,
+                    Place::Synced(_) if keep.next() == Some(true) => {}
+                    Place::Synced(_) | Place::New => Self::place_card(&tx, *card_id, above)?,
+                }
+                above = Some(*card_id);
+            }
+        }
+
+        if let Some(last_synced) = last_synced {
+            let synced_ids = last_synced
+                .columns
+                .iter()
+                .flat_map(|column| &column.cards)
+                .filter_map(|card| card.external_id);
+
+            for external_id in synced_ids.filter(|external_id| !in_file.contains(external_id)) {
+                let card_id: Option<CardId> = tx
+                    .query_one(
+                        "
+        select
+            id
+        from cards
+        where board_id = ?
+        and external_id = ?
+        ",
+                        params![board_id, external_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+
+                if let Some(card_id) = card_id {
+                    Self::remove_card(&tx, card_id)?;
+                }
+            }
+        }
+
+        tx.execute(
+            "
+        update boards
+        set card_id = max(card_id, ?2)
+        where id = ?1
+        ",
+            params![board_id, next_external_id],
+        )?;
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// puts a card right under `above`, which is in the same column, or at the top of its column.
+    /// only ranks change, so it isn't an update to any card
+    fn place_card(conn: &Connection, card_id: CardId, above: Option<CardId>) -> anyhow::Result<()> {
+        match above {
+            Some(above) => {
+                let rank: i64 = conn.query_one(
+                    "
+        select
+            rank
+        from cards
+        where id = ?
+        ",
+                    [above],
+                    |row| row.get(0),
+                )?;
+
+                conn.execute(
+                    "
+        update cards
+        set rank = rank + 1
+        where status_id = (select status_id from cards where id = ?1)
+        and rank >= ?2
+        and id != ?1
+        ",
+                    params![card_id, rank],
+                )?;
+
+                conn.execute(
+                    "
+        update cards
+        set rank = ?2
+        where id = ?1
+        ",
+                    params![card_id, rank],
+                )?;
+            }
+            None => {
+                conn.execute(
+                    "
+        update cards
+        set rank = (
+            select
+                coalesce(max(others.rank), 0) + 1
+            from cards others
+            where others.status_id = cards.status_id
+            and others.id != cards.id
+        )
+        where id = ?
+        ",
+                    [card_id],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_synced_file(&self, board_id: BoardId, path: &str) -> anyhow::Result<Option<String>> {
+        let contents = self
+            .conn
+            .query_one(
+                "
+        select
+            contents
+        from synced_files
+        where board_id = ?
+        and path = ?
+        ",
+                params![board_id, path],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(contents)
+    }
+
+    fn set_synced_file(&self, board_id: BoardId, path: &str, contents: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "
+        insert into synced_files (board_id, path, contents)
+        values (?1, ?2, ?3)
+        on conflict (board_id, path) do update set contents = excluded.contents
+        ",
+            params![board_id, path, contents],
+        )?;
+
+        Ok(())
+    }
+
+    /// applies a `BoardEdit` in a single transaction.
+    ///
+    /// statuses are tracked by id rather than name throughout,
+    /// so renames can swap names or reuse the name of a removed column
+    fn update_board(&mut self, edit: &BoardEdit) -> anyhow::Result<()> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        {
+            let mut status_id_s = tx.prepare(
+                "
+                select
+                    id
+                from statuses
+                where board_id = ?
+                and name = ?
+                ",
+            )?;
+
+            let mut rename_s = tx.prepare(
+                "
+                update statuses
+                set name = ?
+                where id = ?
+                ",
+            )?;
+
+            let mut change_column_order_s = tx.prepare(
+                "
+                insert into statuses (name, column_order, board_id, stage)
+                values (?, ?, ?, ?)
+                on conflict(name, board_id) do update set
+                    column_order = excluded.column_order,
+                    stage = excluded.stage;
+                ",
+            )?;
+
+            // stamps like `set_card_status` does
+            let mut move_cards_s = tx.prepare(
+                "
+                update cards
+                set
+                    status_id = ?1,
+                    doing_at = case (select stage from statuses where id = ?1)
+                        when 'doing' then coalesce(doing_at, current_timestamp)
+                        else doing_at
+                    end,
+                    done_at = case (select stage from statuses where id = ?1)
+                        when 'done' then coalesce(done_at, current_timestamp)
+                        else null
+                    end
+                where status_id = ?2
+                ",
+            )?;
+
+            // like `record_card_event`, for every card in a column at once
+            let mut record_moves_s = tx.prepare(
+                "
+                insert into card_events (
+                    card_id,
+                    board_id,
+                    external_id,
+                    kind,
+                    title,
+                    body,
+                    previous_status,
+                    status
+                )
+                select
+                    id,
+                    board_id,
+                    external_id,
+                    ?2,
+                    title,
+                    body,
+                    ?3,
+                    ?4
+                from cards
+                where status_id = ?1
+                ",
+            )?;
+
+            let mut count_cards_s = tx.prepare(
+                "
+                select
+                    count(*)
+                from cards
+                where status_id = ?
+                ",
+            )?;
+
+            let mut delete_status_s = tx.prepare(
+                "
+                delete from statuses
+                where id = ?
+                ",
+            )?;
+
+            let mut change_board_name_s = tx.prepare(
+                "
+            update boards
+            set name = ?
+            where id = ?
+            ",
+            )?;
+
+            let renamed_ids = edit
+                .renames
+                .iter()
+                .map(|(from, _to)| {
+                    status_id_s.query_one(params![edit.board_id, from], |row| row.get(0))
+                })
+                .collect::<Result<Vec<StatusId>, _>>()?;
+
+            let removed_ids = edit
+                .removals
+                .iter()
+                .map(|removal| {
+                    status_id_s.query_one(params![edit.board_id, removal.column], |row| row.get(0))
+                })
+                .collect::<Result<Vec<StatusId>, _>>()?;
+
+            // move everything being renamed or removed out of the way first,
+            // so the new names are free to be taken
+            for status_id in renamed_ids.iter().chain(removed_ids.iter()) {
+                rename_s.execute(params![format!("\u{1}{}", status_id.0), status_id])?;
+            }
+
+            for ((_from, to), status_id) in edit.renames.iter().zip(&renamed_ids) {
+                rename_s.execute(params![to, status_id])?;
+            }
+
+            for (i, column_name) in edit.columns.iter().enumerate() {
+                change_column_order_s.execute(params![
+                    column_name,
+                    i64::try_from(i).unwrap(),
+                    edit.board_id,
+                    edit.stages.get(i).copied().flatten()
+                ])?;
+            }
+
+            for (removal, status_id) in edit.removals.iter().zip(&removed_ids) {
+                if let Some(destination) = &removal.destination {
+                    let destination_id: StatusId = status_id_s
+                        .query_one(params![edit.board_id, destination], |row| row.get(0))?;
+
+                    record_moves_s.execute(params![
+                        status_id,
+                        CardEventKind::Moved,
+                        removal.column,
+                        destination
+                    ])?;
+                    move_cards_s.execute(params![destination_id, status_id])?;
+                } else {
+                    // cards may have arrived since the edit was started,
+                    // and deleting the column would take them with it
+                    let card_count: i64 = count_cards_s.query_one([status_id], |row| row.get(0))?;
+
+                    if card_count > 0 {
+                        return Err(anyhow!(
+                            "Could not update board: column {} has cards now, choose where they go",
+                            removal.column
+                        ));
+                    }
+                }
+
+                delete_status_s.execute([status_id])?;
+            }
+
+            change_board_name_s.execute(params![edit.name, edit.board_id])?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn count_cards_in_column(&self, board_id: BoardId, column_name: &str) -> anyhow::Result<i64> {
+        let count = self.conn.query_one(
+            "
+        select
+            count(*)
+        from cards
+        inner join statuses
+            on statuses.id = cards.status_id
+        where statuses.board_id = ?1
+        and statuses.name = ?2
+        ",
+            params![board_id, column_name],
+            |row| row.get(0),
+        )?;
+
+        Ok(count)
+    }
+
+    fn load_most_recently_viewed_board(&self) -> anyhow::Result<Option<Board>> {
+        let mut board_s = self.conn.prepare(
+            "
+        select
+            id,
+            name,
+            sort_mode
+        from boards
+        where archived_at is null
+        order by viewed_at desc
+        limit 1
+        ",
+        )?;
+
+        let board_meta: Option<(BoardId, String, SortMode)> = board_s
+            .query_one([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .optional()?;
+
+        if let Some((board_id, board_name, sort_mode)) = board_meta {
+            let columns = self.get_cards_for_board(board_id)?;
+
+            Ok(Some(Board {
+                id: board_id,
+                name: board_name,
+                sort_mode,
+                columns,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn delete_card(&mut self, card_id: CardId) -> anyhow::Result<()> {
+        self.delete_cards(&[card_id])
+    }
+
+    fn delete_cards(&mut self, card_ids: &[CardId]) -> anyhow::Result<()> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        for card_id in card_ids {
+            Self::remove_card(&tx, *card_id)?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn remove_card(conn: &Connection, card_id: CardId) -> anyhow::Result<()> {
+        Self::record_card_event(conn, card_id, CardEventKind::Deleted, None, None)?;
+
+        conn.execute(
+            "
+        delete from cards
+        where id = ?",
+            [card_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// does `operation`, or its opposite when `reverse` is true.
+    /// all of a batch happens, or none of it does
+    fn apply_operation(
+        &mut self,
+        board_id: BoardId,
+        operation: &mut Operation,
+        reverse: bool,
+    ) -> anyhow::Result<Vec<RestoredCard>> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        let mut restored_cards = vec![];
+
+        Self::write_operation(&tx, board_id, operation, reverse, &mut restored_cards)?;
+
+        tx.commit()?;
+
+        Ok(restored_cards)
+    }
+
+    fn write_operation(
+        conn: &Connection,
+        board_id: BoardId,
+        operation: &mut Operation,
+        reverse: bool,
+        restored_cards: &mut Vec<RestoredCard>,
+    ) -> anyhow::Result<()> {
+        match operation {
+            Operation::Move { card_id, from, to } => {
+                let column = if reverse { &*from } else { &*to };
+                Self::move_card(conn, board_id, *card_id, column)?;
+            }
+            Operation::Edit {
+                card_id,
+                before,
+                after,
+            } => {
+                let text = if reverse { &*before } else { &*after };
+                Self::write_card(conn, board_id, *card_id, text)?;
+            }
+            Operation::Delete {
+                card,
+                column,
+                index,
+            } => {
+                if reverse {
+                    let old_card_id = card.id;
+                    card.id = Self::restore_card(conn, board_id, card, column)?;
+                    restored_cards.push(RestoredCard {
+                        old_card_id,
+                        card_id: card.id,
+                        column: column.clone(),
+                        index: *index,
+                    });
+                } else {
+                    Self::remove_card(conn, card.id)?;
+                }
+            }
+            Operation::Batch(operations) => {
+                if reverse {
+                    // undone in the opposite order they were done in
+                    for operation in operations.iter_mut().rev() {
+                        Self::write_operation(conn, board_id, operation, true, restored_cards)?;
+                    }
+                } else {
+                    for operation in operations {
+                        Self::write_operation(conn, board_id, operation, false, restored_cards)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// puts a deleted card back at the top of `column_name`,
+    /// with its original `ExternalCardId` and timestamps.
+    /// it keeps its original `CardId` too, unless a newer card has taken it,
+    /// so the id it ends up with is returned
+    fn restore_card(
+        conn: &Connection,
+        board_id: BoardId,
+        card: &Card,
+        column_name: &str,
+    ) -> anyhow::Result<CardId> {
+        let card_id = conn.query_one(
+            "
+        insert into cards (
+            id,
+            external_id,
+            board_id,
+            status_id,
+            title,
+            body,
+            rank,
+            doing_at,
+            done_at,
+            due_on,
+            priority,
+            sent_from,
+            inserted_at,
+            updated_at
+        )
+        select
+            case when exists (select 1 from cards where id = ?1) then null else ?1 end,
+            ?2,
+            ?3,
+            statuses.id,
+            ?5,
+            ?6,
+            (select coalesce(max(rank), 0) + 1 from cards where status_id = statuses.id),
+            ?7,
+            ?8,
+            ?9,
+            ?10,
+            ?11,
+            ?12,
+            ?13
+        from statuses
+        where statuses.board_id = ?3
+        and statuses.name = ?4
+        returning id
+        ",
+            params![
+                card.id,
+                card.external_id,
+                board_id,
+                column_name,
+                card.title,
+                card.body,
+                card.doing_at,
+                card.done_at,
+                card.due_on,
+                card.priority,
+                card.sent_from,
+                card.inserted_at,
+                card.updated_at
+            ],
+            |row| row.get(0),
+        )?;
+
+        Self::replace_card_tags(conn, board_id, card_id, &card.tags)?;
+
+        Self::record_card_event(conn, card_id, CardEventKind::Restored, None, None)?;
+
+        Ok(card_id)
+    }
+
+    /// everything in a card's template at once, returning the card's new `updated_at`
+    fn edit_card(
+        &mut self,
+        board_id: BoardId,
+        card_id: CardId,
+        text: &CardText,
+    ) -> anyhow::Result<String> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        let updated_at = Self::write_card(&tx, board_id, card_id, text)?;
+
+        tx.commit()?;
+
+        Ok(updated_at)
+    }
+
+    fn edit_cards(
+        &mut self,
+        board_id: BoardId,
+        cards: &[(CardId, CardText)],
+    ) -> anyhow::Result<()> {
+        let tx = self
+            .conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+        for (card_id, text) in cards {
+            Self::write_card(&tx, board_id, *card_id, text)?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// a change to any part of the text counts as an edit in the card's history,
+    /// tags and due dates included
+    fn write_card(
+        conn: &Connection,
+        board_id: BoardId,
+        card_id: CardId,
+        text: &CardText,
+    ) -> anyhow::Result<String> {
+        let before = Self::read_card(conn, card_id)?
+            .ok_or_else(|| Self::missing_card_error(conn, card_id))?
+            .text();
+
+        conn.execute(
+            "
+        update cards
+        set
+            title = ?2,
+            body = ?3
+        where id = ?1
+        ",
+            params![card_id, text.title, text.body],
+        )?;
+
+        let updated_at = Self::write_card_fields(conn, board_id, card_id, text)?;
+
+        if before != *text {
+            Self::record_card_event(conn, card_id, CardEventKind::Edited, None, None)?;
+        }
+
+        Ok(updated_at)
+    }
+
+    fn write_card_fields(
+        conn: &Connection,
+        board_id: BoardId,
+        card_id: CardId,
+        text: &CardText,
+    ) -> anyhow::Result<String> {
+        Self::replace_card_tags(conn, board_id, card_id, &text.tags)?;
+
+        conn.execute(
+            "
+        update cards
+        set
+            due_on = ?2,
+            priority = ?3
+        where id = ?1
+        ",
+            params![card_id, text.due_on, text.priority],
+        )?;
+
+        // `returning` would miss the bump from `cards_updated`
+        let updated_at = conn.query_one(
+            "
+        select
+            updated_at
+        from cards
+        where id = ?
+        ",
+            [card_id],
+            |row| row.get(0),
+        )?;
+
+        Ok(updated_at)
+    }
+
+    /// tags are created the first time a card on the board uses them,
+    /// and deleted once no card on the board does.
+    /// they are read back joined with commas, so they can't contain any
+    fn replace_card_tags(
+        conn: &Connection,
+        board_id: BoardId,
+        card_id: CardId,
+        tags: &[String],
+    ) -> anyhow::Result<()> {
+        if let Some(tag) = tags.iter().find(|tag| tag.contains(',')) {
+            return Err(anyhow!("tags can't have commas in them: {}", tag));
+        }
+
+        conn.execute("delete from card_tags where card_id = ?", [card_id])?;
+
+        let mut insert_tag_s = conn.prepare(
+            "
+        insert into tags (board_id, name)
+        values (?, ?)
+        on conflict(board_id, name) do nothing
+        ",
+        )?;
+
+        let mut tag_card_s = conn.prepare(
+            "
+        insert into card_tags (card_id, tag_id)
+        select
+            ?1,
+            id
+        from tags
+        where board_id = ?2
+        and name = ?3
+        on conflict do nothing
+        ",
+        )?;
+
+        for tag in tags {
+            insert_tag_s.execute(params![board_id, tag])?;
+            tag_card_s.execute(params![card_id, board_id, tag])?;
+        }
+
+        Self::delete_unused_tags(conn, board_id)?;
+
+        Ok(())
+    }
+
+    fn delete_unused_tags(conn: &Connection, board_id: BoardId) -> rusqlite::Result<()> {
+        conn.execute(
+            "
+        delete from tags
+        where board_id = ?
+        and not exists (
+            select 1 from card_tags where card_tags.tag_id = tags.id
+        )
+        ",
+            [board_id],
+        )?;
+
+        Ok(())
+    }
+
+    fn get_board_tags(&self, board_id: BoardId) -> anyhow::Result<Vec<String>> {
+        let mut s = self.conn.prepare(
+            "
+        select
+            name
+        from tags
+        where board_id = ?
+        order by name asc
+        ",
+        )?;
+
+        let tags = s
+            .query_map([board_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
+    fn get_due_thresholds(&self) -> anyhow::Result<DueThresholds> {
+        let due_thresholds = self.conn.query_one(
+            "
+        select
+            date('now', 'localtime'),
+            date('now', 'localtime', ?)
+        ",
+            [format!("+{} days", DueThresholds::SOON_DAYS)],
+            |row| {
+                Ok(DueThresholds {
+                    today: row.get(0)?,
+                    soon: row.get(1)?,
+                })
+            },
+        )?;
+
+        Ok(due_thresholds)
+    }
+
+    /// every card with a due date that isn't done yet,
+    /// on every board that isn't archived
+    fn get_agenda(&self) -> anyhow::Result<Vec<AgendaItem>> {
+        let mut s = self.conn.prepare(&format!(
+            "
+        select
+            {},
+            boards.id,
+            boards.name,
+            statuses.name
+        from cards
+        inner join statuses
+            on statuses.id = cards.status_id
+        inner join boards
+            on boards.id = cards.board_id
+        where cards.due_on is not null
+        and boards.archived_at is null
+        and statuses.stage is not 'done'
+        order by cards.due_on asc, boards.name asc, cards.external_id asc
+        ",
+            Card::SQL_COLUMNS
+        ))?;
+
+        let items = s
+            .query_map([], |row| {
+                Ok(AgendaItem {
+                    card: Card::from_row(row)?,
+                    board_id: row.get(Card::SQL_COLUMNS_LEN)?,
+                    board_name: row.get(Card::SQL_COLUMNS_LEN + 1)?,
+                    column: row.get(Card::SQL_COLUMNS_LEN + 2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    const SEARCH_LIMIT: i64 = 100;
+
+    /// cards on every unarchived board whose title or body
+    /// has words starting with each word of `query`
+    fn search_cards(&self, query: &str) -> anyhow::Result<Vec<SearchResult>> {
+        // quoting every word keeps fts5 query syntax out of what people type
+        let match_expression = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if match_expression.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut s = self.conn.prepare(&format!(
+            "
+        select
+            {},
+            boards.id,
+            boards.name,
+            statuses.name,
+            snippet(cards_search, -1, ?2, ?3, '…', 12)
+        from cards_search
+        inner join cards
+            on cards.id = cards_search.rowid
+        inner join statuses
+            on statuses.id = cards.status_id
+        inner join boards
+            on boards.id = cards.board_id
+        where cards_search match ?1
+        and boards.archived_at is null
+        order by cards_search.rank
+        limit ?4
+        ",
+            Card::SQL_COLUMNS
+        ))?;
+
+        let results = s
+            .query_map(
+                params![
+                    match_expression,
+                    SearchResult::MATCH_START.to_string(),
+                    SearchResult::MATCH_END.to_string(),
+                    Self::SEARCH_LIMIT
+                ],
+                |row| {
+                    Ok(SearchResult {
+                        card: Card::from_row(row)?,
+                        board_id: row.get(Card::SQL_COLUMNS_LEN)?,
+                        board_name: row.get(Card::SQL_COLUMNS_LEN + 1)?,
+                        column: row.get(Card::SQL_COLUMNS_LEN + 2)?,
+                        snippet: row
+                            .get::<_, String>(Card::SQL_COLUMNS_LEN + 3)?
+                            .replace('\n', " "),
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+
+    fn get_board_sort_mode(&self, board_id: BoardId) -> anyhow::Result<SortMode> {
+        let sort_mode = self.conn.query_one(
+            "
+        select
+            sort_mode
+        from boards
+        where id = ?
+        ",
+            [board_id],
+            |row| row.get(0),
+        )?;
+
+        Ok(sort_mode)
+    }
+
+    fn set_board_sort_mode(&self, board_id: BoardId, sort_mode: SortMode) -> anyhow::Result<()> {
+        self.conn.execute(
+            "
+        update boards
+        set sort_mode = ?2
+        where id = ?1
+        ",
+            params![board_id, sort_mode],
+        )?;
+
+        Ok(())
+    }
+
+    /// statuses and cards go with it, via `on delete cascade`
+    fn delete_board(&self, board_id: BoardId) -> anyhow::Result<()> {
+        self.conn
+            .execute("delete from boards where id = ?", [board_id])?;
+
+        Ok(())
+    }
+
+    fn set_board_archived(&self, board_id: BoardId, archived: bool) -> anyhow::Result<()> {
+        self.conn.execute(
+            "
+        update boards
+        set archived_at = case when ?2 then current_timestamp else null end
+        where id = ?1
+        ",
+            params![board_id, archived],
+        )?;
+
+        Ok(())
+    }
+
+    fn get_board_metrics(&self, board_id: BoardId) -> anyhow::Result<BoardMetrics> {
+        let mut cycle_times_s = self.conn.prepare(
+            "
+        select
+            julianday(done_at) - julianday(doing_at)
+        from cards
+        where board_id = ?
+        and done_at is not null
+        and doing_at is not null
+        ",
+        )?;
+
+        let cycle_times = cycle_times_s
+            .query_map([board_id], |row| row.get(0))?
+            .collect::<Result<Vec<f64>, _>>()?;
+
+        let mut lead_times_s = self.conn.prepare(
+            "
+        select
+            julianday(done_at) - julianday(inserted_at)
+        from cards
+        where board_id = ?
+        and done_at is not null
+        ",
+        )?;
+
+        let lead_times = lead_times_s
+            .query_map([board_id], |row| row.get(0))?
+            .collect::<Result<Vec<f64>, _>>()?;
+
+        let mut throughput_s = self.conn.prepare(
+            "
+        select
+            cast((julianday('now') - julianday(done_at)) / 7 as integer) as weeks_ago,
+            count(*)
+        from cards
+        where board_id = ?1
+        and done_at is not null
+        and weeks_ago < ?2
+        group by weeks_ago
+        ",
+        )?;
+
+        let mut weekly_throughput = vec![0; BoardMetrics::THROUGHPUT_WEEKS];
+
+        for row in throughput_s.query_map(
+            params![board_id, BoardMetrics::THROUGHPUT_WEEKS as i64],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )? {
+            let (weeks_ago, count) = row?;
+            if let Ok(weeks_ago) = usize::try_from(weeks_ago) {
+                weekly_throughput[BoardMetrics::THROUGHPUT_WEEKS - 1 - weeks_ago] =
+                    u64::try_from(count)?;
+            }
+        }
+
+        let work_in_progress: i64 = self.conn.query_one(
+            "
+        select
+            count(*)
+        from cards
+        inner join statuses
+            on statuses.id = cards.status_id
+        where cards.board_id = ?
+        and statuses.stage = 'doing'
+        ",
+            [board_id],
+            |row| row.get(0),
+        )?;
+
+        Ok(BoardMetrics {
+            cycle_times,
+            lead_times,
+            weekly_throughput,
+            work_in_progress: u64::try_from(work_in_progress)?,
+        })
+    }
+
+    fn get_board_id_by_name(&self, board_name: &str) -> anyhow::Result<BoardId> {
+        self.conn
+            .query_one(
+                "
+        select
+            id
+        from boards
+        where name = ?
+        ",
+                [board_name],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("no board named {}", board_name))
+    }
+
+    fn column_exists(&self, board_id: BoardId, column: &str) -> anyhow::Result<bool> {
+        let exists = self.conn.query_one(
+            "
+        select exists(
+            select 1
+            from statuses
+            where board_id = ?
+            and name = ?
+        )
+        ",
+            params![board_id, column],
+            |row| row.get(0),
+        )?;
+
+        Ok(exists)
+    }
+
+    fn get_most_recently_viewed_board_id(&self) -> anyhow::Result<Option<BoardId>> {
+        let board_id = self
+            .conn
+            .query_one(
+                "
+        select
+            id
+        from boards
+        where archived_at is null
+        order by viewed_at desc
+        limit 1
+        ",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(board_id)
+    }
+
+    /// returns the card along with the name of the column it is in
+    fn get_card_by_external_id(
+        &self,
+        board_id: BoardId,
+        external_id: ExternalCardId,
+    ) -> anyhow::Result<(Card, String)> {
+        self.find_card_by_external_id(board_id, external_id)?
+            .ok_or_else(|| anyhow!("no card with id {}", external_id))
+    }
+
+    /// `None` if the board has no card with that number
+    fn find_card_by_external_id(
+        &self,
+        board_id: BoardId,
+        external_id: ExternalCardId,
+    ) -> anyhow::Result<Option<(Card, String)>> {
+        let card = self
+            .conn
+            .query_one(
+                &format!(
+                    "
+        select
+            {},
+            statuses.name
+        from cards
+        inner join statuses
+            on statuses.id = cards.status_id
+        where cards.board_id = ?1
+        and cards.external_id = ?2
+        ",
+                    Card::SQL_COLUMNS
+                ),
+                params![board_id, external_id],
+                |row| Ok((Card::from_row(row)?, row.get(Card::SQL_COLUMNS_LEN)?)),
+            )
+            .optional()?;
+
+        Ok(card)
+    }
+
+    /// `None` if the card has been deleted
+    fn get_card(&self, card_id: CardId) -> anyhow::Result<Option<Card>> {
+        Self::read_card(&self.conn, card_id)
+    }
+
+    fn read_card(conn: &Connection, card_id: CardId) -> anyhow::Result<Option<Card>> {
+        let card = conn
+            .query_one(
+                &format!(
+                    "
+        select
+            {}
+        from cards
+        where cards.id = ?
+        ",
+                    Card::SQL_COLUMNS
+                ),
+                [card_id],
+                Card::from_row,
+            )
+            .optional()?;
+
+        Ok(card)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Board {
+    #[serde(skip)]
+    id: BoardId,
+    name: String,
+    sort_mode: SortMode,
+    columns: Vec<Column>,
+}
+
+impl Board {
+    fn retain_tagged(&mut self, tag: &str) {
+        for column in &mut self.columns {
+            column
+                .cards
+                .retain(|card| card.tags.iter().any(|t| t == tag));
+        }
+    }
+
+    fn export<W: Write>(&self, format: ExportFormat, out: &mut W) -> anyhow::Result<()> {
+        match format {
+            ExportFormat::Markdown => self.write_markdown(out),
+            ExportFormat::Json => {
+                serde_json::to_writer_pretty(&mut *out, self)?;
+                writeln!(out)?;
+                Ok(())
+            }
+            ExportFormat::Csv => self.write_csv(out),
+            ExportFormat::Obsidian => self.write_obsidian(out),
+        }
+    }
+
+    /// the markdown the Obsidian Kanban plugin keeps boards in, and `kk sync` reads.
+    /// every card ends with its number as a block id, like `^kk-3`,
+    /// so it can be told apart from other cards with the same title
+    fn write_obsidian<W: Write>(&self, out: &mut W) -> anyhow::Result<()> {
+        writeln!(out, "---\n\nkanban-plugin: basic\n\n---")?;
+
+        for column in &self.columns {
+            writeln!(out)?;
+            writeln!(out, "## {}", column.name)?;
+            writeln!(out)?;
+
+            for card in &column.cards {
+                write!(
+                    out,
+                    "- [{}] {}",
+                    if card.done_at.is_some() { "x" } else { " " },
+                    escape_obsidian_title(&card.title)
+                )?;
+                for tag in &card.tags {
+                    write!(out, " #{}", encode_obsidian_tag(tag))?;
+                }
+                if let Some(due_on) = &card.due_on {
+                    write!(out, " @{{{}}}", due_on)?;
+                }
+                writeln!(out, " ^kk-{}", card.external_id)?;
+
+                for line in card.body.trim_end().lines() {
+                    if line.trim().is_empty() {
+                        writeln!(out)?;
+                    } else {
+                        writeln!(out, "\t{}", line)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// a heading per column and a list item per card,
+    /// with the card's body indented under it
+    fn write_markdown<W: Write>(&self, out: &mut W) -> anyhow::Result<()> {
+        writeln!(out, "# {}", self.name)?;
+
+        for column in &self.columns {
+            writeln!(out)?;
+            writeln!(out, "## {}", column.name)?;
+
+            if !column.cards.is_empty() {
+                writeln!(out)?;
+            }
+
+            for card in &column.cards {
+                let mut details = vec![];
+                if let Some(priority) = card.priority {
+                    details.push(priority.to_string());
+                }
+                if let Some(due_on) = &card.due_on {
+                    details.push(format!("due {}", due_on));
+                }
+                if !card.tags.is_empty() {
+                    details.push(format!("tags: {}", card.tags.join(", ")));
+                }
+
+                write!(out, "- {} {}", card.external_id, card.title)?;
+                if !details.is_empty() {
+                    write!(out, " ({})", details.join(", "))?;
+                }
+                writeln!(out)?;
+
+                for line in card.body.trim_end().lines() {
+                    if line.trim().is_empty() {
+                        writeln!(out)?;
+                    } else {
+                        writeln!(out, "  {}", line)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// one row per card, in column order
+    fn write_csv<W: Write>(&self, out: &mut W) -> anyhow::Result<()> {
+        fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\"")).into()
+            } else {
+                field.into()
+            }
+        }
+
+        writeln!(out, "{}", Self::CSV_HEADER.join(","))?;
+
+        for column in &self.columns {
+            for card in &column.cards {
+                let row = [
+                    card.external_id.to_string(),
+                    card.title.clone(),
+                    column.name.clone(),
+                    card.priority
+                        .map(|priority| priority.to_string())
+                        .unwrap_or_default(),
+                    card.due_on.clone().unwrap_or_default(),
+                    card.tags.join(", "),
+                    card.inserted_at.clone(),
+                    card.updated_at.clone(),
+                    card.doing_at.clone().unwrap_or_default(),
+                    card.done_at.clone().unwrap_or_default(),
+                    card.body.clone(),
+                ];
+
+                writeln!(
+                    out,
+                    "{}",
+                    row.iter()
+                        .map(|field| csv_field(field))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    const CSV_HEADER: [&str; 11] = [
+        "external_id",
+        "title",
+        "status",
+        "priority",
+        "due_on",
+        "tags",
+        "inserted_at",
+        "updated_at",
+        "doing_at",
+        "done_at",
+        "body",
+    ];
+}
+
+/// what `Board::export` can write
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ExportFormat {
+    Markdown,
+    Json,
+    Csv,
+    /// markdown for the Obsidian Kanban plugin
+    Obsidian,
+}
+
+impl ExportFormat {
+    const ALL: [ExportFormat; 4] = [
+        ExportFormat::Markdown,
+        ExportFormat::Json,
+        ExportFormat::Csv,
+        ExportFormat::Obsidian,
+    ];
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Obsidian => "md",
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Markdown => write!(f, "Markdown"),
+            ExportFormat::Json => write!(f, "JSON"),
+            ExportFormat::Csv => write!(f, "CSV"),
+            ExportFormat::Obsidian => write!(f, "Obsidian Kanban"),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "obsidian" => Ok(ExportFormat::Obsidian),
+            _ => Err(anyhow!(
+                "unknown format {}, expected markdown, json, csv or obsidian",
+                s
+            )),
+        }
+    }
+}
+
+/// what `kk import` can read
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ImportFormat {
+    Json,
+    Csv,
+    /// the JSON Trello exports a board as
+    Trello,
+    /// markdown for the Obsidian Kanban plugin
+    Obsidian,
+}
+
+impl ImportFormat {
+    fn from_extension(path: &std::path::Path) -> Option<ImportFormat> {
+        match path.extension()?.to_str()? {
+            "json" => Some(ImportFormat::Json),
+            "csv" => Some(ImportFormat::Csv),
+            "md" => Some(ImportFormat::Obsidian),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ImportFormat::Json),
+            "csv" => Ok(ImportFormat::Csv),
+            "trello" => Ok(ImportFormat::Trello),
+            "obsidian" => Ok(ImportFormat::Obsidian),
+            _ => Err(anyhow!(
+                "unknown format {}, expected json, csv, trello or obsidian",
+                s
+            )),
+        }
+    }
+}
+
+/// a board read from a file, before it is in the database.
+/// deserializes from what `kk export --format json` writes
+#[derive(Debug, Deserialize)]
+struct ImportedBoard {
+    name: String,
+    sort_mode: Option<SortMode>,
+    columns: Vec<ImportedColumn>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportedColumn {
+    name: String,
+    stage: Option<Stage>,
+    /// top to bottom
+    #[serde(default)]
+    cards: Vec<ImportedCard>,
+}
+
+/// everything but the title is optional,
+/// and a card without a number gets the next one on its board
+#[derive(Debug, Default, Deserialize)]
+struct ImportedCard {
+    external_id: Option<ExternalCardId>,
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    due_on: Option<String>,
+    priority: Option<Priority>,
+    inserted_at: Option<String>,
+    updated_at: Option<String>,
+    doing_at: Option<String>,
+    done_at: Option<String>,
+    sent_from: Option<String>,
+}
+
+impl ImportedBoard {
+    fn from_json(text: &str) -> anyhow::Result<ImportedBoard> {
+        serde_json::from_str(text).map_err(|e| {
+            anyhow!(
+                "this doesn't look like a board kk exported ({}). if it came from Trello, use --format trello",
+                e
+            )
+        })
+    }
+
+    /// reads the columns `Board::write_csv` writes, in any order.
+    /// only `title` and `status` have to be there.
+    /// columns are in the order they first show up in, so empty ones are lost
+    fn from_csv(name: &str, text: &str) -> anyhow::Result<ImportedBoard> {
+        let mut rows = parse_csv(text)?.into_iter();
+
+        let header = rows.next().ok_or_else(|| anyhow!("the file is empty"))?;
+        let field_index = |name: &str| header.iter().position(|field| field == name);
+
+        let title_index =
+            field_index("title").ok_or_else(|| anyhow!("there is no title column"))?;
+        let status_index =
+            field_index("status").ok_or_else(|| anyhow!("there is no status column"))?;
+
+        let mut board = ImportedBoard {
+            name: name.to_string(),
+            sort_mode: None,
+            columns: vec![],
+        };
+
+        for (line, row) in rows.enumerate() {
+            // blank lines, like the one many editors leave at the end of a file
+            if row.iter().all(|field| field.is_empty()) {
+                continue;
+            }
+
+            let field = |name: &str| {
+                field_index(name)
+                    .and_then(|i| row.get(i))
+                    .filter(|field| !field.is_empty())
+                    .cloned()
+            };
+
+            let (Some(title), Some(status)) = (row.get(title_index), row.get(status_index)) else {
+                return Err(anyhow!("row {} is missing its title or status", line + 2));
+            };
+
+            let card = ImportedCard {
+                external_id: field("external_id")
+                    .map(|external_id| {
+                        external_id
+                            .parse()
+                            .map(ExternalCardId)
+                            .map_err(|_| anyhow!("{} is not a card number", external_id))
+                    })
+                    .transpose()?,
+                title: title.clone(),
+                body: field("body").unwrap_or_default(),
+                tags: field("tags")
+                    .map(|tags| {
+                        tags.split(',')
+                            .map(|tag| tag.trim().to_string())
+                            .filter(|tag| !tag.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                due_on: field("due_on"),
+                priority: field("priority")
+                    .map(|priority| priority.parse())
+                    .transpose()?,
+                inserted_at: field("inserted_at"),
+                updated_at: field("updated_at"),
+                doing_at: field("doing_at"),
+                done_at: field("done_at"),
+                sent_from: None,
+            };
+
+            if let Some(column) = board.columns.iter_mut().find(|c| &c.name == status) {
+                column.cards.push(card);
+            } else {
+                board.columns.push(ImportedColumn {
+                    name: status.clone(),
+                    stage: None,
+                    cards: vec![card],
+                });
+            }
+        }
+
+        Ok(board)
+    }
+
+    /// the column closed Trello cards, and cards in closed lists, go in.
+    /// if the board has an open list with this name, a number is added until it doesn't
+    const TRELLO_ARCHIVE: &str = "Archive";
+
+    /// lists become columns, in the order they are on the board,
+    /// and cards keep the numbers Trello gave them
+    fn from_trello(text: &str) -> anyhow::Result<ImportedBoard> {
+        #[derive(Deserialize)]
+        struct TrelloBoard {
+            name: String,
+            lists: Vec<TrelloList>,
+            cards: Vec<TrelloCard>,
+        }
+
+        #[derive(Deserialize)]
+        struct TrelloList {
+            id: String,
+            name: String,
+            #[serde(default)]
+            closed: bool,
+            #[serde(default)]
+            pos: f64,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct TrelloCard {
+            name: String,
+            #[serde(default)]
+            desc: String,
+            id_list: String,
+            #[serde(default)]
+            closed: bool,
+            #[serde(default)]
+            pos: f64,
+            #[serde(default)]
+            labels: Vec<TrelloLabel>,
+            due: Option<String>,
+            date_last_activity: Option<String>,
+            id_short: Option<i64>,
+        }
+
+        /// labels can have a color and no name
+        #[derive(Deserialize)]
+        struct TrelloLabel {
+            name: Option<String>,
+            color: Option<String>,
+        }
+
+        /// like 2024-01-02T03:04:05.678Z, which becomes 2024-01-02 03:04:05
+        fn timestamp(date: &str) -> Option<String> {
+            date.get(..19).map(|timestamp| timestamp.replace('T', " "))
+        }
+
+        let mut trello_board: TrelloBoard = serde_json::from_str(text)?;
+
+        // Trello due dates are in UTC, and the day they are due on is the local one.
+        // sqlite does the conversion everywhere else, so it does here too
+        let dates = Connection::open_in_memory()?;
+        let local_date = |due: &str| -> anyhow::Result<Option<String>> {
+            Ok(
+                dates.query_one("select date(?, 'localtime')", [timestamp(due)], |row| {
+                    row.get(0)
+                })?,
+            )
+        };
+
+        let open_list_names = trello_board
+            .lists
+            .iter()
+            .filter(|list| !list.closed)
+            .map(|list| list.name.as_str())
+            .collect::<std::collections::HashSet<_>>();
+
+        let archive = std::iter::once(Self::TRELLO_ARCHIVE.to_string())
+            .chain((2..).map(|n| format!("{} {}", Self::TRELLO_ARCHIVE, n)))
+            .find(|name| !open_list_names.contains(name.as_str()))
+            .expect("a board has fewer lists than there are numbers");
+
+        trello_board.lists.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+        trello_board.cards.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+
+        let mut board = ImportedBoard {
+            name: trello_board.name,
+            sort_mode: None,
+            columns: trello_board
+                .lists
+                .iter()
+                .filter(|list| !list.closed)
+                .map(|list| ImportedColumn {
+                    name: list.name.clone(),
+                    stage: None,
+                    cards: vec![],
+                })
+                .collect(),
+        };
+
+        for card in trello_board.cards {
+            let list = trello_board
+                .lists
+                .iter()
+                .find(|list| list.id == card.id_list);
+
+            let column_name = match list {
+                Some(list) if !list.closed && !card.closed => &list.name,
+                _ => &archive,
+            };
+
+            let mut tags = card
+                .labels
+                .into_iter()
+                .filter_map(|label| {
+                    label
+                        .name
+                        .filter(|name| !name.trim().is_empty())
+                        .or(label.color)
+                })
+                // Trello allows commas in label names, kk's tags don't
+                .map(|tag| tag.trim().replace(',', ";"))
+                .collect::<Vec<_>>();
+            tags.sort();
+            tags.dedup();
+
+            let imported_card = ImportedCard {
+                external_id: card.id_short.map(ExternalCardId),
+                title: card.name,
+                body: card.desc,
+                tags,
+                due_on: card.due.as_deref().map(local_date).transpose()?.flatten(),
+                updated_at: card.date_last_activity.as_deref().and_then(timestamp),
+                ..Default::default()
+            };
+
+            if let Some(column) = board.columns.iter_mut().find(|c| &c.name == column_name) {
+                column.cards.push(imported_card);
+            } else {
+                board.columns.push(ImportedColumn {
+                    name: column_name.to_string(),
+                    stage: None,
+                    cards: vec![imported_card],
+                });
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+impl ImportedBoard {
+    /// reads what `Board::write_obsidian` writes, or what the Obsidian Kanban plugin does.
+    /// `## ` headings are columns and list items under them are cards,
+    /// with indented lines under a card as its body.
+    /// `#tags`, `@{2024-12-31}` due dates and `^kk-3` card numbers come off the end of a card's first line.
+    /// everything else, like the plugin's settings, is left out
+    fn from_obsidian(name: &str, text: &str) -> ImportedBoard {
+        fn finish(card: Option<ImportedCard>, column: Option<&mut ImportedColumn>) {
+            if let (Some(mut card), Some(column)) = (card, column) {
+                card.body = card.body.trim_end().to_string();
+                column.cards.push(card);
+            }
+        }
+
+        let mut board = ImportedBoard {
+            name: name.to_string(),
+            sort_mode: None,
+            columns: vec![],
+        };
+
+        let mut card: Option<ImportedCard> = None;
+        let mut lines = text.lines().peekable();
+
+        // front matter
+        if lines.peek().is_some_and(|line| line.trim() == "---") {
+            lines.next();
+            for line in lines.by_ref() {
+                if line.trim() == "---" {
+                    break;
+                }
+            }
+        }
+
+        while let Some(line) = lines.next() {
+            if let Some(rest) = line.strip_prefix("%%") {
+                finish(card.take(), board.columns.last_mut());
+                if !rest.contains("%%") {
+                    for line in lines.by_ref() {
+                        if line.trim_end().ends_with("%%") {
+                            break;
+                        }
+                    }
+                }
+            } else if let Some(column_name) = line.strip_prefix("## ") {
+                finish(card.take(), board.columns.last_mut());
+                board.columns.push(ImportedColumn {
+                    name: column_name.trim().to_string(),
+                    stage: None,
+                    cards: vec![],
+                });
+            } else if let Some(item) = line.strip_prefix("- ")
+                && !board.columns.is_empty()
+            {
+                finish(card.take(), board.columns.last_mut());
+
+                let item = ["[ ] ", "[x] ", "[X] "]
+                    .iter()
+                    .find_map(|checkbox| item.strip_prefix(checkbox))
+                    .unwrap_or(item);
+
+                // the plugin writes line breaks in a card as <br>
+                let (first_line, body) = item.split_once("<br>").unwrap_or((item, ""));
+
+                let mut new_card = ImportedCard {
+                    body: body.replace("<br>", "\n"),
+                    ..Default::default()
+                };
+
+                let mut words = first_line.split_whitespace().collect::<Vec<_>>();
+
+                while let Some(word) = words.last() {
+                    if let Some(external_id) = word
+                        .strip_prefix("^kk-")
+                        .and_then(|external_id| external_id.parse().ok())
+                    {
+                        new_card.external_id = Some(ExternalCardId(external_id));
+                    } else if let Some(due_on) = word
+                        .strip_prefix("@{")
+                        .and_then(|due_on| due_on.strip_suffix('}'))
+                    {
+                        new_card.due_on = Some(due_on.to_string());
+                    } else if let Some(tag) = word.strip_prefix('#')
+                        && !tag.is_empty()
+                        && !tag.chars().all(|c| c.is_ascii_digit())
+                    {
+                        new_card.tags.push(decode_obsidian_tag(tag));
+                    } else {
+                        break;
+                    }
+                    words.pop();
+                }
+
+                // see `escape_obsidian_title`
+                if let Some(word) = words.last_mut()
+                    && let Some(escaped) = word.strip_prefix('\\')
+                    && is_obsidian_suffix(escaped.trim_start_matches('\\'))
+                {
+                    *word = escaped;
+                }
+
+                new_card.title = words.join(" ");
+                new_card.tags.sort();
+                new_card.tags.dedup();
+
+                card = Some(new_card);
+            } else if let Some(card) = &mut card
+                && (line.starts_with(['\t', ' ']) || line.trim().is_empty())
+            {
+                let line = line
+                    .strip_prefix('\t')
+                    .or_else(|| line.strip_prefix("    "))
+                    .unwrap_or(line.trim_start());
+
+                if !card.body.is_empty() || !line.is_empty() {
+                    if !card.body.is_empty() {
+                        card.body.push('\n');
+                    }
+                    card.body.push_str(line);
+                }
+            } else {
+                finish(card.take(), board.columns.last_mut());
+            }
+        }
+
+        finish(card.take(), board.columns.last_mut());
+
+        board
+    }
+}
+
+/// whether `ImportedBoard::from_obsidian` takes a word off the end of a card's first line
+/// as a tag, due date or card number
+fn is_obsidian_suffix(word: &str) -> bool {
+    word.strip_prefix("^kk-")
+        .is_some_and(|external_id| external_id.parse::<i64>().is_ok())
+        || (word.starts_with("@{") && word.ends_with('}'))
+        || word
+            .strip_prefix('#')
+            .is_some_and(|tag| !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// a title that ends in something like `#word` gets a `\` in front of that last word,
+/// so it stays part of the title when the file is read back
+fn escape_obsidian_title(title: &str) -> String {
+    let start = title
+        .trim_end()
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+
+    if is_obsidian_suffix(title[start..].trim().trim_start_matches('\\')) {
+        format!("{}\\{}", &title[..start], &title[start..])
+    } else {
+        title.to_string()
+    }
+}
+
+/// a tag ends at whitespace, so whitespace in a tag, and `%`, are percent-encoded,
+/// like `#needs%20review`
+fn encode_obsidian_tag(tag: &str) -> String {
+    let mut encoded = String::new();
+
+    for c in tag.chars() {
+        if c == '%' || c.is_whitespace() {
+            for byte in c.to_string().bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+
+    encoded
+}
+
+/// undoes `encode_obsidian_tag`. a `%` that isn't followed by two hex digits is left as it is
+fn decode_obsidian_tag(tag: &str) -> String {
+    let mut bytes = vec![];
+    let mut rest = tag.as_bytes();
+
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte == b'%'
+            && let Some(hex) = after.get(..2)
+            && hex.iter().all(u8::is_ascii_hexdigit)
+            && let Some(decoded) = std::str::from_utf8(hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            bytes.push(decoded);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+
+    String::from_utf8(bytes).unwrap_or_else(|_| tag.to_string())
+}
+
+/// rows of fields. fields can be quoted, with `""` for a quote,
+/// and quoted fields can have commas and newlines in them
+fn parse_csv(text: &str) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(anyhow!("the file ends in the middle of a quoted field"));
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// flow statistics for a board, in days.
+/// only cards that have been through `Stage` columns count
+#[derive(Debug, Default, PartialEq)]
+struct BoardMetrics {
+    /// `doing_at` to `done_at`, for every done card that was started
+    cycle_times: Vec<f64>,
+    /// `inserted_at` to `done_at`, for every done card
+    lead_times: Vec<f64>,
+    /// cards finished in each of the last `BoardMetrics::THROUGHPUT_WEEKS` weeks, oldest first
+    weekly_throughput: Vec<u64>,
+    /// cards currently in `Stage::Doing` columns
+    work_in_progress: u64,
+}
+
+impl BoardMetrics {
+    const THROUGHPUT_WEEKS: usize = 12;
+
+    /// upper bounds in days, and labels, of the cycle time histogram buckets
+    const CYCLE_TIME_BUCKETS: [(f64, &str); 6] = [
+        (1.0, "<1d"),
+        (2.0, "1-2d"),
+        (4.0, "2-4d"),
+        (8.0, "4-8d"),
+        (16.0, "8-16d"),
+        (f64::INFINITY, "16d+"),
+    ];
+
+    fn cycle_time_histogram(&self) -> Vec<(&'static str, u64)> {
+        let mut histogram = Self::CYCLE_TIME_BUCKETS
+            .iter()
+            .map(|(_, label)| (*label, 0))
+            .collect::<Vec<_>>();
+
+        for cycle_time in &self.cycle_times {
+            if let Some(i) = Self::CYCLE_TIME_BUCKETS
+                .iter()
+                .position(|(upper_bound, _)| cycle_time < upper_bound)
+            {
+                histogram[i].1 += 1;
+            }
+        }
+
+        histogram
+    }
+
+    /// nearest-rank percentile, `p` from 0 to 100
+    fn percentile(days: &[f64], p: f64) -> Option<f64> {
+        if days.is_empty() {
+            return None;
+        }
+
+        let mut sorted = days.to_vec();
+        sorted.sort_unstable_by(f64::total_cmp);
+
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+
+    fn mean(days: &[f64]) -> Option<f64> {
+        if days.is_empty() {
+            None
+        } else {
+            Some(days.iter().sum::<f64>() / days.len() as f64)
+        }
+    }
+
+    /// like "p50 1.5d, p85 3.0d, avg 2.1d"
+    fn summarize(days: &[f64]) -> String {
+        match (
+            Self::percentile(days, 50.0),
+            Self::percentile(days, 85.0),
+            Self::mean(days),
+        ) {
+            (Some(p50), Some(p85), Some(mean)) => {
+                format!("p50 {:.1}d, p85 {:.1}d, avg {:.1}d", p50, p85, mean)
+            }
+            _ => "no done cards yet".to_string(),
+        }
+    }
+}
+
+/// something that can be undone with `u` and redone with `ctrl-r`
+#[derive(Clone, Debug)]
+enum Operation {
+    Move {
+        card_id: CardId,
+        from: String,
+        to: String,
+    },
+    Edit {
+        card_id: CardId,
+        before: CardText,
+        after: CardText,
+    },
+    /// the whole card, so it can be restored where it was
+    Delete {
+        card: Card,
+        column: String,
+        index: usize,
+    },
+    /// operations done together on marked cards, in the order they were done
+    Batch(Vec<Operation>),
+}
+
+impl Operation {
+    /// the card that was touched last, once this has been applied
+    fn card_to_select(&self, reverse: bool) -> Option<CardId> {
+        match self {
+            Operation::Move { card_id, .. } | Operation::Edit { card_id, .. } => Some(*card_id),
+            Operation::Delete { card, .. } => reverse.then_some(card.id),
+            Operation::Batch(operations) => {
+                if reverse {
+                    operations
+                        .iter()
+                        .find_map(|operation| operation.card_to_select(true))
+                } else {
+                    operations
+                        .iter()
+                        .rev()
+                        .find_map(|operation| operation.card_to_select(false))
+                }
+            }
+        }
+    }
+
+    fn involves(&self, card_id: CardId) -> bool {
+        match self {
+            Operation::Move { card_id: id, .. } | Operation::Edit { card_id: id, .. } => {
+                *id == card_id
+            }
+            Operation::Delete { card, .. } => card.id == card_id,
+            Operation::Batch(operations) => operations
+                .iter()
+                .any(|operation| operation.involves(card_id)),
+        }
+    }
+
+    fn replace_card_id(&mut self, old_card_id: CardId, new_card_id: CardId) {
+        let card_id = match self {
+            Operation::Move { card_id, .. } | Operation::Edit { card_id, .. } => card_id,
+            Operation::Delete { card, .. } => &mut card.id,
+            Operation::Batch(operations) => {
+                for operation in operations {
+                    operation.replace_card_id(old_card_id, new_card_id);
+                }
+                return;
+            }
+        };
+
+        if *card_id == old_card_id {
+            *card_id = new_card_id;
+        }
+    }
+}
+
+/// a card that undoing an `Operation::Delete` put back at the top of its column,
+/// and where it was before it was deleted
+#[derive(Debug)]
+struct RestoredCard {
+    old_card_id: CardId,
+    card_id: CardId,
+    column: String,
+    index: usize,
+}
+
+/// what counts as overdue and due soon, as of when they were read.
+/// dates are local `YYYY-MM-DD` strings, so they compare as strings
+#[derive(Debug)]
+struct DueThresholds {
+    today: String,
+    soon: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum DueStatus {
+    Overdue,
+    Soon,
+    Later,
+}
+
+impl DueThresholds {
+    /// cards due within this many days of today are due soon
+    const SOON_DAYS: i64 = 3;
+
+    fn status(&self, due_on: &str) -> DueStatus {
+        if due_on < self.today.as_str() {
+            DueStatus::Overdue
+        } else if due_on <= self.soon.as_str() {
+            DueStatus::Soon
+        } else {
+            DueStatus::Later
+        }
+    }
+
+    fn style(&self, due_on: &str) -> Style {
+        match self.status(due_on) {
+            DueStatus::Overdue => Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD),
+            DueStatus::Soon => Style::default().fg(Color::Yellow),
+            DueStatus::Later => Style::default(),
+        }
+    }
+}
+
+/// a card with a due date, and where it lives
+#[derive(Debug)]
+struct AgendaItem {
+    board_id: BoardId,
+    board_name: String,
+    column: String,
+    card: Card,
+}
+
+/// the cards shown by `Mode::ViewingAgenda`, soonest due first
+#[derive(Debug)]
+struct Agenda {
+    items: Vec<AgendaItem>,
+    selected: usize,
+}
+
+#[derive(Debug)]
+struct SearchResult {
+    board_id: BoardId,
+    board_name: String,
+    column: String,
+    card: Card,
+    /// the best matching part of the card, with matches
+    /// between `SearchResult::MATCH_START` and `SearchResult::MATCH_END`
+    snippet: String,
+}
+
+impl SearchResult {
+    const MATCH_START: char = '\u{1}';
+    const MATCH_END: char = '\u{2}';
+
+    /// the snippet, with matches in `highlight`
+    fn snippet_line(&self, highlight: Style) -> Line<'_> {
+        let mut spans = vec![];
+
+        for (i, part) in self.snippet.split(Self::MATCH_START).enumerate() {
+            if i == 0 {
+                spans.push(Span::raw(part));
+            } else if let Some((matched, rest)) = part.split_once(Self::MATCH_END) {
+                spans.push(Span::styled(matched, highlight));
+                spans.push(Span::raw(rest));
+            } else {
+                spans.push(Span::styled(part, highlight));
+            }
+        }
+
+        Line::from(spans)
+    }
+}
+
+/// the state of `Mode::Searching`, best matches first
+#[derive(Debug, Default)]
+struct Search {
+    query: String,
+    results: Vec<SearchResult>,
+    selected: usize,
+}
+
+/// the state of `Mode::SendingCard`: first a board is chosen, then one of its columns
+#[derive(Debug)]
+struct SendCard {
+    /// every other board
+    boards: Vec<BoardMeta>,
+    board_index: usize,
+    /// `None` until a board has been chosen
+    column_index: Option<usize>,
+}
+
+/// a card that was changed somewhere else while it was open in `$EDITOR`,
+/// which `Mode::ResolvingConflict` asks what to do about
+#[derive(Debug)]
+struct EditConflict {
+    mine: CardText,
+    /// the card as it is in the database now
+    theirs: Card,
+    /// where to go back to once the conflict is resolved
+    mode: Mode,
+    /// an index into `EditConflict::CHOICES`
+    selected: usize,
+}
+
+impl EditConflict {
+    const CHOICES: [&str; 3] = ["keep mine", "keep theirs", "edit both"];
+
+    const THEIRS_START: &str =
+        "<<<<<<< theirs. copy what you want to keep, this is removed when you save";
+    const THEIRS_END: &str = ">>>>>>>";
+
+    /// my version, with theirs at the end of the body to copy from
+    fn to_editor_text(&self) -> String {
+        format!(
+            "{}\n\n{}\n{}\n{}\n",
+            self.mine.to_editor_text().trim_end(),
+            Self::THEIRS_START,
+            self.theirs.text().to_editor_text().trim_end(),
+            Self::THEIRS_END
+        )
+    }
+
+    /// takes out what `to_editor_text` added, if it is still there
+    fn remove_theirs(body: &str) -> String {
+        if let Some(start) = body.find(Self::THEIRS_START)
+            && let Some(end) = body[start..].find(Self::THEIRS_END)
+        {
+            let mut body = body.to_string();
+            body.replace_range(start..start + end + Self::THEIRS_END.len(), "");
+            body.trim_end().to_string()
+        } else {
+            body.to_string()
+        }
+    }
+}
+
+/// the tags offered by `Mode::ChoosingTagFilter`
+#[derive(Debug)]
+struct TagPicker {
+    tags: Vec<String>,
+    selected: usize,
+}
+
+/// the events shown by `Mode::ViewingActivity`,
+/// either for a single card or for a whole board
+#[derive(Debug)]
+struct Activity {
+    title: String,
+    events: Vec<CardEvent>,
+    selected: usize,
+    previous_mode: Mode,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct SelectedState {
+    board_index: Option<usize>,
+    column_index: Option<usize>,
+    card_index: Option<usize>,
+}
+
+enum Event {
+    KeyEvent(crossterm::event::KeyEvent),
+    InternalEvent(InternalEvent),
+}
+
+enum InternalEvent {
+    ClearError,
+    DatabaseChanged,
+}
+
+#[derive(Debug, Serialize)]
+struct Column {
+    name: String,
+    stage: Option<Stage>,
+    cards: Vec<Card>,
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Card {
+    #[serde(skip)]
+    id: CardId,
+    external_id: ExternalCardId,
+    title: String,
+    body: String,
+    inserted_at: String,
+    updated_at: String,
+    /// when the card first moved into a `Stage::Doing` column
+    doing_at: Option<String>,
+    /// when the card moved into a `Stage::Done` column, if it is still in one
+    done_at: Option<String>,
+    /// sorted by name
+    tags: Vec<String>,
+    due_on: Option<String>,
+    priority: Option<Priority>,
+    /// the board and number a card had before it was sent to this board
+    sent_from: Option<String>,
+}
+
+impl Card {
+    /// the columns `Card::from_row` expects, in order
+    const SQL_COLUMNS: &str = "
+        cards.id,
+        cards.external_id,
+        cards.title,
+        cards.body,
+        cards.inserted_at,
+        cards.updated_at,
+        cards.doing_at,
+        cards.done_at,
+        (
+            select
+                group_concat(tags.name, ',' order by tags.name)
+            from card_tags
+            inner join tags
+                on tags.id = card_tags.tag_id
+            where card_tags.card_id = cards.id
+        ),
+        cards.due_on,
+        cards.priority,
+        cards.sent_from";
+
+    /// the number of columns in `Card::SQL_COLUMNS`,
+    /// for reading anything selected after them
+    const SQL_COLUMNS_LEN: usize = 12;
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Card> {
+        Ok(Card {
+            id: row.get(0)?,
+            external_id: row.get(1)?,
+            title: row.get(2)?,
+            body: row.get(3)?,
+            inserted_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            doing_at: row.get(6)?,
+            done_at: row.get(7)?,
+            tags: row
+                .get::<_, Option<String>>(8)?
+                .map(|tags| tags.split(',').map(|tag| tag.to_string()).collect())
+                .unwrap_or_default(),
+            due_on: row.get(9)?,
+            priority: row.get(10)?,
+            sent_from: row.get(11)?,
+        })
+    }
+
+    /// case-insensitively, in the title or body.
+    /// everything matches an empty pattern
+    fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.to_lowercase();
+
+        self.title.to_lowercase().contains(&pattern) || self.body.to_lowercase().contains(&pattern)
+    }
+
+    fn text(&self) -> CardText {
+        CardText {
+            title: self.title.clone(),
+            body: self.body.clone(),
+            tags: self.tags.clone(),
+            due_on: self.due_on.clone(),
+            priority: self.priority,
+        }
+    }
+}
+
+/// the parts of a card that are edited in `$EDITOR`
+#[derive(Clone, Debug, PartialEq)]
+struct CardText {
+    title: String,
+    body: String,
+    tags: Vec<String>,
+    due_on: Option<String>,
+    priority: Option<Priority>,
+}
+
+impl CardText {
+    /// fields go between the title and the body, one per line
+    fn to_editor_text(&self) -> String {
+        format!(
+            "{}\n==========\npriority: {}\ntags: {}\ndue: {}\n\n{}",
+            self.title,
+            self.priority
+                .map(|priority| priority.to_string())
+                .unwrap_or_default(),
+            self.tags.join(", "),
+            self.due_on.as_deref().unwrap_or(""),
+            self.body
+        )
+    }
+}
+
+/// a card as it was when something happened to it
+#[derive(Debug)]
+struct CardEvent {
+    external_id: ExternalCardId,
+    kind: CardEventKind,
+    title: String,
+    body: String,
+    /// the column a card moved out of, for `CardEventKind::Moved`
+    previous_status: Option<String>,
+    /// the column the card was in after the event
+    status: String,
+    /// where a card was sent to or received from,
+    /// for `CardEventKind::Sent` and `CardEventKind::Received`
+    other_board: Option<String>,
+    inserted_at: String,
+}
+
+impl CardEvent {
+    /// the columns `CardEvent::from_row` expects, in order
+    const SQL_COLUMNS: &str = "
+        card_events.external_id,
+        card_events.kind,
+        card_events.title,
+        card_events.body,
+        card_events.previous_status,
+        card_events.status,
+        card_events.inserted_at,
+        card_events.other_board";
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<CardEvent> {
+        Ok(CardEvent {
+            external_id: row.get(0)?,
+            kind: row.get(1)?,
+            title: row.get(2)?,
+            body: row.get(3)?,
+            previous_status: row.get(4)?,
+            status: row.get(5)?,
+            inserted_at: row.get(6)?,
+            other_board: row.get(7)?,
+        })
+    }
+
+    fn describe(&self) -> String {
+        match (self.kind, &self.previous_status) {
+            (CardEventKind::Created, _) => format!("created in {}", self.status),
+            (CardEventKind::Moved, Some(previous_status)) => {
+                format!("moved {} → {}", previous_status, self.status)
+            }
+            (CardEventKind::Moved, None) => format!("moved to {}", self.status),
+            (CardEventKind::Edited, _) => "edited".to_string(),
+            (CardEventKind::Deleted, _) => format!("deleted from {}", self.status),
+            (CardEventKind::Restored, _) => format!("restored to {}", self.status),
+            (CardEventKind::Sent, _) => format!(
+                "sent from {} to {}",
+                self.status,
+                self.other_board.as_deref().unwrap_or("another board")
+            ),
+            (CardEventKind::Received, _) => format!(
+                "sent here from {}, into {}",
+                self.other_board.as_deref().unwrap_or("another board"),
+                self.status
+            ),
+            (CardEventKind::Imported, _) => format!("imported into {}", self.status),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+enum RunningState {
+    #[default]
+    Running,
+    Done,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Mode {
+    #[default]
+    ViewingBoard,
+    ViewingCardDetail,
+    MovingCard,
+    ViewingBoards,
+    ConfirmCardDeletion,
+    ConfirmBoardDeletion,
+    ChoosingColumnDestination,
+    ViewingMetrics,
+    ViewingActivity,
+    ChoosingTagFilter,
+    ViewingAgenda,
+    Searching,
+    FilteringBoard,
+    JumpingToCard,
+    SendingCard,
+    SelectingCards,
+    TaggingCards,
+    ExportingBoard,
+    ResolvingConflict,
+}
+
+#[derive(Debug, PartialEq)]
+enum Message {
+    NavigateLeft,
+    NavigateDown,
+    NavigateUp,
+    NavigateRight,
+    Quit,
+    NewCard,
+    MoveCardMode,
+    MoveCardLeft,
+    MoveCardDown,
+    MoveCardUp,
+    MoveCardRight,
+    /// the index of the column, counting from 0
+    MoveCardToColumn(usize),
+    EditCard,
+    ViewBoardMode,
+    ViewCardDetailMode,
+    SetError(Option<String>),
+    ReloadChangedData,
+    ViewBoardsMode,
+    EditBoard,
+    NewBoard,
+    DeleteCard,
+    ConfirmChoice,
+    DeleteBoard,
+    ToggleArchiveBoard,
+    ToggleArchivedBoardsView,
+    ViewMetricsMode,
+    ViewCardHistoryMode,
+    ViewBoardActivityMode,
+    Undo,
+    Redo,
+    FilterByTagMode,
+    ClearTagFilter,
+    CycleSortMode,
+    ViewAgendaMode,
+    SearchMode,
+    FilterBoardMode,
+    JumpToCardMode,
+    SendCardMode,
+    SelectCardsMode,
+    ToggleMark,
+    TagCardsMode,
+    ExportBoardMode,
+    ClearBoardFilter,
+    NextMatch,
+    PreviousMatch,
+    InputChar(char),
+    DeleteChar,
+}
+
+fn run_editor<B>(terminal: &mut Terminal<B>, template_text: &str) -> anyhow::Result<String>
+where
+    B: Backend,
+    B::Error: Send + Sync + 'static,
+{
+    std::io::stdout().execute(crossterm::terminal::LeaveAlternateScreen)?;
+    crossterm::terminal::disable_raw_mode()?;
+
+    let edited_text = edit_text(template_text);
+
+    std::io::stdout().execute(crossterm::terminal::EnterAlternateScreen)?;
+    crossterm::terminal::enable_raw_mode()?;
+    terminal.clear()?;
+
+    edited_text
+}
+
+/// open `$EDITOR` on a tempfile containing `template_text`
+/// and return whatever the user saved.
+/// does not touch the terminal, so it is usable outside of the TUI
+fn edit_text(template_text: &str) -> anyhow::Result<String> {
+    let path = {
+        let tempfile = tempfile::Builder::new();
+        let mut f = tempfile.tempfile()?;
+        f.write_all(template_text.as_bytes())?;
+        f.into_temp_path()
+    };
+
+    let editor = std::env::var("EDITOR")?;
+
+    Command::new(editor).arg(&path).status()?;
+
+    let edited_text = std::fs::read_to_string(&path)?;
+
+    path.close()?;
+
+    Ok(edited_text)
+}
+
+fn view(model: &mut Model, frame: &mut ratatui::Frame) {
+    match model.mode {
+        Mode::ViewingBoard
+        | Mode::ViewingCardDetail
+        | Mode::MovingCard
+        | Mode::ConfirmCardDeletion
+        | Mode::ChoosingTagFilter
+        | Mode::Searching
+        | Mode::FilteringBoard
+        | Mode::JumpingToCard
+        | Mode::SendingCard
+        | Mode::SelectingCards
+        | Mode::TaggingCards
+        | Mode::ExportingBoard
+        | Mode::ResolvingConflict => view_board(model, frame),
+        Mode::ViewingBoards | Mode::ConfirmBoardDeletion | Mode::ChoosingColumnDestination => {
+            view_boards(model, frame)
+        }
+        Mode::ViewingMetrics => view_metrics(model, frame),
+        Mode::ViewingActivity => view_activity(model, frame),
+        Mode::ViewingAgenda => view_agenda(model, frame),
+    }
+}
+
+fn view_metrics(model: &mut Model, frame: &mut ratatui::Frame) {
+    if let Some(board) = &model.board
+        && let Some(metrics) = &model.metrics
+    {
+        let [
+            summary_layout,
+            cycle_times_layout,
+            throughput_layout,
+            modeline_layout,
+        ] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Max(4),
+                Constraint::Min(8),
+                Constraint::Min(5),
+                Constraint::Max(3),
+            ])
+            .areas(frame.area());
+
+        let summary = Paragraph::new(vec![
+            Line::from(format!(
+                "done: {} │ in progress: {}",
+                metrics.lead_times.len(),
+                metrics.work_in_progress
+            )),
+            Line::from(format!(
+                "cycle time: {}",
+                BoardMetrics::summarize(&metrics.cycle_times)
+            )),
+            Line::from(format!(
+                "lead time: {}",
+                BoardMetrics::summarize(&metrics.lead_times)
+            )),
+        ])
+        .block(Block::new().padding(Padding::horizontal(1)));
+
+        let cycle_time_histogram = metrics.cycle_time_histogram();
+
+        let cycle_times = ratatui::widgets::BarChart::default()
+            .data(&cycle_time_histogram)
+            .bar_width(6)
+            .bar_gap(2)
+            .bar_style(Style::default().fg(model.highlight_color))
+            .block(
+                Block::bordered()
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Black))
+                    .title("cycle time distribution"),
+            );
+
+        let throughput = ratatui::widgets::Sparkline::default()
+            .data(&metrics.weekly_throughput)
+            .style(Style::default().fg(model.highlight_color))
+            .block(
+                Block::bordered()
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Black))
+                    .title(format!(
+                        "weekly throughput, last {} weeks: {}",
+                        BoardMetrics::THROUGHPUT_WEEKS,
+                        metrics
+                            .weekly_throughput
+                            .iter()
+                            .map(|count| count.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )),
+            );
+
+        frame.render_widget(summary, summary_layout);
+        frame.render_widget(cycle_times, cycle_times_layout);
+        frame.render_widget(throughput, throughput_layout);
+
+        let modeline_block = Block::new()
+            .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+            .title(Line::from("VIEWING METRICS").left_aligned())
+            .title(Line::from(&*board.name).right_aligned());
+
+        let modeline_text = if let Some(e) = &model.error {
+            format!(" - Error: {}", e.replace("\n", " "))
+        } else {
+            [("[s|enter|esc]", "back to board"), ("[q]", "quit")]
+                .iter()
+                .map(|(k, action)| format!("{} - {}", k, action))
+                .collect::<Vec<_>>()
+                .join(" │ ")
+        };
+
+        let modeline = Paragraph::new(modeline_text).block(modeline_block);
+
+        frame.render_widget(modeline, modeline_layout);
+    }
+}
+
+fn view_agenda(model: &mut Model, frame: &mut ratatui::Frame) {
+    if let Some(agenda) = &model.agenda {
+        let [title_layout, items_layout, modeline_layout] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Max(1), Constraint::Min(1), Constraint::Max(3)])
+            .areas(frame.area());
+
+        let mut state = ListState::default().with_selected(if agenda.items.is_empty() {
+            None
+        } else {
+            Some(agenda.selected)
+        });
+
+        let list_items = agenda
+            .items
+            .iter()
+            .map(|item| {
+                let due_on = item.card.due_on.as_deref().unwrap_or_default();
+
+                ListItem::new(format!(
+                    "{:<12}{:<20}{} {} ({})",
+                    due_on, item.board_name, item.card.external_id, item.card.title, item.column
+                ))
+                .style(model.due_thresholds.style(due_on))
+            })
+            .collect::<Vec<_>>();
+
+        let list = List::new(list_items)
+            .highlight_symbol("> ")
+            .highlight_style(Style::default().fg(model.highlight_color))
+            .block(
+                Block::new()
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+                    .border_style(Style::default().fg(Color::Black)),
+            );
+
+        frame.render_widget(
+            Paragraph::new(if agenda.items.is_empty() {
+                "Nothing is due".to_string()
+            } else {
+                format!("Due, as of {}", model.due_thresholds.today)
+            }),
+            title_layout,
+        );
+        frame.render_stateful_widget(list, items_layout, &mut state);
+
+        let modeline_block = Block::new()
+            .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+            .title(Line::from("VIEWING AGENDA").left_aligned());
+
+        let modeline_text = if let Some(e) = &model.error {
+            format!(" - Error: {}", e.replace("\n", " "))
+        } else {
+            [
+                ("[j/down]", "down"),
+                ("[k/up]", "up"),
+                ("[enter]", "view card on its board"),
+                ("[g|esc]", "back"),
+                ("[q]", "quit"),
+            ]
+            .iter()
+            .map(|(k, action)| format!("{} - {}", k, action))
+            .collect::<Vec<_>>()
+            .join(" │ ")
+        };
+
+        let modeline = Paragraph::new(modeline_text).block(modeline_block);
+
+        frame.render_widget(modeline, modeline_layout);
+    }
+}
+
+fn view_activity(model: &mut Model, frame: &mut ratatui::Frame) {
+    if let Some(activity) = &model.activity {
+        let [main_layout, modeline_layout] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Max(3)])
+            .areas(frame.area());
+
+        let [events_layout, snapshot_layout] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(main_layout);
+
+        let mut state = ListState::default().with_selected(if activity.events.is_empty() {
+            None
+        } else {
+            Some(activity.selected)
+        });
+
+        let list_items = activity
+            .events
+            .iter()
+            .map(|event| {
+                ListItem::new(format!(
+                    "{}  {} {}",
+                    event.inserted_at,
+                    event.external_id,
+                    event.describe()
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let list = List::new(list_items)
+            .highlight_symbol("> ")
+            .highlight_style(Style::default().fg(model.highlight_color))
+            .block(
+                Block::bordered()
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Black)),
+            );
+
+        frame.render_stateful_widget(list, events_layout, &mut state);
+
+        // what the card said at the time
+        if let Some(event) = activity.events.get(activity.selected) {
+            let block = Block::bordered()
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Black))
+                .title(Line::from(format!(
+                    "{} {}",
+                    event.external_id,
+                    event.kind.as_str()
+                )))
+                .title(Line::from(&*event.inserted_at).right_aligned())
+                .padding(Padding::horizontal(1));
+
+            let title_style = Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+            let wrapped = textwrap::wrap(
+                &event.body,
+                (snapshot_layout.width as usize).saturating_sub(4).max(1),
+            );
+
+            let mut lines = vec![Line::styled(&*event.title, title_style), Line::from("")];
+            lines.extend(wrapped.iter().map(|line| Line::from(line.to_string())));
+
+            frame.render_widget(Paragraph::new(lines).block(block), snapshot_layout);
+        }
+
+        let modeline_block = Block::new()
+            .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+            .title(Line::from("VIEWING ACTIVITY").left_aligned())
+            .title(Line::from(&*activity.title).right_aligned());
+
+        let modeline_text = if let Some(e) = &model.error {
+            format!(" - Error: {}", e.replace("\n", " "))
+        } else {
+            [
+                ("[j/down]", "older"),
+                ("[k/up]", "newer"),
+                ("[enter|esc]", "back to board"),
+                ("[q]", "quit"),
+            ]
+            .iter()
+            .map(|(k, action)| format!("{} - {}", k, action))
+            .collect::<Vec<_>>()
+            .join(" │ ")
+        };
+
+        let modeline = Paragraph::new(modeline_text).block(modeline_block);
+
+        frame.render_widget(modeline, modeline_layout);
+    }
+}
+
+fn view_boards(model: &mut Model, frame: &mut ratatui::Frame<'_>) {
+    let [title_layout, boards_layout, modeline_layout] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Max(1), Constraint::Min(1), Constraint::Max(3)])
+        .areas(frame.area());
+
+    let mut state = ListState::default().with_selected(model.selected.board_index);
+
+    let list_items = model
+        .board_metas
+        .iter()
+        .map(|board| {
+            ListItem::new(format!(
+                "{:<30}{:<30}{:<30}{:<30}",
+                &*board.name, &*board.updated_at, &*board.viewed_at, &*board.inserted_at
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    let list = List::new(list_items)
+        .highlight_symbol("> ")
+        .highlight_style(Style::default().fg(model.highlight_color))
+        .block(
+            Block::new()
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+                .border_style(Style::default().fg(Color::Black))
+                .title(
+                    "──name──────────────────────────last updated──────────────────last viewed───────────────────created",
+                ),
+        );
+
+    frame.render_widget(
+        Paragraph::new(if model.showing_archived_boards {
+            "Archived boards"
+        } else {
+            "Boards"
+        }),
+        title_layout,
+    );
+    frame.render_stateful_widget(list, boards_layout, &mut state);
+
+    if model.mode == Mode::ConfirmBoardDeletion
+        && let Some(board_index) = model.selected.board_index
+    {
+        view_confirmation_popup(
+            frame,
+            &format!("Delete {}", &model.board_metas[board_index].name),
+            &model.confirmation_state,
+        );
+    }
+
+    if model.mode == Mode::ChoosingColumnDestination
+        && let Some(board_edit) = &model.board_edit
+        && let Some(removal) = board_edit.undecided_removal()
+    {
+        let area = {
+            let [area] = Layout::vertical([Constraint::Percentage(50)])
+                .flex(Flex::Center)
+                .areas(frame.area());
+            let [area] = Layout::horizontal([Constraint::Percentage(40)])
+                .flex(Flex::Center)
+                .areas(area);
+            area
+        };
+
+        let mut state = ListState::default().with_selected(Some(board_edit.destination_index));
+
+        let list = List::new(
+            board_edit
+                .columns
+                .iter()
+                .map(|column| ListItem::new(&**column)),
+        )
+        .highlight_symbol("> ")
+        .highlight_style(Style::default().fg(model.highlight_color))
+        .block(
+            Block::bordered()
+                .title(format!("Move the cards in {} to", removal.column))
+                .title_style(Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
+                .padding(Padding::uniform(1)),
+        );
+
+        frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    let modeline_block = Block::new()
+        .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+        .title(
+            Line::from(match model.mode {
+                Mode::ViewingBoards => "VIEWING BOARDS",
+                Mode::ConfirmBoardDeletion => "DELETING BOARD",
+                Mode::ChoosingColumnDestination => "REMOVING COLUMN",
+                _ => unreachable!(),
+            })
+            .left_aligned(),
+        );
+
+    let modeline_text = {
+        let mut modeline_text = String::new();
+
+        if let Some(e) = &model.error {
+            modeline_text.push_str(" - Error: ");
+            modeline_text.push_str(&e.replace("\n", " "));
+        } else {
+            let formatted = match model.mode {
+                Mode::ViewingBoards => [
+                    ("[j/down]", "down"),
+                    ("[k/up]", "up"),
+                    ("[enter]", "view board"),
+                    ("[n]", "new board"),
+                    ("[e]", "edit board"),
+                    ("[d]", "delete board"),
+                    ("[g]", "agenda"),
+                    (
+                        "[a]",
+                        if model.showing_archived_boards {
+                            "unarchive board"
+                        } else {
+                            "archive board"
+                        },
+                    ),
+                    (
+                        "[A]",
+                        if model.showing_archived_boards {
+                            "view boards"
+                        } else {
+                            "view archived boards"
+                        },
+                    ),
+                    ("[q]", "quit"),
+                ]
+                .iter()
+                .map(|(k, action)| format!("{} - {}", k, action))
+                .collect::<Vec<_>>(),
+                Mode::ConfirmBoardDeletion => [
+                    ("[h/left]", "left"),
+                    ("[l/right]", "right"),
+                    ("[enter]", "confirm selection"),
+                ]
+                .iter()
+                .map(|(k, action)| format!("{} - {}", k, action))
+                .collect::<Vec<_>>(),
+                Mode::ChoosingColumnDestination => [
+                    ("[j/down]", "down"),
+                    ("[k/up]", "up"),
+                    ("[enter]", "move cards here"),
+                    ("[esc]", "cancel edit"),
+                ]
+                .iter()
+                .map(|(k, action)| format!("{} - {}", k, action))
+                .collect::<Vec<_>>(),
+                _ => unreachable!(),
+            };
+
+            modeline_text.push_str(&formatted.join(" │ "));
+        }
+
+        modeline_text
+    };
+
+    let modeline = Paragraph::new(modeline_text).block(modeline_block);
+
+    frame.render_widget(modeline, modeline_layout);
+}
+
+fn view_board(model: &mut Model, frame: &mut ratatui::Frame) {
+    if let Some(board) = &model.board {
+        let [columns_layout, modeline_layout] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Max(3)])
+            .areas(frame.area());
+
+        let columns_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(std::iter::repeat_n(
+                Constraint::Ratio(1, board.columns.len().try_into().unwrap()),
+                board.columns.len(),
+            ))
+            .split(columns_layout);
+
+        for (i, column) in board.columns.iter().enumerate() {
+            let column_layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Max(1), Constraint::Min(5)])
+                .split(columns_layout[i]);
+
+            // numbered while moving a card, for moving it straight there
+            if matches!(model.mode, Mode::MovingCard | Mode::SelectingCards) && i < 9 {
+                frame.render_widget(
+                    Paragraph::new(format!("{} {}", i + 1, column.name)),
+                    column_layout[0],
+                );
+            } else {
+                frame.render_widget(Paragraph::new(&*column.name), column_layout[0]);
+            }
+
+            let mut state = if i == model.selected.column_index.unwrap() {
+                ListState::default().with_selected(model.selected.card_index)
+            } else {
+                ListState::default().with_selected(None)
+            };
+
+            let list_items = column
+                .cards
+                .iter()
+                .map(|card| {
+                    let s = if let Some(priority) = card.priority {
+                        format!("{} {} {}", priority, card.external_id, card.title)
+                    } else {
+                        format!("{} {}", card.external_id, card.title)
+                    };
+                    let mut text = Text::from(textwrap::fill(
+                        &s,
+                        (column_layout[1].width as usize).saturating_sub(4),
+                    ));
+                    if !card.tags.is_empty() {
+                        text.push_line(tag_chips(&card.tags));
+                    }
+                    if let Some(due_on) = &card.due_on {
+                        // overdue and due soon cards stand out, but their tags keep their colors
+                        text.push_line(Line::from(format!("due {}", due_on)));
+                        text = text.style(model.due_thresholds.style(due_on));
+                    }
+                    if let Some(pattern) = &model.board_filter
+                        && !card.matches(pattern)
+                    {
+                        text = text
+                            .style(Style::new().fg(Color::DarkGray).add_modifier(Modifier::DIM));
+                    }
+                    if model.marked.contains(&card.id) {
+                        text = text.patch_style(Style::new().add_modifier(Modifier::REVERSED));
+                    }
+                    ListItem::new(text)
+                })
+                .collect::<Vec<_>>();
+
+            let list = List::new(list_items)
+                .highlight_symbol("> ")
+                .highlight_style(Style::default().fg(model.highlight_color))
+                .block(
+                    Block::new()
+                        .border_type(ratatui::widgets::BorderType::Rounded)
+                        .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+                        .border_style(Style::default().fg(Color::Black)),
+                );
+
+            frame.render_stateful_widget(list, column_layout[1], &mut state);
+        }
+
+        if model.mode == Mode::ViewingCardDetail
+            && let Some(card) = model.selected_card()
+        {
+            let block = Block::bordered()
+                .title(Line::from(card.external_id.to_string()).left_aligned())
+                .title(
+                    Line::from(format!(
+                        "created {}, updated {}",
+                        card.inserted_at, card.updated_at
+                    ))
+                    .right_aligned(),
+                )
+                .title_bottom(
+                    Line::from(
+                        [
+                            card.sent_from
+                                .as_ref()
+                                .map(|from| format!("sent from {}", from)),
+                            card.due_on.as_ref().map(|on| format!("due {}", on)),
+                            card.doing_at.as_ref().map(|at| format!("started {}", at)),
+                            card.done_at.as_ref().map(|at| format!("done {}", at)),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    )
+                    .right_aligned(),
+                )
+                .padding(Padding::uniform(1));
+
+            let title_style = Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+            let area = popup_area(frame.area(), 60, 50);
+
+            let wrapped = textwrap::wrap(&card.body, area.width as usize);
+
+            let body = wrapped.iter().map(|line| Line::from(line.to_string()));
+
+            let mut lines = vec![Line::styled(&*card.title, title_style)];
+            if !card.tags.is_empty() {
+                lines.push(tag_chips(&card.tags));
+            }
+            lines.push(Line::from("\n\n"));
+            lines.extend(body);
+
+            let paragraph = Paragraph::new(lines).block(block);
+
+            frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
+            frame.render_widget(paragraph, area);
+
+            /// helper function to create a centered rect using up certain percentage of the available rect `r`
+            fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+                let vertical =
+                    Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+                let horizontal =
+                    Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+                let [area] = vertical.areas(area);
+                let [area] = horizontal.areas(area);
+                area
+            }
+        }
+
+        if model.mode == Mode::ChoosingTagFilter
+            && let Some(tag_picker) = &model.tag_picker
+        {
+            let area = {
+                let [area] = Layout::vertical([Constraint::Percentage(50)])
+                    .flex(Flex::Center)
+                    .areas(frame.area());
+                let [area] = Layout::horizontal([Constraint::Percentage(40)])
+                    .flex(Flex::Center)
+                    .areas(area);
+                area
+            };
+
+            let mut state = ListState::default().with_selected(Some(tag_picker.selected));
+
+            let list = List::new(
+                tag_picker
+                    .tags
+                    .iter()
+                    .map(|tag| ListItem::new(tag_chips(std::slice::from_ref(tag)))),
+            )
+            .highlight_symbol("> ")
+            .block(
+                Block::bordered()
+                    .title("Show only cards tagged")
+                    .title_style(Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
+                    .padding(Padding::uniform(1)),
+            );
+
+            frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
+            frame.render_stateful_widget(list, area, &mut state);
+        }
+
+        if model.mode == Mode::ResolvingConflict
+            && let Some(conflict) = &model.conflict
+        {
+            let area = {
+                let [area] = Layout::vertical([Constraint::Percentage(50)])
+                    .flex(Flex::Center)
+                    .areas(frame.area());
+                let [area] = Layout::horizontal([Constraint::Percentage(50)])
+                    .flex(Flex::Center)
+                    .areas(area);
+                area
+            };
+
+            let mut state = ListState::default().with_selected(Some(conflict.selected));
+
+            let list = List::new([
+                ListItem::new(format!("keep mine: {}", conflict.mine.title)),
+                ListItem::new(format!("keep theirs: {}", conflict.theirs.title)),
+                ListItem::new("edit both in $EDITOR"),
+            ])
+            .highlight_symbol("> ")
+            .block(
+                Block::bordered()
+                    .title(format!(
+                        "{} was changed while you were editing it",
+                        conflict.theirs.external_id
+                    ))
+                    .title_style(Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
+                    .padding(Padding::uniform(1)),
+            );
+
+            frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
+            frame.render_stateful_widget(list, area, &mut state);
+        }
+
+        if model.mode == Mode::ExportingBoard
+            && let Some(format_index) = model.export_format
+        {
+            let area = {
+                let [area] = Layout::vertical([Constraint::Percentage(50)])
+                    .flex(Flex::Center)
+                    .areas(frame.area());
+                let [area] = Layout::horizontal([Constraint::Percentage(40)])
+                    .flex(Flex::Center)
+                    .areas(area);
+                area
+            };
+
+            let mut state = ListState::default().with_selected(Some(format_index));
+
+            let list = List::new(
+                ExportFormat::ALL
+                    .iter()
+                    .map(|format| ListItem::new(format!("{} (.{})", format, format.extension()))),
+            )
+            .highlight_symbol("> ")
+            .block(
+                Block::bordered()
+                    .title(
+                        match model
+                            .export_dir
+                            .clone()
+                            .or_else(|| std::env::current_dir().ok())
+                        {
+                            Some(export_dir) => {
+                                format!("Export {} to {}", board.name, export_dir.display())
+                            }
+                            None => format!("Export {}", board.name),
+                        },
+                    )
+                    .title_style(Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
+                    .padding(Padding::uniform(1)),
+            );
+
+            frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
+            frame.render_stateful_widget(list, area, &mut state);
+        }
+
+        if model.mode == Mode::SendingCard
+            && let Some(send_card) = &model.send_card
+            && let Some(card) = model.selected_card()
+            && let Some(board_meta) = send_card.boards.get(send_card.board_index)
+        {
+            let area = {
+                let [area] = Layout::vertical([Constraint::Percentage(50)])
+                    .flex(Flex::Center)
+                    .areas(frame.area());
+                let [area] = Layout::horizontal([Constraint::Percentage(40)])
+                    .flex(Flex::Center)
+                    .areas(area);
+                area
+            };
+
+            let (title, items, selected) = if let Some(column_index) = send_card.column_index {
+                (
+                    format!(
+                        "Send {} to which column of {}",
+                        card.external_id, board_meta.name
+                    ),
+                    board_meta.columns.clone(),
+                    column_index,
+                )
+            } else {
+                (
+                    format!("Send {} to", card.external_id),
+                    send_card
+                        .boards
+                        .iter()
+                        .map(|board_meta| board_meta.name.clone())
+                        .collect(),
+                    send_card.board_index,
+                )
+            };
+
+            let mut state = ListState::default().with_selected(Some(selected));
+
+            let list = List::new(items)
+                .highlight_symbol("> ")
+                .highlight_style(Style::default().fg(model.highlight_color))
+                .block(
+                    Block::bordered()
+                        .title(title)
+                        .title_style(
+                            Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                        )
+                        .padding(Padding::uniform(1)),
+                );
+
+            frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
+            frame.render_stateful_widget(list, area, &mut state);
+        }
+
+        if model.mode == Mode::Searching
+            && let Some(search) = &model.search
+        {
+            let area = {
+                let [area] = Layout::vertical([Constraint::Percentage(70)])
+                    .flex(Flex::Center)
+                    .areas(frame.area());
+                let [area] = Layout::horizontal([Constraint::Percentage(80)])
+                    .flex(Flex::Center)
+                    .areas(area);
+                area
+            };
+
+            let block = Block::bordered()
+                .title("Search all boards")
+                .title_style(Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
+                .padding(Padding::horizontal(1));
+
+            let [query_layout, results_layout] =
+                Layout::vertical([Constraint::Length(2), Constraint::Min(1)])
+                    .areas(block.inner(area));
+
+            let mut state = ListState::default().with_selected(if search.results.is_empty() {
+                None
+            } else {
+                Some(search.selected)
+            });
+
+            let highlight = Style::new()
+                .fg(model.highlight_color)
+                .add_modifier(Modifier::BOLD);
+
+            let list = List::new(search.results.iter().map(|result| {
+                ListItem::new(Text::from(vec![
+                    Line::from(format!(
+                        "{} {} ({} › {})",
+                        result.card.external_id,
+                        result.card.title,
+                        result.board_name,
+                        result.column
+                    )),
+                    result.snippet_line(highlight),
+                ]))
+            }))
+            .highlight_symbol("> ")
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+            frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
+            frame.render_widget(block, area);
+            frame.render_widget(
+                Paragraph::new(if search.query.is_empty() || !search.results.is_empty() {
+                    format!("?{}", search.query)
+                } else {
+                    format!("?{}  (no matches)", search.query)
+                }),
+                query_layout,
+            );
+            frame.render_stateful_widget(list, results_layout, &mut state);
+        }
+
+        if model.mode == Mode::ConfirmCardDeletion && !model.marked.is_empty() {
+            view_confirmation_popup(
+                frame,
+                &format!("Delete {} cards", model.marked.len()),
+                &model.confirmation_state,
+            );
+        } else if model.mode == Mode::ConfirmCardDeletion
+            && let Some(card) = model.selected_card()
+        {
+            view_confirmation_popup(
+                frame,
+                &format!("Delete {}", &card.title),
+                &model.confirmation_state,
+            );
+        }
+
+        let modeline_block = Block::new()
+            .borders(Borders::TOP | Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+            .title(
+                Line::from(match model.mode {
+                    Mode::ViewingBoard => "VIEWING BOARD",
+                    Mode::ViewingCardDetail => "VIEWING CARD",
+                    Mode::MovingCard => "MOVING CARD",
+                    Mode::ConfirmCardDeletion => "DELETING CARD",
+                    Mode::ViewingBoards => "VIEWING BOARDS",
+                    Mode::ConfirmBoardDeletion => "DELETING BOARD",
+                    Mode::ChoosingColumnDestination => "REMOVING COLUMN",
+                    Mode::ViewingMetrics => "VIEWING METRICS",
+                    Mode::ViewingActivity => "VIEWING ACTIVITY",
+                    Mode::ChoosingTagFilter => "FILTERING BY TAG",
+                    Mode::ViewingAgenda => "VIEWING AGENDA",
+                    Mode::Searching => "SEARCHING",
+                    Mode::FilteringBoard => "FILTERING",
+                    Mode::JumpingToCard => "GOING TO CARD",
+                    Mode::SendingCard => "SENDING CARD",
+                    Mode::SelectingCards => "SELECTING CARDS",
+                    Mode::TaggingCards => "TAGGING CARDS",
+                    Mode::ExportingBoard => "EXPORTING BOARD",
+                    Mode::ResolvingConflict => "RESOLVING CONFLICT",
+                })
+                .left_aligned(),
+            )
+            .title(
+                Line::from({
+                    let mut title = board.name.clone();
+                    if let Some(tag) = &model.tag_filter {
+                        title.push_str(&format!(" (tagged {})", tag));
+                    }
+                    if let Some(pattern) = &model.board_filter
+                        && !pattern.is_empty()
+                    {
+                        title.push_str(&format!(" (matching {})", pattern));
+                    }
+                    if !model.marked.is_empty() {
+                        title.push_str(&format!(" ({} marked)", model.marked.len()));
+                    }
+                    if board.sort_mode != SortMode::Manual {
+                        title.push_str(&format!(" (sorted {})", board.sort_mode.describe()));
+                    }
+                    title
+                })
+                .right_aligned(),
+            );
+
+        let modeline_text = {
+            let mut modeline_text = String::new();
+
+            if let Some(e) = &model.error {
+                modeline_text.push_str(" - Error: ");
+                modeline_text.push_str(&e.replace("\n", " "));
+            } else if let Some(notice) = &model.notice {
+                modeline_text.push_str(" - ");
+                modeline_text.push_str(notice);
+            } else {
+                let formatted = match model.mode {
+                    Mode::ViewingBoard => [
+                        ("[h,j,k,l/arrows]", "move"),
+                        ("[q]", "quit"),
+                        ("[enter]", "view card"),
+                        ("[m]", "move card"),
+                        ("[n]", "new card"),
+                        ("[e]", "edit card"),
+                        ("[d]", "delete card"),
+                        ("[>]", "send to board"),
+                        ("[v]", "select cards"),
+                        ("[s]", "metrics"),
+                        ("[g]", "agenda"),
+                        ("[t]", "filter by tag"),
+                        ("[T]", "clear filter"),
+                        ("[o]", "sort"),
+                        ("[f]", "filter"),
+                        ("[n/N]", "next/previous match"),
+                        ("[esc]", "clear filter"),
+                        ("[/]", "search all boards"),
+                        ("[:/#]", "go to card"),
+                        ("[u]", "undo"),
+                        ("[ctrl-r]", "redo"),
+                        ("[H]", "card history"),
+                        ("[a]", "board activity"),
+                        ("[x]", "export"),
+                        ("[b]", "view boards"),
+                    ]
+                    .iter()
+                    // n is for matches while a filter is up, and new cards the rest of the time
+                    .filter(|(k, _)| match *k {
+                        "[n]" => model.board_filter.is_none(),
+                        "[n/N]" | "[esc]" => model.board_filter.is_some(),
+                        _ => true,
+                    })
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::ViewingCardDetail => [
+                        ("[enter/esc]", "close detail view"),
+                        ("[e]", "edit card"),
+                        ("[H]", "history"),
+                        ("[q]", "quit"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::MovingCard => [
+                        ("[h/left]", "move card left"),
+                        ("[j/down]", "move card down"),
+                        ("[k/up]", "move card up"),
+                        ("[l/right]", "move card right"),
+                        ("[1-9]", "move card to column"),
+                        ("[q]", "quit"),
+                        ("[m|enter|esc]", "close card detail view"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::ViewingBoards
+                    | Mode::ChoosingColumnDestination
+                    | Mode::ViewingMetrics
+                    | Mode::ViewingActivity
+                    | Mode::ViewingAgenda => [
+                        ("[j/down]", "down"),
+                        ("[k/up]", "up"),
+                        ("[enter]", "view board"),
+                        ("[n]", "new board"),
+                        ("[e]", "edit board"),
+                        ("[q]", "quit"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::ConfirmCardDeletion | Mode::ConfirmBoardDeletion => [
+                        ("[h/left]", "left"),
+                        ("[l/right]", "right"),
+                        ("[enter]", "confirm selection"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::ChoosingTagFilter => [
+                        ("[j/down]", "down"),
+                        ("[k/up]", "up"),
+                        ("[enter]", "show only this tag"),
+                        ("[esc]", "cancel"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::ExportingBoard => [
+                        ("[j/down]", "down"),
+                        ("[k/up]", "up"),
+                        ("[enter]", "export"),
+                        ("[esc]", "cancel"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::ResolvingConflict => [
+                        ("[j/down]", "down"),
+                        ("[k/up]", "up"),
+                        ("[enter]", "choose"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::FilteringBoard => [
+                        format!(
+                            "filter: {}",
+                            model.board_filter.as_deref().unwrap_or_default()
+                        ),
+                        "[enter] - keep filter".to_string(),
+                        "[esc] - cancel".to_string(),
+                    ]
+                    .to_vec(),
+                    Mode::SelectingCards => [
+                        ("[h,j,k,l/arrows]", "move"),
+                        ("[v/space]", "mark"),
+                        ("[1-9]", "move marked to column"),
+                        ("[d]", "delete marked"),
+                        ("[t]", "tag marked"),
+                        ("[>]", "send marked to board"),
+                        ("[esc]", "done"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::TaggingCards => [
+                        format!("tag: {}", model.new_tag.as_deref().unwrap_or_default()),
+                        "[enter] - tag marked cards".to_string(),
+                        "[esc] - cancel".to_string(),
+                    ]
+                    .to_vec(),
+                    Mode::SendingCard => [
+                        ("[j/down]", "down"),
+                        ("[k/up]", "up"),
+                        ("[enter]", "choose"),
+                        ("[esc]", "back"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                    Mode::JumpingToCard => [
+                        format!("#{}", model.card_number.as_deref().unwrap_or_default()),
+                        "[enter] - go to card".to_string(),
+                        "[esc] - cancel".to_string(),
+                    ]
+                    .to_vec(),
+                    Mode::Searching => [
+                        ("[down/ctrl-n]", "down"),
+                        ("[up/ctrl-p]", "up"),
+                        ("[enter]", "view card on its board"),
+                        ("[esc]", "cancel"),
+                    ]
+                    .iter()
+                    .map(|(k, action)| format!("{} - {}", k, action))
+                    .collect::<Vec<_>>(),
+                };
+
+                modeline_text.push_str(&formatted.join(" │ "));
+            }
+
+            modeline_text
+        };
+
+        let modeline = Paragraph::new(modeline_text).block(modeline_block);
+
+        frame.render_widget(modeline, modeline_layout);
+    }
+}
+
+/// tags as colored chips. the same tag always gets the same color
+fn tag_chips(tags: &[String]) -> Line<'_> {
+    const COLORS: [Color; 6] = [
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+    ];
+
+    Line::from(
+        tags.iter()
+            .flat_map(|tag| {
+                let hash = tag.bytes().fold(0usize, |hash, b| {
+                    hash.wrapping_mul(31).wrapping_add(b.into())
+                });
+
+                [
+                    Span::styled(
+                        format!(" {} ", tag),
+                        Style::default()
+                            .fg(Color::Black)
+                            .bg(COLORS[hash % COLORS.len()]),
+                    ),
+                    Span::raw(" "),
+                ]
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// the Delete/Cancel popup shared by everything that needs confirming
+fn view_confirmation_popup(
+    frame: &mut ratatui::Frame,
+    title: &str,
+    confirmation_state: &ConfirmationState,
+) {
+    let title_style = Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+    let block = Block::bordered()
+        .title(title.to_string())
+        .padding(Padding::uniform(1))
+        .title_style(title_style);
+
+    let area = popup_area(frame.area(), 30, 20);
+
+    let [left, right] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+        .areas(area);
+
+    let left_text = {
+        let text = if *confirmation_state == ConfirmationState::Yes {
+            "[ Delete ]"
+        } else {
+            "Delete"
+        };
+
+        Text::from(text).centered()
+    };
+
+    let right_text = {
+        let text = if *confirmation_state == ConfirmationState::No {
+            "[ Cancel ]"
+        } else {
+            "Cancel"
+        };
+
+        Text::from(text).centered()
+    };
+
+    let left = center(
+        left,
+        Constraint::Length(left_text.width() as u16),
+        Constraint::Length(1),
+    );
+
+    let right = center(
+        right,
+        Constraint::Length(right_text.width() as u16),
+        Constraint::Length(1),
+    );
+
+    frame.render_widget(ratatui::widgets::Clear, area); //this clears out the background
+    frame.render_widget(left_text, left);
+    frame.render_widget(right_text, right);
+    frame.render_widget(block, area);
+
+    fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+        let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+        let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+        let [area] = vertical.areas(area);
+        let [area] = horizontal.areas(area);
+        area
+    }
+
+    fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
+        let [area] = Layout::horizontal([horizontal])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([vertical]).flex(Flex::Center).areas(area);
+        area
+    }
+}
+
+/// Convert Event to Message
+///
+/// We don't need to pass in a `model` to this function in this example
+/// but you might need it as your project evolves
+fn receive_event(model: &Model) -> anyhow::Result<Option<Message>> {
+    if crossterm::event::poll(Duration::from_millis(1000))?
+        && let crossterm::event::Event::Key(key) = crossterm::event::read()?
+        && key.kind == crossterm::event::KeyEventKind::Press
+    {
+        return Ok(handle_event(Event::KeyEvent(key), model));
+    }
+
+    if let Ok(event) = model.internal_event_rx.try_recv() {
+        return Ok(handle_event(event, model));
+    }
+
+    if model.repo.data_version()? != model.data_version {
+        return Ok(handle_event(
+            Event::InternalEvent(InternalEvent::DatabaseChanged),
+            model,
+        ));
+    }
+
+    Ok(None)
+}
+
+fn handle_event(event: Event, model: &Model) -> Option<Message> {
+    match event {
+        Event::KeyEvent(key) => match model.mode {
+            Mode::ViewingBoard => match key.code {
+                KeyCode::Char('h') | KeyCode::Left => Some(Message::NavigateLeft),
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Char('l') | KeyCode::Right => Some(Message::NavigateRight),
+                KeyCode::Char('q') => Some(Message::Quit),
+                KeyCode::Char('m') => Some(Message::MoveCardMode),
+                // while a filter is up, n and N are for going between matches
+                KeyCode::Char('n') if model.board_filter.is_some() => Some(Message::NextMatch),
+                KeyCode::Char('N') if model.board_filter.is_some() => Some(Message::PreviousMatch),
+                KeyCode::Esc if model.board_filter.is_some() => Some(Message::ClearBoardFilter),
+                KeyCode::Char('n') => Some(Message::NewCard),
+                KeyCode::Char('e') => Some(Message::EditCard),
+                KeyCode::Char('d') => Some(Message::DeleteCard),
+                KeyCode::Char('>') => Some(Message::SendCardMode),
+                KeyCode::Char('v') => Some(Message::SelectCardsMode),
+                KeyCode::Char('b') => Some(Message::ViewBoardsMode),
+                KeyCode::Char('s') => Some(Message::ViewMetricsMode),
+                KeyCode::Char('H') => Some(Message::ViewCardHistoryMode),
+                KeyCode::Char('a') => Some(Message::ViewBoardActivityMode),
+                KeyCode::Char('g') => Some(Message::ViewAgendaMode),
+                KeyCode::Char('x') => Some(Message::ExportBoardMode),
+                KeyCode::Char('t') => Some(Message::FilterByTagMode),
+                KeyCode::Char('T') => Some(Message::ClearTagFilter),
+                KeyCode::Char('o') => Some(Message::CycleSortMode),
+                KeyCode::Char('f') => Some(Message::FilterBoardMode),
+                KeyCode::Char('/') => Some(Message::SearchMode),
+                KeyCode::Char(':') | KeyCode::Char('#') => Some(Message::JumpToCardMode),
+                KeyCode::Char('u') => Some(Message::Undo),
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    Some(Message::Redo)
+                }
+                KeyCode::Enter => Some(Message::ViewCardDetailMode),
+                _ => None,
+            },
+            Mode::SelectingCards => match key.code {
+                KeyCode::Char('h') | KeyCode::Left => Some(Message::NavigateLeft),
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Char('l') | KeyCode::Right => Some(Message::NavigateRight),
+                KeyCode::Char('v') | KeyCode::Char(' ') => Some(Message::ToggleMark),
+                KeyCode::Char(c @ '1'..='9') => Some(Message::MoveCardToColumn(
+                    c.to_digit(10).unwrap() as usize - 1,
+                )),
+                KeyCode::Char('d') => Some(Message::DeleteCard),
+                KeyCode::Char('t') => Some(Message::TagCardsMode),
+                KeyCode::Char('>') => Some(Message::SendCardMode),
+                KeyCode::Char('q') => Some(Message::Quit),
+                KeyCode::Esc => Some(Message::ViewBoardMode),
+                _ => None,
+            },
+            Mode::TaggingCards => match key.code {
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                KeyCode::Esc => Some(Message::ViewBoardMode),
+                KeyCode::Backspace => Some(Message::DeleteChar),
+                KeyCode::Char(c) => Some(Message::InputChar(c)),
+                _ => None,
+            },
+            Mode::SendingCard => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                KeyCode::Esc => Some(Message::ViewBoardMode),
+                _ => None,
+            },
+            Mode::JumpingToCard => match key.code {
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                KeyCode::Esc => Some(Message::ViewBoardMode),
+                KeyCode::Backspace => Some(Message::DeleteChar),
+                KeyCode::Char(c) if c.is_ascii_digit() => Some(Message::InputChar(c)),
+                _ => None,
+            },
+            Mode::FilteringBoard => match key.code {
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                KeyCode::Esc => Some(Message::ViewBoardMode),
+                KeyCode::Backspace => Some(Message::DeleteChar),
+                KeyCode::Char(c) => Some(Message::InputChar(c)),
+                _ => None,
+            },
+            // every other key is part of the query
+            Mode::Searching => match key.code {
+                KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    Some(Message::NavigateDown)
+                }
+                KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    Some(Message::NavigateUp)
+                }
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                KeyCode::Esc => Some(Message::ViewBoardMode),
+                KeyCode::Backspace => Some(Message::DeleteChar),
+                KeyCode::Char(c) => Some(Message::InputChar(c)),
+                _ => None,
+            },
+            Mode::ChoosingTagFilter | Mode::ExportingBoard => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                KeyCode::Esc => Some(Message::ViewBoardMode),
+                _ => None,
+            },
+            // there's no cancelling, since that would quietly throw one of the versions away
+            Mode::ResolvingConflict => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                _ => None,
+            },
+            Mode::ViewingActivity => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Enter | KeyCode::Esc => Some(Message::ViewBoardMode),
+                KeyCode::Char('q') => Some(Message::Quit),
+                _ => None,
+            },
+            Mode::ViewingMetrics => match key.code {
+                KeyCode::Char('s') | KeyCode::Enter | KeyCode::Esc => Some(Message::ViewBoardMode),
+                KeyCode::Char('q') => Some(Message::Quit),
+                _ => None,
+            },
+            Mode::MovingCard => match key.code {
+                KeyCode::Char('h') | KeyCode::Left => Some(Message::MoveCardLeft),
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveCardDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveCardUp),
+                KeyCode::Char('l') | KeyCode::Right => Some(Message::MoveCardRight),
+                KeyCode::Char(c @ '1'..='9') => Some(Message::MoveCardToColumn(
+                    c.to_digit(10).unwrap() as usize - 1,
+                )),
+                KeyCode::Char('q') => Some(Message::Quit),
+                KeyCode::Char('m') | KeyCode::Enter | KeyCode::Esc => Some(Message::ViewBoardMode),
+                _ => None,
+            },
+            Mode::ConfirmCardDeletion | Mode::ConfirmBoardDeletion => match key.code {
+                KeyCode::Char('h') | KeyCode::Left => Some(Message::NavigateLeft),
+                KeyCode::Char('l') | KeyCode::Right => Some(Message::NavigateRight),
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                _ => None,
+            },
+            Mode::ViewingCardDetail => match key.code {
+                KeyCode::Enter | KeyCode::Esc => Some(Message::ViewBoardMode),
+                KeyCode::Char('e') => Some(Message::EditCard),
+                KeyCode::Char('H') => Some(Message::ViewCardHistoryMode),
+                KeyCode::Char('q') => Some(Message::Quit),
+                _ => None,
+            },
+            Mode::ViewingBoards => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Char('n') => Some(Message::NewBoard),
+                KeyCode::Char('e') => Some(Message::EditBoard),
+                KeyCode::Char('d') => Some(Message::DeleteBoard),
+                KeyCode::Char('a') => Some(Message::ToggleArchiveBoard),
+                KeyCode::Char('A') => Some(Message::ToggleArchivedBoardsView),
+                KeyCode::Char('g') => Some(Message::ViewAgendaMode),
+                KeyCode::Char('q') => Some(Message::Quit),
+                KeyCode::Enter => Some(Message::ViewBoardMode),
+                _ => None,
+            },
+            Mode::ViewingAgenda => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                KeyCode::Char('g') | KeyCode::Esc => Some(Message::ViewBoardMode),
+                KeyCode::Char('q') => Some(Message::Quit),
+                _ => None,
+            },
+            Mode::ChoosingColumnDestination => match key.code {
+                KeyCode::Char('j') | KeyCode::Down => Some(Message::NavigateDown),
+                KeyCode::Char('k') | KeyCode::Up => Some(Message::NavigateUp),
+                KeyCode::Enter => Some(Message::ConfirmChoice),
+                KeyCode::Esc => Some(Message::ViewBoardsMode),
+                _ => None,
+            },
+        },
+        Event::InternalEvent(e) => match e {
+            InternalEvent::ClearError => Some(Message::SetError(None)),
+            InternalEvent::DatabaseChanged => Some(Message::ReloadChangedData),
+        },
+    }
+}
+
+fn update<B>(
+    model: &mut Model,
+    msg: Message,
+    terminal: &mut Terminal<B>,
+) -> anyhow::Result<Option<Message>>
+where
+    B: Backend,
+    B::Error: Send + Sync + 'static,
+{
+    update_with_run_editor_fn(model, msg, terminal, run_editor)
+}
+
+/// this exists only so we can mock out the run_editor function,
+/// which in the real program actually opens the user's editor.
+/// we can't do this in tests, so we need to mock it out
+/// with a function that just returns whatever data
+/// we tell it to, depending on the desired test condition
+fn update_with_run_editor_fn<F, B>(
+    model: &mut Model,
+    msg: Message,
+    terminal: &mut Terminal<B>,
+    run_editor_fn: F,
+) -> anyhow::Result<Option<Message>>
+where
+    F: Fn(&mut Terminal<B>, &str) -> anyhow::Result<String>,
+    B: Backend,
+{
+    // errors can come out of any mode, so any mode has to be able to show them
+    if let Message::SetError(e) = msg {
+        model.error = e;
+        let internal_event_tx = model.internal_event_tx.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_secs(10));
+            let _ = internal_event_tx.send(Event::InternalEvent(InternalEvent::ClearError));
+        });
+        return Ok(None);
+    }
+
+    // like errors, changes from elsewhere can show up in any mode
+    if msg == Message::ReloadChangedData {
+        model.reload_changed_data()?;
+        return Ok(None);
+    }
+
+    model.notice = None;
+
+    match model.mode {
+        Mode::ViewingBoard => {
+            match msg {
+                Message::ViewBoardsMode => model.switch_to_viewing_boards_mode()?,
+                Message::MoveCardMode => {
+                    if model.selected.card_index.is_some() {
+                        model.mode = Mode::MovingCard
+                    }
+                }
+                Message::ViewCardDetailMode => {
+                    if let Some(column) = model.selected_column()
+                        && !column.cards.is_empty()
+                    {
+                        model.mode = Mode::ViewingCardDetail
+                    }
+                }
+                Message::Quit => model.running_state = RunningState::Done,
+                Message::NavigateLeft => model.navigate_left(),
+                Message::NavigateDown => model.navigate_down(),
+                Message::NavigateUp => model.navigate_up(),
+                Message::NavigateRight => model.navigate_right(),
+                Message::NewCard => {
+                    // new cards start out with the tag being filtered on,
+                    // so they don't vanish as soon as they are made
+                    let template = CardText {
+                        title: "Title".to_string(),
+                        body: "Content goes here".to_string(),
+                        tags: model.tag_filter.iter().cloned().collect(),
+                        due_on: None,
+                        priority: None,
+                    };
+
+                    let text = edit_until_parsed(
+                        &template.to_editor_text(),
+                        |text| run_editor_fn(terminal, text),
+                        parse_raw_card_text,
+                    )?;
+
+                    let board_id = if let Some(board) = &model.board {
+                        board.id
+                    } else {
+                        panic!()
+                    };
+
+                    let card = model.repo.add_card(board_id, None, &text)?;
+
+                    model.mode = Mode::ViewingBoard;
+                    model.selected.column_index = Some(0);
+                    model.selected.card_index = Some(0);
+
+                    model.add_card_to_selected_column(card);
+
+                    model.resort_board()?;
+                }
+                Message::EditCard => {
+                    if let Some(card) = model.selected_card_from_repo()? {
+                        let text = edit_until_parsed(
+                            &card.text().to_editor_text(),
+                            |text| run_editor_fn(terminal, text),
+                            parse_raw_card_text,
+                        )?;
+
+                        model.save_card_edit(&card, text)?;
+                    }
+                }
+                Message::DeleteCard => model.confirm_card_delete()?,
+                Message::ViewCardHistoryMode => model.view_card_history()?,
+                Message::ViewBoardActivityMode => model.view_board_activity()?,
+                Message::Undo => model.undo()?,
+                Message::Redo => model.redo()?,
+                Message::ViewAgendaMode => model.view_agenda()?,
+                Message::FilterByTagMode => model.choose_tag_filter()?,
+                Message::ClearTagFilter => model.set_tag_filter(None)?,
+                Message::CycleSortMode => model.cycle_sort_mode()?,
+                Message::SearchMode => model.start_search(),
+                Message::FilterBoardMode => model.start_board_filter(),
+                Message::JumpToCardMode => model.start_jump_to_card(),
+                Message::SendCardMode => model.choose_send_destination()?,
+                Message::SelectCardsMode => model.start_selecting_cards(),
+                Message::ExportBoardMode => model.start_export(),
+                Message::ClearBoardFilter => model.clear_board_filter(),
+                Message::NextMatch => model.jump_to_next_match(true)?,
+                Message::PreviousMatch => model.jump_to_next_match(false)?,
+                Message::ViewMetricsMode => {
+                    if let Some(board) = &model.board {
+                        model.metrics = Some(model.repo.get_board_metrics(board.id)?);
+                        model.mode = Mode::ViewingMetrics;
+                    }
+                }
+                m => panic!("unhandled message: {:?}", m),
+            };
+        }
+        Mode::ViewingCardDetail => match msg {
+            Message::ViewBoardMode => model.mode = Mode::ViewingBoard,
+            Message::Quit => model.running_state = RunningState::Done,
+            Message::ViewCardHistoryMode => model.view_card_history()?,
+            Message::EditCard => {
+                if let Some(card) = model.selected_card_from_repo()? {
+                    let text = edit_until_parsed(
+                        &card.text().to_editor_text(),
+                        |text| run_editor_fn(terminal, text),
+                        parse_raw_card_text,
+                    )?;
+
+                    model.save_card_edit(&card, text)?;
+                }
+            }
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::MovingCard => match msg {
+            Message::MoveCardLeft => model.move_selected_card_left()?,
+            Message::MoveCardRight => model.move_selected_card_right()?,
+            Message::MoveCardToColumn(column_index) => {
+                model.move_selected_card_to_column(column_index)?
+            }
+            Message::MoveCardUp => model.move_selected_card_up()?,
+            Message::MoveCardDown => model.move_selected_card_down()?,
+            Message::ViewBoardMode => model.mode = Mode::ViewingBoard,
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ConfirmCardDeletion => match msg {
+            Message::ConfirmChoice => match model.confirmation_state {
+                ConfirmationState::Yes => {
+                    if model.marked.is_empty() {
+                        model.delete_selected_card()?;
+                    } else {
+                        model.delete_marked_cards()?;
+                    }
+                    model.mode = Mode::ViewingBoard;
+                    model.confirmation_state = ConfirmationState::No;
+                }
+                ConfirmationState::No => model.mode = model.board_mode(),
+            },
+            Message::NavigateLeft | Message::NavigateRight => model.toggle_confirmation_state(),
+            Message::ViewBoardMode => model.mode = model.board_mode(),
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ViewingBoards => match msg {
+            Message::NavigateUp => {
+                model.selected.board_index =
+                    model.selected.board_index.map(|i| i.saturating_sub(1));
+            }
+            Message::NavigateDown => {
+                model.selected.board_index = model
+                    .selected
+                    .board_index
+                    .map(|i| min(model.board_metas.len().saturating_sub(1), i + 1));
+            }
+            Message::NewBoard => {
+                let (name, column_names) = edit_until_parsed(
+                    "Board Name\n==========\n\n- Column #1\n- Column #2 [doing]\n- Column #3 [done]",
+                    |text| run_editor_fn(terminal, text),
+                    parse_raw_board_text,
+                )?;
+
+                // TODO
+                // 1. create board, get board_id
+                model.create_board(
+                    &name,
+                    &column_names.iter().map(String::as_str).collect::<Vec<_>>(),
+                )?;
+                // 2. insert columns, get columns ids
+                model.selected.board_index = Some(0);
+                model.selected.column_index = Some(0);
+            }
+            Message::EditBoard => {
+                let selected_board = &model.board_metas[model.selected.board_index.unwrap()];
+                let mut board_for_editor = format!("{}\n==========\n\n", selected_board.name);
+
+                for (column_name, stage) in
+                    selected_board.columns.iter().zip(&selected_board.stages)
+                {
+                    board_for_editor.push_str("- ");
+                    board_for_editor.push_str(column_name);
+                    if let Some(stage) = stage {
+                        board_for_editor.push_str(" [");
+                        board_for_editor.push_str(stage.as_str());
+                        board_for_editor.push(']');
+                    }
+                    board_for_editor.push('\n');
+                }
+
+                let (name, column_names) = edit_until_parsed(
+                    &board_for_editor,
+                    |text| run_editor_fn(terminal, text),
+                    parse_raw_board_text,
+                )?;
+
+                model.update_selected_board(
+                    &name,
+                    column_names.iter().map(String::as_str).collect(),
+                )?;
+            }
+            Message::ViewBoardMode => {
+                model.mode = Mode::ViewingBoard;
+                model.load_selected_board()?;
+            }
+            Message::DeleteBoard => model.confirm_board_delete()?,
+            Message::ToggleArchiveBoard => model.toggle_selected_board_archived()?,
+            Message::ToggleArchivedBoardsView => model.toggle_archived_boards_view()?,
+            Message::ViewAgendaMode => model.view_agenda()?,
+            Message::Quit => model.running_state = RunningState::Done,
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ConfirmBoardDeletion => match msg {
+            Message::ConfirmChoice => {
+                if model.confirmation_state == ConfirmationState::Yes {
+                    model.delete_selected_board()?;
+                }
+                model.mode = Mode::ViewingBoards;
+                model.confirmation_state = ConfirmationState::No;
+            }
+            Message::NavigateLeft | Message::NavigateRight => model.toggle_confirmation_state(),
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ChoosingColumnDestination => match msg {
+            Message::NavigateUp => {
+                if let Some(board_edit) = &mut model.board_edit {
+                    board_edit.destination_index = board_edit.destination_index.saturating_sub(1);
+                }
+            }
+            Message::NavigateDown => {
+                if let Some(board_edit) = &mut model.board_edit {
+                    board_edit.destination_index = min(
+                        board_edit.destination_index + 1,
+                        board_edit.columns.len().saturating_sub(1),
+                    );
+                }
+            }
+            Message::ConfirmChoice => model.choose_column_destination()?,
+            Message::ViewBoardsMode => {
+                model.board_edit = None;
+                model.mode = Mode::ViewingBoards;
+            }
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ViewingMetrics => match msg {
+            Message::ViewBoardMode => {
+                model.metrics = None;
+                model.mode = Mode::ViewingBoard;
+            }
+            Message::Quit => model.running_state = RunningState::Done,
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ChoosingTagFilter => match msg {
+            Message::NavigateUp => {
+                if let Some(tag_picker) = &mut model.tag_picker {
+                    tag_picker.selected = tag_picker.selected.saturating_sub(1);
+                }
+            }
+            Message::NavigateDown => {
+                if let Some(tag_picker) = &mut model.tag_picker {
+                    tag_picker.selected = min(
+                        tag_picker.selected + 1,
+                        tag_picker.tags.len().saturating_sub(1),
+                    );
+                }
+            }
+            Message::ConfirmChoice => {
+                model.mode = Mode::ViewingBoard;
+                if let Some(mut tag_picker) = model.tag_picker.take() {
+                    let tag = tag_picker.tags.swap_remove(tag_picker.selected);
+                    model.set_tag_filter(Some(tag))?;
+                }
+            }
+            Message::ViewBoardMode => {
+                model.tag_picker = None;
+                model.mode = Mode::ViewingBoard;
+            }
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ResolvingConflict => match msg {
+            Message::NavigateUp => {
+                if let Some(conflict) = &mut model.conflict {
+                    conflict.selected = conflict.selected.saturating_sub(1);
+                }
+            }
+            Message::NavigateDown => {
+                if let Some(conflict) = &mut model.conflict {
+                    conflict.selected = min(conflict.selected + 1, EditConflict::CHOICES.len() - 1);
+                }
+            }
+            Message::ConfirmChoice => model.resolve_conflict(terminal, run_editor_fn)?,
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ExportingBoard => match msg {
+            Message::NavigateUp => {
+                if let Some(format_index) = &mut model.export_format {
+                    *format_index = format_index.saturating_sub(1);
+                }
+            }
+            Message::NavigateDown => {
+                if let Some(format_index) = &mut model.export_format {
+                    *format_index = min(*format_index + 1, ExportFormat::ALL.len() - 1);
+                }
+            }
+            Message::ConfirmChoice => model.export_board()?,
+            Message::ViewBoardMode => {
+                model.export_format = None;
+                model.mode = Mode::ViewingBoard;
+            }
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::SelectingCards => match msg {
+            Message::NavigateLeft => model.navigate_left(),
+            Message::NavigateDown => model.navigate_down(),
+            Message::NavigateUp => model.navigate_up(),
+            Message::NavigateRight => model.navigate_right(),
+            Message::ToggleMark => model.toggle_mark(),
+            Message::MoveCardToColumn(column_index) => {
+                model.ensure_marked()?;
+                model.move_marked_cards(column_index)?;
+            }
+            Message::DeleteCard => {
+                model.ensure_marked()?;
+                model.confirm_card_delete()?;
+            }
+            Message::TagCardsMode => {
+                model.ensure_marked()?;
+                model.new_tag = Some(String::new());
+                model.mode = Mode::TaggingCards;
+            }
+            Message::SendCardMode => {
+                model.ensure_marked()?;
+                model.choose_send_destination()?;
+            }
+            Message::ViewBoardMode => model.stop_selecting_cards(),
+            Message::Quit => model.running_state = RunningState::Done,
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::TaggingCards => match msg {
+            Message::InputChar(c) => {
+                if let Some(new_tag) = &mut model.new_tag {
+                    new_tag.push(c);
+                }
+            }
+            Message::DeleteChar => {
+                if let Some(new_tag) = &mut model.new_tag {
+                    new_tag.pop();
+                }
+            }
+            Message::ConfirmChoice => {
+                // kept until it works, so a bad tag can be fixed
+                let new_tag = model.new_tag.clone().unwrap_or_default();
+                model.tag_marked_cards(&new_tag)?;
+                model.new_tag = None;
+            }
+            Message::ViewBoardMode => {
+                model.new_tag = None;
+                model.mode = Mode::SelectingCards;
+            }
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::SendingCard => match msg {
+            Message::NavigateUp => {
+                if let Some(send_card) = &mut model.send_card {
+                    if let Some(column_index) = &mut send_card.column_index {
+                        *column_index = column_index.saturating_sub(1);
+                    } else {
+                        send_card.board_index = send_card.board_index.saturating_sub(1);
+                    }
+                }
+            }
+            Message::NavigateDown => {
+                if let Some(send_card) = &mut model.send_card {
+                    if let Some(column_index) = &mut send_card.column_index {
+                        let columns_len = send_card.boards[send_card.board_index].columns.len();
+                        *column_index = min(*column_index + 1, columns_len.saturating_sub(1));
+                    } else {
+                        send_card.board_index = min(
+                            send_card.board_index + 1,
+                            send_card.boards.len().saturating_sub(1),
+                        );
+                    }
+                }
+            }
+            Message::ConfirmChoice => {
+                if let Some(send_card) = &mut model.send_card
+                    && send_card.column_index.is_none()
+                {
+                    let board_meta = &send_card.boards[send_card.board_index];
+
+                    if board_meta.columns.is_empty() {
+                        return Err(anyhow!(
+                            "{} has no columns to send cards to",
+                            board_meta.name
+                        ));
+                    }
+
+                    send_card.column_index = Some(0);
+                } else {
+                    model.send_selected_card()?;
+                }
+            }
+            Message::ViewBoardMode => {
+                if let Some(send_card) = &mut model.send_card
+                    && send_card.column_index.is_some()
+                {
+                    send_card.column_index = None;
+                } else {
+                    model.send_card = None;
+                    model.mode = model.board_mode();
+                }
+            }
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::JumpingToCard => match msg {
+            Message::InputChar(c) => {
+                if let Some(card_number) = &mut model.card_number {
+                    card_number.push(c);
+                }
+            }
+            Message::DeleteChar => {
+                if let Some(card_number) = &mut model.card_number {
+                    card_number.pop();
+                }
+            }
+            Message::ConfirmChoice => model.jump_to_card()?,
+            Message::ViewBoardMode => {
+                model.card_number = None;
+                model.mode = Mode::ViewingBoard;
+            }
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::FilteringBoard => match msg {
+            Message::InputChar(c) => model.edit_board_filter(|pattern| pattern.push(c)),
+            Message::DeleteChar => model.edit_board_filter(|pattern| {
+                pattern.pop();
+            }),
+            Message::ConfirmChoice => model.finish_board_filter(),
+            Message::ViewBoardMode => model.clear_board_filter(),
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::Searching => match msg {
+            Message::InputChar(c) => model.edit_search_query(|query| query.push(c))?,
+            Message::DeleteChar => model.edit_search_query(|query| {
+                query.pop();
+            })?,
+            Message::NavigateUp => {
+                if let Some(search) = &mut model.search {
+                    search.selected = search.selected.saturating_sub(1);
+                }
+            }
+            Message::NavigateDown => {
+                if let Some(search) = &mut model.search {
+                    search.selected =
+                        min(search.selected + 1, search.results.len().saturating_sub(1));
+                }
+            }
+            Message::ConfirmChoice => model.open_selected_search_result()?,
+            Message::ViewBoardMode => model.close_search(),
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ViewingAgenda => match msg {
+            Message::NavigateUp => {
+                if let Some(agenda) = &mut model.agenda {
+                    agenda.selected = agenda.selected.saturating_sub(1);
+                }
+            }
+            Message::NavigateDown => {
+                if let Some(agenda) = &mut model.agenda {
+                    agenda.selected =
+                        min(agenda.selected + 1, agenda.items.len().saturating_sub(1));
+                }
+            }
+            Message::ConfirmChoice => model.open_selected_agenda_item()?,
+            Message::ViewBoardMode => model.close_agenda()?,
+            Message::Quit => model.running_state = RunningState::Done,
+            m => panic!("unhandled message: {:?}", m),
+        },
+        Mode::ViewingActivity => match msg {
+            Message::NavigateUp => {
+                if let Some(activity) = &mut model.activity {
+                    activity.selected = activity.selected.saturating_sub(1);
+                }
+            }
+            Message::NavigateDown => {
+                if let Some(activity) = &mut model.activity {
+                    activity.selected = min(
+                        activity.selected + 1,
+                        activity.events.len().saturating_sub(1),
+                    );
+                }
+            }
+            Message::ViewBoardMode => model.close_activity(),
+            Message::Quit => model.running_state = RunningState::Done,
+            m => panic!("unhandled message: {:?}", m),
+        },
+    }
+
+    Ok(None)
+}
+
+/// starts the lines `edit_until_parsed` adds above a draft that didn't parse
+const PARSE_ERROR_PREFIX: &str = "# kk: ";
+
+/// runs `edit` on `template` until what comes back passes `parse`, and returns what it parsed to.
+///
+/// when it doesn't parse, the editor reopens on what was written, with the error above it,
+/// so a long body isn't lost to a typo in a field.
+/// saving the draft without changing it gives up with the error,
+/// after saving the draft to a file the error names
+fn edit_until_parsed<T>(
+    template: &str,
+    mut edit: impl FnMut(&str) -> anyhow::Result<String>,
+    parse: impl Fn(&str) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    // only what was written after an error can have the error's lines in it
+    let mut draft = edit(template)?;
+
+    loop {
+        let e = match parse(&draft) {
+            Ok(parsed) => return Ok(parsed),
+            Err(e) => e,
+        };
+
+        let retry = remove_parse_error(&edit(&format!(
+            "{PARSE_ERROR_PREFIX}{e}\n\
+             {PARSE_ERROR_PREFIX}fix it and save to try again, or quit without changing anything to give up.\n\
+             {PARSE_ERROR_PREFIX}these lines are removed when you save.\n\
+             \n\
+             {draft}"
+        ))?);
+
+        if retry == draft {
+            let mut f = tempfile::Builder::new()
+                .prefix("kk-draft-")
+                .suffix(".txt")
+                .tempfile()?;
+            f.write_all(draft.as_bytes())?;
+            let (_, path) = f.keep()?;
+
+            return Err(anyhow!("{}. your draft is in {}", e, path.display()));
+        }
+
+        draft = retry;
+    }
+}
+
+/// the draft without the lines `edit_until_parsed` put above it
+fn remove_parse_error(raw_text: &str) -> String {
+    let mut rest = raw_text;
+    let mut removed_any = false;
+
+    while rest.starts_with(PARSE_ERROR_PREFIX) {
+        rest = rest.split_once('\n').map(|(_, rest)| rest).unwrap_or("");
+        removed_any = true;
+    }
+
+    if removed_any {
+        rest.strip_prefix('\n').unwrap_or(rest).to_string()
+    } else {
+        raw_text.to_string()
+    }
+}
+
+/// a card is its title, underlined, then any fields like `tags: bug, ui`,
+/// then a blank line, then its body
+fn parse_raw_card_text(raw_card_text: &str) -> anyhow::Result<CardText> {
+    let card_regex =
+        Regex::new(r#"(?s)(?<title>[^=\n]+)\n=+\n(?<fields>(?:[^\n]+\n)*)\n(?<body>.*)"#).unwrap();
+
+    let m = card_regex.captures(raw_card_text);
+
+    if let Some(captures) = m
+        && let Some(title) = captures.name("title")
+        && let Some(fields) = captures.name("fields")
+        && let Some(body) = captures.name("body")
+    {
+        let mut tags: Vec<String> = vec![];
+        let mut due_on = None;
+        let mut priority = None;
+
+        for field in fields.as_str().lines() {
+            match field.split_once(':') {
+                Some(("tags", value)) => {
+                    for tag in value.split(',').map(|tag| tag.trim()) {
+                        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+                            tags.push(tag.to_string());
+                        }
+                    }
+                }
+                Some(("priority", value)) => {
+                    let value = value.trim();
+                    priority = if value.is_empty() {
+                        None
+                    } else {
+                        Some(
+                            value
+                                .parse()
+                                .map_err(|e| anyhow!("could not parse raw card text: {}", e))?,
+                        )
+                    };
+                }
+                Some(("due", value)) => {
+                    let value = value.trim();
+                    if value.is_empty() {
+                        due_on = None;
+                    } else if is_valid_date(value) {
+                        due_on = Some(value.to_string());
+                    } else {
+                        return Err(anyhow!(
+                            "could not parse raw card text: due dates look like 2024-12-31, not {}",
+                            value
+                        ));
+                    }
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "could not parse raw card text: bad field: {}",
+                        field
+                    ));
+                }
+            }
+        }
+
+        tags.sort();
+
+        Ok(CardText {
+            title: title.as_str().to_string(),
+            body: body.as_str().to_string(),
+            tags,
+            due_on,
+            priority,
+        })
+    } else {
+        Err(anyhow!("could not parse raw card text"))
+    }
+}
+
+/// whether `date` is a real `YYYY-MM-DD` date
+fn is_valid_date(date: &str) -> bool {
+    static DATE_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"^(?<year>\d{4})-(?<month>\d{2})-(?<day>\d{2})$"#).unwrap());
+
+    if let Some(captures) = DATE_REGEX.captures(date)
+        && let Ok(year) = captures["year"].parse::<u32>()
+        && let Ok(month) = captures["month"].parse::<u32>()
+        && let Ok(day) = captures["day"].parse::<u32>()
+    {
+        let leap_year = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap_year => 29,
+            2 => 28,
+            _ => return false,
+        };
+
+        (1..=days_in_month).contains(&day)
+    } else {
+        false
+    }
+}
+
+fn parse_raw_board_text(raw_board_text: &str) -> anyhow::Result<(String, Vec<String>)> {
+    let board_regex = Regex::new(r#"(?<name>[^=\n]+)\n=+\n\n"#).unwrap();
+
+    let columns_regex = Regex::new(r#"- (?<column>[^\n]+)"#).unwrap();
+
+    let m_name = board_regex.captures(raw_board_text);
+    let m_columns = columns_regex.captures_iter(raw_board_text);
+
+    if let Some(captures_name) = m_name
+        && let Some(name) = captures_name.name("name")
+    {
+        let mut columns = vec![];
+
+        for cap in m_columns {
+            if let Some(column) = cap.name("column") {
+                columns.push(column.as_str().to_string())
+            }
+        }
+
+        if columns.is_empty() {
+            return Err(anyhow!("could not parse raw board text: bad columns"));
+        }
+
+        Ok((name.as_str().to_string(), columns))
+    } else {
+        Err(anyhow!("could not parse raw board text: bad board name"))
+    }
+}
+
+/// splits a stage marker like ` [doing]` or ` [done]` off the end of a column name
+fn parse_column_stage(column_name: &str) -> (&str, Option<Stage>) {
+    let column_name = column_name.trim_end();
+
+    if let Some(rest) = column_name.strip_suffix(']')
+        && let Some((name, stage)) = rest.rsplit_once('[')
+        && let Ok(stage) = stage.parse()
+    {
+        (name.trim_end(), Some(stage))
+    } else {
+        (column_name, None)
+    }
+}
+
+#[derive(Parser)]
+#[command(author, version, about, name = "kk")]
+struct Options {
+    #[arg(short, long, env)]
+    database_path: Option<PathBuf>,
+    #[arg(short = 'c', long, env, default_value = "#FF96A7")]
+    highlight_color: String,
+    /// run a single command against the database instead of starting the TUI
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum CliCommand {
+    /// list all boards, or the columns and cards of a single board
+    List {
+        /// the board to list. lists all boards if not given
+        board: Option<String>,
+        /// print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// add a card to a board
+    Add {
+        title: String,
+        #[arg(long, default_value = "")]
+        body: String,
+        /// the column to put the card in. defaults to the board's first column
+        #[arg(long)]
+        column: Option<String>,
+        /// tag the card. can be given more than once
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// when the card is due, like 2024-12-31
+        #[arg(long)]
+        due: Option<String>,
+        /// how urgent the card is, from P0 to P3
+        #[arg(short, long)]
+        priority: Option<Priority>,
+        /// defaults to the most recently viewed board
+        #[arg(short, long)]
+        board: Option<String>,
+    },
+    /// show a card
+    Show {
+        id: i64,
+        /// defaults to the most recently viewed board
+        #[arg(short, long)]
+        board: Option<String>,
+        /// print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// move a card to a different column
+    Move {
+        id: i64,
+        column: String,
+        /// defaults to the most recently viewed board
+        #[arg(short, long)]
+        board: Option<String>,
+    },
+    /// edit a card. opens $EDITOR if neither --title nor --body is given
+    Edit {
+        id: i64,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long)]
+        body: Option<String>,
+        /// defaults to the most recently viewed board
+        #[arg(short, long)]
+        board: Option<String>,
+    },
+    /// delete a card
+    Rm {
+        id: i64,
+        /// defaults to the most recently viewed board
+        #[arg(short, long)]
+        board: Option<String>,
+    },
+    /// export a board as markdown, json, csv or an Obsidian Kanban board
+    Export {
+        /// defaults to the most recently viewed board
+        #[arg(short, long)]
+        board: Option<String>,
+        /// markdown, json, csv, or obsidian for the Obsidian Kanban plugin
+        #[arg(short, long, default_value = "markdown")]
+        format: ExportFormat,
+        /// the file to write to. writes to stdout if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// create a board from a json, csv, Obsidian Kanban or Trello export, keeping its card numbers
+    Import {
+        file: PathBuf,
+        /// json, csv, obsidian, or trello for a board exported from Trello.
+        /// guessed from the file's extension if not given
+        #[arg(short, long)]
+        format: Option<ImportFormat>,
+        /// what to call the board. defaults to the name in a json file, or the name of a csv file
+        #[arg(short, long)]
+        board: Option<String>,
+        /// if there is already a board with the same name, import into it
+        /// instead of stopping. cards with the same number are replaced
+        #[arg(long)]
+        merge: bool,
+    },
+    /// keep a board and an Obsidian Kanban markdown file the same,
+    /// creating whichever one doesn't exist yet
+    Sync {
+        file: PathBuf,
+        /// defaults to the name of the file
+        #[arg(short, long)]
+        board: Option<String>,
+        /// keep going, syncing whenever either one changes
+        #[arg(short, long)]
+        watch: bool,
+    },
+}
+
+fn resolve_board_id(repo: &Repo, board_name: Option<&str>) -> anyhow::Result<BoardId> {
+    if let Some(board_name) = board_name {
+        repo.get_board_id_by_name(board_name)
+    } else {
+        repo.get_most_recently_viewed_board_id()?
+            .ok_or_else(|| anyhow!("there are no boards yet"))
+    }
+}
+
+/// one round of keeping a board and a markdown file the same.
+/// a file that has changed since the last round is merged into the board first,
+/// and then the board is written back to the file if they differ
+fn sync_markdown_file(
+    repo: &mut Repo,
+    board_name: &str,
+    path: &std::path::Path,
+) -> anyhow::Result<()> {
+    const SETTINGS: &str = "%% kanban:settings";
+
+    let path = std::path::absolute(path)?;
+    let path_key = path.to_string_lossy();
+
+    let file_text = match std::fs::read_to_string(&path) {
+        Ok(file_text) => Some(file_text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let board_id = match (repo.get_board_id_by_name(board_name), &file_text) {
+        (Ok(board_id), file_text) => {
+            let last_synced = repo.get_synced_file(board_id, &path_key)?;
+
+            if let Some(file_text) = file_text
+                && Some(file_text) != last_synced.as_ref()
+            {
+                // an editor that is partway through saving
+                if file_text.trim().is_empty() && last_synced.is_some() {
+                    return Ok(());
+                }
+
+                let last_synced =
+                    last_synced.map(|text| ImportedBoard::from_obsidian(board_name, &text));
+
+                repo.merge_synced_file(
+                    board_id,
+                    &ImportedBoard::from_obsidian(board_name, file_text),
+                    last_synced.as_ref(),
+                )?;
+            }
+
+            board_id
+        }
+        (Err(_), Some(file_text)) => {
+            repo.import_board(&ImportedBoard::from_obsidian(board_name, file_text), false)?
+        }
+        (Err(e), None) => return Err(e),
+    };
+
+    let mut contents = vec![];
+    repo.get_board(board_id)?.write_obsidian(&mut contents)?;
+    let mut contents = String::from_utf8(contents)?;
+
+    // the plugin's settings for the board
+    if let Some(settings) = file_text
+        .as_deref()
+        .and_then(|file_text| file_text.find(SETTINGS).map(|i| &file_text[i..]))
+    {
+        contents.push('\n');
+        contents.push_str(settings);
+    }
+
+    if file_text.as_ref() != Some(&contents) {
+        std::fs::write(&path, &contents)?;
+    }
+
+    if repo.get_synced_file(board_id, &path_key)?.as_ref() != Some(&contents) {
+        repo.set_synced_file(board_id, &path_key, &contents)?;
+    }
+
+    Ok(())
+}
+
+/// runs a single non-interactive command, writing any output to `out`
+fn run_command<W: Write>(repo: &mut Repo, command: CliCommand, out: &mut W) -> anyhow::Result<()> {
+    fn ensure_column_exists(repo: &Repo, board_id: BoardId, column: &str) -> anyhow::Result<()> {
+        if repo.column_exists(board_id, column)? {
+            Ok(())
+        } else {
+            Err(anyhow!("no column named {}", column))
+        }
+    }
+
+    fn write_json<W: Write, T: Serialize>(out: &mut W, value: &T) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, value)?;
+        writeln!(out)?;
+        Ok(())
+    }
+
+    match command {
+        CliCommand::List { board: None, json } => {
+            let board_metas = repo.get_board_metas()?;
+
+            if json {
+                return write_json(out, &board_metas);
+            }
+
+            for board in board_metas {
+                writeln!(
+                    out,
+                    "{}\t{}\tupdated {}\tviewed {}\tcreated {}",
+                    board.name,
+                    board.columns.join("|"),
+                    board.updated_at,
+                    board.viewed_at,
+                    board.inserted_at
+                )?;
+            }
+        }
+        CliCommand::List {
+            board: Some(board_name),
+            json,
+        } => {
+            let board_id = repo.get_board_id_by_name(&board_name)?;
+            let columns = repo.get_cards_for_board(board_id)?;
+
+            if json {
+                return write_json(
+                    out,
+                    &Board {
+                        id: board_id,
+                        name: board_name,
+                        sort_mode: repo.get_board_sort_mode(board_id)?,
+                        columns,
+                    },
+                );
+            }
+
+            for column in columns {
+                writeln!(out, "{}", column.name)?;
+                for card in column.cards {
+                    writeln!(out, "  {}\t{}", card.external_id, card.title)?;
+                }
+            }
+        }
+        CliCommand::Add {
+            title,
+            body,
+            column,
+            tags,
+            due,
+            priority,
+            board,
+        } => {
+            let board_id = resolve_board_id(repo, board.as_deref())?;
+
+            // everything is checked before anything is written,
+            // so a bad argument doesn't leave half a card behind
+            if let Some(column) = &column {
+                ensure_column_exists(repo, board_id, column)?;
+            }
+
+            if let Some(due) = &due
+                && !is_valid_date(due)
+            {
+                return Err(anyhow!("due dates look like 2024-12-31, not {}", due));
+            }
+
+            if let Some(tag) = tags.iter().find(|tag| tag.contains(',')) {
+                return Err(anyhow!("tags can't have commas in them: {}", tag));
+            }
+
+            let mut tags = tags;
+            tags.sort();
+            tags.dedup();
+
+            let card = repo.add_card(
+                board_id,
+                column.as_deref(),
+                &CardText {
+                    title,
+                    body,
+                    tags,
+                    due_on: due,
+                    priority,
+                },
+            )?;
+
+            writeln!(out, "{}", card.external_id)?;
+        }
+        CliCommand::Show { id, board, json } => {
+            let board_id = resolve_board_id(repo, board.as_deref())?;
+            let (card, column_name) = repo.get_card_by_external_id(board_id, ExternalCardId(id))?;
+
+            if json {
+                #[derive(Serialize)]
+                struct CardInColumn<'a> {
+                    #[serde(flatten)]
+                    card: &'a Card,
+                    column: &'a str,
+                }
+
+                return write_json(
+                    out,
+                    &CardInColumn {
+                        card: &card,
+                        column: &column_name,
+                    },
+                );
+            }
+
+            writeln!(out, "{} {}", card.external_id, card.title)?;
+            writeln!(out, "column: {}", column_name)?;
+            if let Some(priority) = card.priority {
+                writeln!(out, "priority: {}", priority)?;
+            }
+            if let Some(sent_from) = &card.sent_from {
+                writeln!(out, "sent from: {}", sent_from)?;
+            }
+            if !card.tags.is_empty() {
+                writeln!(out, "tags: {}", card.tags.join(", "))?;
+            }
+            if let Some(due_on) = &card.due_on {
+                writeln!(out, "due: {}", due_on)?;
+            }
+            writeln!(
+                out,
+                "created {}, updated {}",
+                card.inserted_at, card.updated_at
+            )?;
+            if let Some(doing_at) = &card.doing_at {
+                writeln!(out, "started {}", doing_at)?;
+            }
+            if let Some(done_at) = &card.done_at {
+                writeln!(out, "done {}", done_at)?;
+            }
+            if !card.body.is_empty() {
+                writeln!(out)?;
+                writeln!(out, "{}", card.body)?;
+            }
+        }
+        CliCommand::Move { id, column, board } => {
+            let board_id = resolve_board_id(repo, board.as_deref())?;
+            let (card, _) = repo.get_card_by_external_id(board_id, ExternalCardId(id))?;
+
+            ensure_column_exists(repo, board_id, &column)?;
+
+            repo.set_card_status(board_id, card.id, &column)?;
+        }
+        CliCommand::Edit {
+            id,
+            title,
+            body,
+            board,
+        } => {
+            let board_id = resolve_board_id(repo, board.as_deref())?;
+            let (card, _) = repo.get_card_by_external_id(board_id, ExternalCardId(id))?;
+
+            if title.is_none() && body.is_none() {
+                let text = edit_until_parsed(
+                    &card.text().to_editor_text(),
+                    edit_text,
+                    parse_raw_card_text,
+                )?;
+                repo.edit_card(board_id, card.id, &text)?;
+            } else {
+                repo.update_card(
+                    card.id,
+                    title.as_deref().unwrap_or(&card.title),
+                    body.as_deref().unwrap_or(&card.body),
+                )?;
+            }
+        }
+        CliCommand::Rm { id, board } => {
+            let board_id = resolve_board_id(repo, board.as_deref())?;
+            let (card, _) = repo.get_card_by_external_id(board_id, ExternalCardId(id))?;
+
+            repo.delete_card(card.id)?;
+        }
+        CliCommand::Export {
+            board,
+            format,
+            output,
+        } => {
+            let board_id = resolve_board_id(repo, board.as_deref())?;
+            let board = repo.get_board(board_id)?;
+
+            if let Some(output) = output {
+                let mut f = std::io::BufWriter::new(std::fs::File::create(output)?);
+                board.export(format, &mut f)?;
+                f.flush()?;
+            } else {
+                board.export(format, out)?;
+            }
+        }
+        CliCommand::Sync { file, board, watch } => {
+            let board_name = board.unwrap_or_else(|| {
+                file.file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into()
+            });
+
+            loop {
+                sync_markdown_file(repo, &board_name, &file)?;
+
+                if !watch {
+                    break;
+                }
+
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
+        CliCommand::Import {
+            file,
+            format,
+            board,
+            merge,
+        } => {
+            let format = format
+                .or_else(|| ImportFormat::from_extension(&file))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "can't tell what format {} is in, use --format",
+                        file.display()
+                    )
+                })?;
+
+            let text = std::fs::read_to_string(&file)?;
+
+            let mut imported_board = match format {
+                ImportFormat::Json => ImportedBoard::from_json(&text)?,
+                ImportFormat::Csv => ImportedBoard::from_csv(
+                    &file.file_stem().unwrap_or_default().to_string_lossy(),
+                    &text,
+                )?,
+                ImportFormat::Trello => ImportedBoard::from_trello(&text)?,
+                ImportFormat::Obsidian => ImportedBoard::from_obsidian(
+                    &file.file_stem().unwrap_or_default().to_string_lossy(),
+                    &text,
+                ),
+            };
+
+            if let Some(board) = board {
+                imported_board.name = board;
+            }
+
+            repo.import_board(&imported_board, merge)?;
+
+            writeln!(out, "{}", imported_board.name)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut options = Options::parse();
+
+    if let Some(command) = options.command.take() {
+        let mut repo = Repo::new(options.database_path)?;
+        return run_command(&mut repo, command, &mut std::io::stdout().lock());
+    }
+
+    let mut model = Model::new(options)?;
+
+    if let Some(board) = &model.board
+        && let Some(first_column) = board.columns.first()
+        && !first_column.cards.is_empty()
+    {
+        model.selected.card_index = Some(0);
+    }
+
+    ratatui::run(|terminal| -> anyhow::Result<()> {
+        while model.running_state != RunningState::Done {
+            // Render the current view
+            terminal.draw(|f| view(&mut model, f))?;
+
+            // Handle events and map to a Message
+            let mut current_msg = receive_event(&model)?;
+
+            // Process updates as long as they return a non-None message
+            while let Some(m) = current_msg {
+                match update(&mut model, m, terminal) {
+                    Ok(m) => current_msg = m,
+                    Err(e) => current_msg = Some(Message::SetError(Some(e.to_string()))),
+                }
+            }
+        }
+
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::Terminal;
+
+    use crate::{
+        BoardId, Card, CardId, ConfirmationState, ExternalCardId, Mode, Model, Options,
+        RunningState, update, update_with_run_editor_fn,
+    };
+
+    impl From<i64> for BoardId {
+        fn from(value: i64) -> Self {
+            Self(value)
+        }
+    }
+
+    impl From<i64> for CardId {
+        fn from(value: i64) -> Self {
+            Self(value)
+        }
+    }
+
+    impl From<i64> for ExternalCardId {
+        fn from(value: i64) -> Self {
+            Self(value)
+        }
+    }
+
+    /// right now, we don't care about comparing whether cards
+    /// have the same inserted_at and updated_at.
+    ///
+    /// we don't even use PartialEq in application code
+    impl PartialEq for Card {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id && self.title == other.title && self.body == other.body
+        }
+    }
+
+    /// shortcuts for setting up cards, which the app itself does with `add_card` and `edit_card`
+    impl crate::Repo {
+        fn insert_card(
+            &mut self,
+            board_id: crate::BoardId,
+            title: &str,
+            body: &str,
+        ) -> anyhow::Result<Card> {
+            let tx = self
+                .conn
+                .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+            let card = Self::write_new_card(&tx, board_id, title, body)?;
+
+            tx.commit()?;
+
+            Ok(card)
+        }
+
+        /// everything in a card's template besides its title and body
+        fn set_card_fields(
+            &mut self,
+            board_id: crate::BoardId,
+            card_id: crate::CardId,
+            text: &crate::CardText,
+        ) -> anyhow::Result<String> {
+            let tx = self
+                .conn
+                .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+
+            let updated_at = Self::write_card_fields(&tx, board_id, card_id, text)?;
+
+            tx.commit()?;
+
+            Ok(updated_at)
+        }
+    }
+
+    impl Options {
+        fn test_options() -> Options {
+            Options {
+                database_path: Some(":memory:".into()),
+                highlight_color: "#FF96A7".to_string(),
+                command: None,
+            }
+        }
+    }
+
+    /// setup and key presses that tests in more than one module need
+    mod helpers {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use ratatui::{Terminal, backend::TestBackend};
+
+        use crate::{Event, Message, Model, Options, handle_event, update};
+
+        /// "Board1" with "Todo" and "Done", and card3, card2, card1 top to bottom in "Todo"
+        pub(super) fn model_with_cards() -> Model {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal = Terminal::new(TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo", "Done"]).unwrap();
+            model.repo.insert_card(1.into(), "card1", "").unwrap();
+            model.repo.insert_card(1.into(), "card2", "").unwrap();
+            model.repo.insert_card(1.into(), "card3", "").unwrap();
+            model.selected.board_index = Some(0);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            model
+        }
+
+        /// the titles in a column of board 1, as they are in the database
+        pub(super) fn card_titles(model: &Model, column_name: &str) -> Vec<String> {
+            model
+                .repo
+                .cards_for_column(1.into(), column_name)
+                .unwrap()
+                .into_iter()
+                .map(|card| card.title)
+                .collect()
+        }
+
+        /// the titles in a column, as they are on screen
+        pub(super) fn titles(model: &Model, column_index: usize) -> Vec<&str> {
+            model.board.as_ref().unwrap().columns[column_index]
+                .cards
+                .iter()
+                .map(|card| card.title.as_str())
+                .collect()
+        }
+
+        pub(super) fn selected_title(model: &Model) -> &str {
+            &model.selected_card().unwrap().title
+        }
+
+        /// the message a key without modifiers maps to
+        pub(super) fn key(model: &Model, code: KeyCode) -> Option<Message> {
+            handle_event(
+                Event::KeyEvent(KeyEvent::new(code, KeyModifiers::NONE)),
+                model,
+            )
+        }
+
+        /// a key that must do something
+        pub(super) fn press(
+            model: &mut Model,
+            terminal: &mut Terminal<TestBackend>,
+            code: KeyCode,
+        ) -> anyhow::Result<()> {
+            let message = key(model, code).unwrap();
+
+            update(model, message, terminal).map(|_| ())
+        }
+
+        /// each character as a key press, with `\n` as enter. keys that do nothing are skipped
+        pub(super) fn type_keys(
+            model: &mut Model,
+            terminal: &mut Terminal<TestBackend>,
+            keys: &str,
+        ) -> anyhow::Result<()> {
+            for c in keys.chars() {
+                let code = if c == '\n' {
+                    KeyCode::Enter
+                } else {
+                    KeyCode::Char(c)
+                };
+
+                if let Some(message) = key(model, code) {
+                    update(model, message, terminal)?;
+                }
+            }
+
+            Ok(())
+        }
+
+        /// `text` into whatever prompt is open, whatever its keys would otherwise do
+        pub(super) fn type_text(
+            model: &mut Model,
+            terminal: &mut Terminal<TestBackend>,
+            text: &str,
+        ) {
+            for c in text.chars() {
+                update(model, Message::InputChar(c), terminal).unwrap();
+            }
+        }
+    }
+
+    mod create_board {
+        use ratatui::Terminal;
+
+        use crate::{Message, Model, Options, RunningState, update, update_with_run_editor_fn};
+
+        #[test]
+        fn with_zero_columns() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns invalid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Some Board Name\n==========\n\n".to_string())
+                },
+            );
+
+            assert!(update_result.is_err());
+
+            assert_eq!(model.running_state, RunningState::Running);
+        }
+
+        #[test]
+        fn with_at_least_one_column() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns invalid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Some Board Name\n==========\n\n- Todo".to_string())
+                },
+            );
+
+            assert!(update_result.is_ok());
+
+            assert_eq!(model.running_state, RunningState::Running);
+        }
+
+        #[test]
+        fn new_card_goes_to_correct_board_when_multiple_boards() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns invalid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Board1\n==========\n\n- Todo".to_string())
+                },
+            )
+            .unwrap();
+
+            assert_eq!(model.selected.board_index, Some(0));
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns invalid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Board2\n==========\n\n- Todo".to_string())
+                },
+            )
+            .unwrap();
+
+            assert_eq!(model.selected.board_index, Some(0));
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns invalid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Card1\n==========\n\n- Todo".to_string())
+                },
+            )
+            .unwrap();
+
+            assert!(model.board.is_some());
+
+            let board = model.board.unwrap();
+
+            assert_eq!(board.columns[0].cards[0].id, 1.into());
+            assert_eq!(board.columns[0].cards[0].title, "Card1");
+        }
+    }
+
+    mod new_card {
+        use crate::{
+            Card, Message, Model, Options, RunningState, update, update_with_run_editor_fn,
+        };
+        use ratatui::Terminal;
+
+        #[test]
+        fn with_bad_input() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns invalid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("bad input".to_string())
+                },
+            );
+
+            assert!(update_result.is_err());
+
+            assert_eq!(model.running_state, RunningState::Running);
+        }
+
+        #[test]
+        fn with_valid_input() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns valid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Valid Title\n==========\n\nValid card body".to_string())
+                },
+            );
+
+            assert!(update_result.is_ok());
+
+            assert_eq!(
+                model.board.unwrap().columns[0].cards,
+                vec![Card {
+                    id: 1.into(),
+                    external_id: 1.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                }]
+            );
+
+            assert_eq!(model.selected.column_index, Some(0));
+            assert_eq!(model.selected.card_index, Some(0));
+
+            assert_eq!(
+                model.repo.cards_for_column(1.into(), "Todo").unwrap(),
+                vec![Card {
+                    id: 1.into(),
+                    external_id: 1.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                }]
+            );
+
+            assert_eq!(model.running_state, RunningState::Running);
+        }
+    }
+
+    mod edit_card {
+        use crate::{
+            Card, Message, Model, Options, RunningState, update, update_with_run_editor_fn,
+        };
+        use ratatui::Terminal;
+
+        #[test]
+        fn with_bad_input() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns valid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Valid Title\n==========\n\nValid card body".to_string())
+                },
+            );
+
+            assert!(update_result.is_ok());
+
+            model.selected.column_index = Some(0);
+            model.selected.card_index = Some(0);
+
+            assert_eq!(model.selected.column_index, Some(0));
+            assert_eq!(model.selected.card_index, Some(0));
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::EditCard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns invalid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Bad input".to_string())
+                },
+            );
+
+            assert!(update_result.is_err());
+
+            assert_eq!(
+                model.board.unwrap().columns[0].cards,
+                vec![Card {
+                    id: 1.into(),
+                    external_id: 1.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                }]
+            );
+
+            assert_eq!(model.selected.column_index, Some(0));
+            assert_eq!(model.selected.card_index, Some(0));
+
+            assert_eq!(
+                model.repo.cards_for_column(1.into(), "Todo").unwrap(),
+                vec![Card {
+                    id: 1.into(),
+                    external_id: 1.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                }]
+            );
+
+            assert_eq!(model.running_state, RunningState::Running);
+        }
+
+        #[test]
+        fn with_valid_input() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns valid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Valid Title\n==========\n\nValid card body".to_string())
+                },
+            );
+
+            assert!(update_result.is_ok());
+
+            model.selected.column_index = Some(0);
+            model.selected.card_index = Some(0);
+
+            assert_eq!(model.selected.column_index, Some(0));
+            assert_eq!(model.selected.card_index, Some(0));
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::EditCard,
+                &mut terminal,
+                // replace default run_editor_fn with a stub that returns valid data
+                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                    Ok("Valid Title\n==========\n\nValid card body".to_string())
+                },
+            );
+
+            assert!(update_result.is_ok());
+
+            assert_eq!(
+                model.board.unwrap().columns[0].cards,
+                vec![Card {
+                    id: 1.into(),
+                    external_id: 1.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                }]
+            );
+
+            assert_eq!(model.selected.column_index, Some(0));
+            assert_eq!(model.selected.card_index, Some(0));
+
+            assert_eq!(
+                model.repo.cards_for_column(1.into(), "Todo").unwrap(),
+                vec![Card {
+                    id: 1.into(),
+                    external_id: 1.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                }]
+            );
+
+            assert_eq!(model.running_state, RunningState::Running);
+        }
+    }
+
+    #[test]
+    fn update_quit() {
+        let mut model = Model::new(Options::test_options()).unwrap();
+
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+        update(&mut model, crate::Message::Quit, &mut terminal).unwrap();
+
+        assert_eq!(model.running_state, RunningState::Done);
+    }
+
+    mod navigate_left {
+        use crate::{
+            Board, Card, Column, Message, Model, Options, RunningState, SelectedState, SortMode,
+            update, update_with_run_editor_fn,
+        };
+
+        #[test]
+        fn when_left() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update(&mut model, Message::NavigateLeft, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(0),
+                    card_index: None
+                }
+            );
+        }
+
+        #[test]
+        fn when_right_with_card() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model.board = Some(Board {
+                id: 1.into(),
+                name: "Board".to_string(),
+                sort_mode: SortMode::Manual,
+                columns: vec![
+                    Column {
+                        name: "Todo".to_string(),
+                        stage: None,
+                        cards: vec![Card {
+                            id: 1.into(),
+                            external_id: 1.into(),
+                            title: "great card".to_string(),
+                            body: "great body".to_string(),
+                            inserted_at: "".to_string(),
+                            updated_at: "".to_string(),
+                            doing_at: None,
+                            done_at: None,
+                            tags: vec![],
+                            due_on: None,
+                            priority: None,
+                            sent_from: None,
+                        }],
+                    },
+                    Column {
+                        name: "Doing".to_string(),
+                        stage: None,
+                        cards: vec![Card {
+                            id: 2.into(),
+                            external_id: 2.into(),
+                            title: "title 2".to_string(),
+                            body: "body 2".to_string(),
+                            inserted_at: "".to_string(),
+                            updated_at: "".to_string(),
+                            doing_at: None,
+                            done_at: None,
+                            tags: vec![],
+                            due_on: None,
+                            priority: None,
+                            sent_from: None,
+                        }],
+                    },
+                ],
+            });
+
+            model.selected.column_index = Some(1);
+            model.selected.card_index = Some(0);
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update(&mut model, crate::Message::NavigateLeft, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(0),
+                    card_index: Some(0)
+                }
+            );
+        }
+
+        #[test]
+        fn when_right_without_card() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model.board = Some(Board {
+                id: 1.into(),
+                name: "Board".to_string(),
+                sort_mode: SortMode::Manual,
+                columns: vec![
+                    Column {
+                        name: "Todo".to_string(),
+                        stage: None,
+                        cards: vec![],
+                    },
+                    Column {
+                        name: "Doing".to_string(),
+                        stage: None,
+                        cards: vec![Card {
+                            id: 2.into(),
+                            external_id: 1.into(),
+                            title: "title 2".to_string(),
+                            body: "body 2".to_string(),
+                            inserted_at: "".to_string(),
+                            updated_at: "".to_string(),
+                            doing_at: None,
+                            done_at: None,
+                            tags: vec![],
+                            due_on: None,
+                            priority: None,
+                            sent_from: None,
+                        }],
+                    },
+                ],
+            });
+
+            model.selected.column_index = Some(1);
+            model.selected.card_index = Some(0);
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update(&mut model, crate::Message::NavigateLeft, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(0),
+                    card_index: None
+                }
+            );
+        }
+    }
+
+    mod navigate_right {
+        use crate::{
+            Board, Card, Column, Model, Options, RunningState, SelectedState, SortMode, update,
+            update_with_run_editor_fn,
+        };
+
+        #[test]
+        fn when_right() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n- Doing\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update(&mut model, crate::Message::NavigateRight, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(1),
+                    card_index: None
+                }
+            );
+        }
+
+        #[test]
+        fn when_left_with_card() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model.board = Some(Board {
+                id: 1.into(),
+                name: "Board".to_string(),
+                sort_mode: SortMode::Manual,
+                columns: vec![
+                    Column {
+                        name: "Todo".to_string(),
+                        stage: None,
+                        cards: vec![Card {
+                            id: 1.into(),
+                            external_id: 1.into(),
+                            title: "great card".to_string(),
+                            body: "great body".to_string(),
+                            inserted_at: "".to_string(),
+                            updated_at: "".to_string(),
+                            doing_at: None,
+                            done_at: None,
+                            tags: vec![],
+                            due_on: None,
+                            priority: None,
+                            sent_from: None,
+                        }],
+                    },
+                    Column {
+                        name: "Doing".to_string(),
+                        stage: None,
+                        cards: vec![Card {
+                            id: 2.into(),
+                            external_id: 2.into(),
+                            title: "title 2".to_string(),
+                            body: "body 2".to_string(),
+                            inserted_at: "".to_string(),
+                            updated_at: "".to_string(),
+                            doing_at: None,
+                            done_at: None,
+                            tags: vec![],
+                            due_on: None,
+                            priority: None,
+                            sent_from: None,
+                        }],
+                    },
+                ],
+            });
+
+            model.selected.column_index = Some(0);
+            model.selected.card_index = Some(0);
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update(&mut model, crate::Message::NavigateRight, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(1),
+                    card_index: Some(0)
+                }
+            );
+        }
+
+        #[test]
+        fn when_left_without_card() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model.board = Some(Board {
+                id: 1.into(),
+                name: "Board".to_string(),
+                sort_mode: SortMode::Manual,
+                columns: vec![
+                    Column {
+                        name: "Todo".to_string(),
+                        stage: None,
+                        cards: vec![Card {
+                            id: 2.into(),
+                            external_id: 1.into(),
+                            title: "title 2".to_string(),
+                            body: "body 2".to_string(),
+                            inserted_at: "".to_string(),
+                            updated_at: "".to_string(),
+                            doing_at: None,
+                            done_at: None,
+                            tags: vec![],
+                            due_on: None,
+                            priority: None,
+                            sent_from: None,
+                        }],
+                    },
+                    Column {
+                        name: "Doing".to_string(),
+                        stage: None,
+                        cards: vec![],
+                    },
+                ],
+            });
+
+            model.selected.column_index = Some(1);
+            model.selected.card_index = Some(0);
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update(&mut model, crate::Message::NavigateRight, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(1),
+                    card_index: Some(0)
+                }
+            );
+        }
+    }
+
+    mod navigate_down {
+        use crate::{
+            Board, Card, Column, Model, Options, RunningState, SelectedState, SortMode, update,
+            update_with_run_editor_fn,
+        };
+
+        #[test]
+        fn when_length_is_one() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model.board = Some(Board {
+                id: 1.into(),
+                name: "Board".to_string(),
+                sort_mode: SortMode::Manual,
+                columns: vec![Column {
+                    name: "Todo".to_string(),
+                    stage: None,
+                    cards: vec![Card {
+                        id: 2.into(),
+                        external_id: 1.into(),
+                        title: "title 2".to_string(),
+                        body: "body 2".to_string(),
+                        inserted_at: "".to_string(),
+                        updated_at: "".to_string(),
+                        doing_at: None,
+                        done_at: None,
+                        tags: vec![],
+                        due_on: None,
+                        priority: None,
+                        sent_from: None,
+                    }],
+                }],
+            });
+
+            model.selected.column_index = Some(0);
+            model.selected.card_index = Some(0);
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(0),
+                    card_index: Some(0)
+                }
+            );
+        }
+
+        #[test]
+        fn when_length_is_greater_than_one() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                |_terminal, _template| Ok("card1\n=====\n\n- body1\n".to_string()),
+            )
+            .unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                |_terminal, _template| Ok("card2\n=====\n\n- body2\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(0),
+                    card_index: Some(1)
+                }
+            );
+        }
+    }
+
+    mod navigate_up {
+        use crate::{
+            Board, Card, Column, Model, Options, RunningState, SelectedState, SortMode, update,
+            update_with_run_editor_fn,
+        };
+
+        #[test]
+        fn when_length_is_one() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model.board = Some(Board {
+                id: 1.into(),
+                name: "Board".to_string(),
+                sort_mode: SortMode::Manual,
+                columns: vec![Column {
+                    name: "Todo".to_string(),
+                    stage: None,
+                    cards: vec![Card {
+                        id: 2.into(),
+                        external_id: 1.into(),
+                        title: "title 2".to_string(),
+                        body: "body 2".to_string(),
+                        inserted_at: "".to_string(),
+                        updated_at: "".to_string(),
+                        doing_at: None,
+                        done_at: None,
+                        tags: vec![],
+                        due_on: None,
+                        priority: None,
+                        sent_from: None,
+                    }],
+                }],
+            });
+
+            model.selected.column_index = Some(0);
+            model.selected.card_index = Some(0);
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, crate::Message::NavigateUp, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(0),
+                    card_index: Some(0)
+                }
+            );
+        }
+
+        #[test]
+        fn when_length_is_greater_than_one() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                |_terminal, _template| Ok("card1\n=====\n\n- body1\n".to_string()),
+            )
+            .unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                |_terminal, _template| Ok("card2\n=====\n\n- body2\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, crate::Message::NavigateUp, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(
+                model.selected,
+                SelectedState {
+                    board_index: None,
+                    column_index: Some(0),
+                    card_index: Some(0)
+                }
+            );
+        }
+    }
+
+    mod switch_to_moving_mode {
+        use crate::{
+            Message, Mode, Model, Options, RunningState, update, update_with_run_editor_fn,
+        };
+
+        #[test]
+        fn switches_with_card() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                |_terminal, _template| Ok("card1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+
+            update(&mut model, crate::Message::MoveCardMode, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(model.mode, Mode::MovingCard);
+        }
+
+        #[test]
+        fn does_not_switch_without_card() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+
+            update(&mut model, crate::Message::MoveCardMode, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(model.mode, Mode::ViewingBoard);
+        }
+    }
+
+    mod reorder_cards {
+        use super::helpers::{card_titles, model_with_cards, titles};
+        use crate::{Message, Mode, update};
+
+        #[test]
+        fn move_down_and_up_persists_order() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            assert_eq!(card_titles(&model, "Todo"), vec!["card3", "card2", "card1"]);
+
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::MovingCard);
+
+            update(&mut model, Message::MoveCardDown, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardDown, &mut terminal).unwrap();
+            // already at the bottom
+            update(&mut model, Message::MoveCardDown, &mut terminal).unwrap();
+
+            assert_eq!(model.selected.card_index, Some(2));
+            assert_eq!(card_titles(&model, "Todo"), vec!["card2", "card1", "card3"]);
+
+            update(&mut model, Message::MoveCardUp, &mut terminal).unwrap();
+
+            assert_eq!(model.selected.card_index, Some(1));
+            assert_eq!(card_titles(&model, "Todo"), vec!["card2", "card3", "card1"]);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert_eq!(titles(&model, 0), vec!["card2", "card3", "card1"]);
+            assert_eq!(model.selected_card().unwrap().title, "card3");
+        }
+
+        #[test]
+        fn moved_and_new_cards_go_to_the_top() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            // move card3 right, then card2 right
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardRight, &mut terminal).unwrap();
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            update(&mut model, Message::NavigateLeft, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardRight, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Done"), vec!["card2", "card3"]);
+
+            model.repo.insert_card(1.into(), "card4", "").unwrap();
+
+            assert_eq!(card_titles(&model, "Todo"), vec!["card4", "card1"]);
+        }
+    }
+
+    mod switch_to_view_card_detail_mode {
+        use crate::{
+            Message, Mode, Model, Options, RunningState, update, update_with_run_editor_fn,
+        };
+
+        #[test]
+        fn switches_when_column_is_not_empty() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewCard,
+                &mut terminal,
+                |_terminal, _template| Ok("card1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+
+            update(
+                &mut model,
+                crate::Message::ViewCardDetailMode,
+                &mut terminal,
+            )
+            .unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(model.mode, Mode::ViewingCardDetail);
+        }
+
+        #[test]
+        fn does_not_switch_when_column_is_empty() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+
+            update(
+                &mut model,
+                crate::Message::ViewCardDetailMode,
+                &mut terminal,
+            )
+            .unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(model.mode, Mode::ViewingBoard);
+        }
+    }
+
+    mod switch_to_viewing_board_mode {
+        use crate::{Mode, Model, Options, RunningState, update};
+
+        #[test]
+        fn switches() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.mode = Mode::ViewingCardDetail;
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(model.mode, Mode::ViewingBoard);
+
+            model.mode = Mode::MovingCard;
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(model.mode, Mode::ViewingBoard);
+        }
+    }
+
+    mod boards_view {
+        use crate::{Mode, Model, Options, update, update_with_run_editor_fn};
+
+        #[test]
+        fn navigate_down_with_one_board() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model.create_board("Board1", &["Todo"]).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update(&mut model, crate::Message::ViewBoardsMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+
+            assert_eq!(model.selected.board_index, Some(0));
+
+            assert!(model.board.is_none());
+
+            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+
+            assert_eq!(model.selected.board_index, Some(0));
+        }
+
+        #[test]
+        fn navigate_down_with_two_boards() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.selected.board_index, Some(0));
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board2\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.selected.board_index, Some(0));
+
+            assert!(model.board.is_none());
+
+            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+
+            assert_eq!(model.selected.board_index, Some(1));
+        }
+
+        #[test]
+        fn navigate_up_with_one_board() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+
+            model.create_board("Board1", &["Todo"]).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update(&mut model, crate::Message::ViewBoardsMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+
+            assert_eq!(model.selected.board_index, Some(0));
+
+            assert!(model.board.is_none());
+
+            update(&mut model, crate::Message::NavigateUp, &mut terminal).unwrap();
+
+            assert_eq!(model.selected.board_index, Some(0));
+        }
+
+        #[test]
+        fn navigate_up_with_two_boards() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            assert_eq!(model.selected.board_index, None);
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.selected.board_index, Some(0));
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board2\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+
+            assert_eq!(model.selected.board_index, Some(0));
+
+            assert!(model.board.is_none());
+
+            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+            assert_eq!(model.selected.board_index, Some(1));
+            update(&mut model, crate::Message::NavigateUp, &mut terminal).unwrap();
+            assert_eq!(model.selected.board_index, Some(0));
+        }
+    }
+
+    #[test]
+    fn delete_card() {
+        let mut model = Model::new(Options::test_options()).unwrap();
+
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+        update_with_run_editor_fn(
+            &mut model,
+            crate::Message::NewBoard,
+            &mut terminal,
+            |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+        )
+        .unwrap();
+
+        update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+        let update_result = update_with_run_editor_fn(
+            &mut model,
+            crate::Message::NewCard,
+            &mut terminal,
+            // replace default run_editor_fn with a stub that returns valid data
+            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                Ok("Valid Title\n==========\n\nValid card body".to_string())
+            },
+        );
+
+        assert!(update_result.is_ok());
+
+        let card = model.selected_card().unwrap();
+
+        assert_eq!(
+            &Card {
+                id: 1.into(),
+                external_id: 1.into(),
+                title: "Valid Title".to_string(),
+                body: "Valid card body".to_string(),
+                inserted_at: "".to_string(),
+                updated_at: "".to_string(),
+                doing_at: None,
+                done_at: None,
+                tags: vec![],
+                due_on: None,
+                priority: None,
+                sent_from: None,
+            },
+            card
+        );
+
+        let column = model.selected_column().unwrap();
+        assert!(!column.cards.is_empty());
+
+        update(&mut model, crate::Message::DeleteCard, &mut terminal).unwrap();
+        assert_eq!(model.confirmation_state, ConfirmationState::No);
+        assert_eq!(model.mode, Mode::ConfirmCardDeletion);
+
+        let column = model.selected_column().unwrap();
+        assert!(!column.cards.is_empty());
+
+        update(&mut model, crate::Message::NavigateLeft, &mut terminal).unwrap();
+        assert_eq!(model.confirmation_state, ConfirmationState::Yes);
+
+        update(&mut model, crate::Message::ConfirmChoice, &mut terminal).unwrap();
+        let column = model.selected_column().unwrap();
+        assert!(column.cards.is_empty());
+    }
+
+    #[test]
+    fn card_ids_are_unique_and_increment_per_board() {
+        let mut model = Model::new(Options::test_options()).unwrap();
+
+        assert_eq!(model.selected.column_index, None);
+        assert_eq!(model.selected.card_index, None);
+
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+        update_with_run_editor_fn(
+            &mut model,
+            crate::Message::NewBoard,
+            &mut terminal,
+            |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(model.selected.board_index, Some(0));
+
+        update_with_run_editor_fn(
+            &mut model,
+            crate::Message::NewBoard,
+            &mut terminal,
+            |_terminal, _template| Ok("Board2\n=====\n\n- Todo\n".to_string()),
+        )
+        .unwrap();
+
+        update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+        let update_result1 = update_with_run_editor_fn(
+            &mut model,
+            crate::Message::NewCard,
+            &mut terminal,
+            // replace default run_editor_fn with a stub that returns valid data
+            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                Ok("Valid Title\n==========\n\nValid card body".to_string())
+            },
+        );
+
+        assert!(&update_result1.is_ok());
+
+        let update_result2 = update_with_run_editor_fn(
+            &mut model,
+            crate::Message::NewCard,
+            &mut terminal,
+            // replace default run_editor_fn with a stub that returns valid data
+            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                Ok("Valid Title\n==========\n\nValid card body".to_string())
+            },
+        );
+
+        assert!(&update_result2.is_ok());
+
+        assert_eq!(
+            model.board.as_ref().unwrap().columns[0].cards,
+            vec![
+                Card {
+                    id: 2.into(),
+                    external_id: 2.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                },
+                Card {
+                    id: 1.into(),
+                    external_id: 1.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                },
+            ]
+        );
+
+        assert_eq!(model.selected.column_index, Some(0));
+        assert_eq!(model.selected.card_index, Some(0));
+
+        update(&mut model, crate::Message::ViewBoardsMode, &mut terminal).unwrap();
+        update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+        update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+
+        let update_result1 = update_with_run_editor_fn(
+            &mut model,
+            crate::Message::NewCard,
+            &mut terminal,
+            // replace default run_editor_fn with a stub that returns valid data
+            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                Ok("Valid Title\n==========\n\nValid card body".to_string())
+            },
+        );
+
+        assert!(&update_result1.is_ok());
+
+        let update_result2 = update_with_run_editor_fn(
+            &mut model,
+            crate::Message::NewCard,
+            &mut terminal,
+            // replace default run_editor_fn with a stub that returns valid data
+            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
+                Ok("Valid Title\n==========\n\nValid card body".to_string())
+            },
+        );
+
+        assert!(&update_result2.is_ok());
+
+        // ids here are 4 and 3, but external ids are 2 and 1,
+        // because we are creating ids specific to the other board,
+        // so they start from 1.
+        assert_eq!(
+            model.board.as_ref().unwrap().columns[0].cards,
+            vec![
+                Card {
+                    id: 4.into(),
+                    external_id: 2.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                },
+                Card {
+                    id: 3.into(),
+                    external_id: 1.into(),
+                    title: "Valid Title".to_string(),
+                    body: "Valid card body".to_string(),
+                    inserted_at: "".to_string(),
+                    updated_at: "".to_string(),
+                    doing_at: None,
+                    done_at: None,
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
+                    sent_from: None,
+                },
+            ]
+        );
+
+        assert_eq!(model.running_state, RunningState::Running);
+    }
+
+    mod delete_board {
+        use crate::{ConfirmationState, Message, Mode, Model, Options, update};
+
+        #[test]
+        fn deletes_board_and_its_cards_when_confirmed() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.create_board("Board2", &["Todo"]).unwrap();
+            model.repo.insert_card(1.into(), "card", "body").unwrap();
+            model.selected.board_index = model
+                .board_metas
+                .iter()
+                .position(|board| board.name == "Board1");
+
+            update(&mut model, Message::DeleteBoard, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ConfirmBoardDeletion);
+
+            update(&mut model, Message::NavigateLeft, &mut terminal).unwrap();
+            assert_eq!(model.confirmation_state, ConfirmationState::Yes);
+
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+            assert_eq!(model.confirmation_state, ConfirmationState::No);
+            assert_eq!(model.board_metas.len(), 1);
+            assert_eq!(model.board_metas[0].name, "Board2");
+            assert_eq!(model.selected.board_index, Some(0));
+
+            let remaining_cards: i64 = model
+                .repo
+                .conn
+                .query_one("select count(*) from cards", [], |row| row.get(0))
+                .unwrap();
+
+            assert_eq!(remaining_cards, 0);
+        }
+
+        #[test]
+        fn does_not_delete_board_when_cancelled() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.selected.board_index = Some(0);
+
+            update(&mut model, Message::DeleteBoard, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+            assert_eq!(model.board_metas.len(), 1);
+        }
+    }
+
+    mod edit_board_columns {
+        use super::helpers::card_titles;
+        use crate::{Message, Mode, Model, Options, update, update_with_run_editor_fn};
+
+        fn model_with_board() -> Model {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model
+                .create_board("Board1", &["Todo", "Doing", "Done"])
+                .unwrap();
+            model.selected.board_index = Some(0);
+
+            let todo_card = model.repo.insert_card(1.into(), "todo card", "").unwrap();
+            let doing_card = model.repo.insert_card(1.into(), "doing card", "").unwrap();
+            model
+                .repo
+                .set_card_status(1.into(), doing_card.id, "Doing")
+                .unwrap();
+            model
+                .repo
+                .set_card_status(1.into(), todo_card.id, "Todo")
+                .unwrap();
+
+            model
+        }
+
+        #[test]
+        fn rename_keeps_cards() {
+            let mut model = model_with_board();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditBoard,
+                &mut terminal,
+                |_terminal, _template| {
+                    Ok("Board1\n=====\n\n- Todo\n- Doing -> In Progress\n- Done\n".to_string())
+                },
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+            assert_eq!(
+                model.board_metas[0].columns,
+                vec!["Todo", "In Progress", "Done"]
+            );
+            assert_eq!(card_titles(&model, "In Progress"), vec!["doing card"]);
+        }
+
+        #[test]
+        fn renames_can_swap_names() {
+            let mut model = model_with_board();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditBoard,
+                &mut terminal,
+                |_terminal, _template| {
+                    Ok("Board1\n=====\n\n- Todo -> Doing\n- Doing -> Todo\n- Done\n".to_string())
+                },
+            )
+            .unwrap();
+
+            assert_eq!(model.board_metas[0].columns, vec!["Doing", "Todo", "Done"]);
+            assert_eq!(card_titles(&model, "Doing"), vec!["todo card"]);
+            assert_eq!(card_titles(&model, "Todo"), vec!["doing card"]);
+        }
+
+        #[test]
+        fn rename_of_unknown_column_is_an_error() {
+            let mut model = model_with_board();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            let update_result = update_with_run_editor_fn(
+                &mut model,
+                Message::EditBoard,
+                &mut terminal,
+                |_terminal, _template| {
+                    Ok("Board1\n=====\n\n- Todo\n- Nope -> Doing\n- Done\n".to_string())
+                },
+            );
+
+            assert!(update_result.is_err());
+            assert_eq!(model.board_metas[0].columns, vec!["Todo", "Doing", "Done"]);
+        }
+
+        #[test]
+        fn removing_an_empty_column_does_not_ask() {
+            let mut model = model_with_board();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n- Doing\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+            assert_eq!(model.board_metas[0].columns, vec!["Todo", "Doing"]);
+        }
+
+        #[test]
+        fn removing_a_column_with_cards_asks_where_they_go() {
+            let mut model = model_with_board();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n- Done\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ChoosingColumnDestination);
+            // nothing changes until we choose
+            assert_eq!(model.board_metas[0].columns, vec!["Todo", "Doing", "Done"]);
+
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+            assert!(model.board_edit.is_none());
+            assert_eq!(model.board_metas[0].columns, vec!["Todo", "Done"]);
+            assert_eq!(card_titles(&model, "Done"), vec!["doing card"]);
+            assert_eq!(card_titles(&model, "Todo"), vec!["todo card"]);
+        }
+
+        #[test]
+        fn removal_fails_when_cards_arrive_after_the_edit_started() {
+            let mut model = model_with_board();
+
+            // the editor saw an empty Done column
+            let board_edit = crate::BoardEdit {
+                board_id: 1.into(),
+                name: "Board1".to_string(),
+                columns: vec!["Todo".to_string(), "Doing".to_string()],
+                stages: vec![None, None],
+                renames: vec![],
+                removals: vec![crate::ColumnRemoval {
+                    column: "Done".to_string(),
+                    has_cards: false,
+                    destination: None,
+                }],
+                destination_index: 0,
+            };
+
+            let card = model.repo.insert_card(1.into(), "late card", "").unwrap();
+            model
+                .repo
+                .set_card_status(1.into(), card.id, "Done")
+                .unwrap();
+
+            let error = model.repo.update_board(&board_edit).unwrap_err();
+
+            assert!(error.to_string().contains("column Done has cards now"));
+            model.refresh_board_metas().unwrap();
+            assert_eq!(model.board_metas[0].columns, vec!["Todo", "Doing", "Done"]);
+            assert_eq!(card_titles(&model, "Done"), vec!["late card"]);
+        }
+
+        #[test]
+        fn cancelling_column_removal_changes_nothing() {
+            let mut model = model_with_board();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n- Done\n".to_string()),
+            )
+            .unwrap();
+
+            update(&mut model, Message::ViewBoardsMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoards);
+            assert!(model.board_edit.is_none());
+            assert_eq!(model.board_metas[0].columns, vec!["Todo", "Doing", "Done"]);
+            assert_eq!(card_titles(&model, "Doing"), vec!["doing card"]);
+        }
+    }
+
+    mod column_stages {
+        use crate::{Message, Model, Options, Stage, update_with_run_editor_fn};
+
+        #[test]
+        fn moving_cards_stamps_doing_at_and_done_at() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model
+                .create_board("Board1", &["Todo", "Doing [doing]", "Done [done]"])
+                .unwrap();
+
+            assert_eq!(model.board_metas[0].columns, vec!["Todo", "Doing", "Done"]);
+            assert_eq!(
+                model.board_metas[0].stages,
+                vec![None, Some(Stage::Doing), Some(Stage::Done)]
+            );
+
+            let card = model.repo.insert_card(1.into(), "card", "").unwrap();
+
+            let get_card = |model: &Model| {
+                model
+                    .repo
+                    .get_card_by_external_id(1.into(), card.external_id)
+                    .unwrap()
+                    .0
+            };
+
+            assert_eq!(get_card(&model).doing_at, None);
+            assert_eq!(get_card(&model).done_at, None);
+
+            model
+                .repo
+                .set_card_status(1.into(), card.id, "Doing")
+                .unwrap();
+
+            let doing_at = get_card(&model).doing_at;
+            assert!(doing_at.is_some());
+            assert_eq!(get_card(&model).done_at, None);
+
+            model
+                .repo
+                .set_card_status(1.into(), card.id, "Done")
+                .unwrap();
+
+            assert_eq!(get_card(&model).doing_at, doing_at);
+            assert!(get_card(&model).done_at.is_some());
+
+            // reopening a card keeps when it was started, but it is no longer done
+            model
+                .repo
+                .set_card_status(1.into(), card.id, "Todo")
+                .unwrap();
+
+            assert_eq!(get_card(&model).doing_at, doing_at);
+            assert_eq!(get_card(&model).done_at, None);
+        }
+
+        #[test]
+        fn new_cards_are_stamped_too() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model
+                .create_board("Board1", &["Doing [doing]", "Done [done]"])
+                .unwrap();
+
+            let card = model.repo.insert_card(1.into(), "card", "").unwrap();
+
+            assert!(card.doing_at.is_some());
+            assert_eq!(card.done_at, None);
+
+            let (stored, _) = model
+                .repo
+                .get_card_by_external_id(1.into(), card.external_id)
+                .unwrap();
+
+            assert_eq!(stored.doing_at, card.doing_at);
+        }
+
+        #[test]
+        fn stages_round_trip_through_the_editor() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n- Doing [doing]\n".to_string()),
+            )
+            .unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditBoard,
+                &mut terminal,
+                |_terminal, template| {
+                    assert_eq!(template, "Board1\n==========\n\n- Todo\n- Doing [doing]\n");
+                    Ok("Board1\n=====\n\n- Todo\n- Doing\n- Done [done]\n".to_string())
+                },
+            )
+            .unwrap();
+
+            assert_eq!(
+                model.board_metas[0].stages,
+                vec![None, None, Some(Stage::Done)]
+            );
+        }
+    }
+
+    mod metrics {
+        use crate::{BoardMetrics, Message, Mode, Model, Options, update, view};
+
+        #[test]
+        fn computes_cycle_time_lead_time_throughput_and_wip() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model
+                .create_board("Board1", &["Todo", "Doing [doing]", "Done [done]"])
+                .unwrap();
+
+            let started = model.repo.insert_card(1.into(), "started", "").unwrap();
+            let finished = model.repo.insert_card(1.into(), "finished", "").unwrap();
+            model.repo.insert_card(1.into(), "not started", "").unwrap();
+
+            model
+                .repo
+                .set_card_status(1.into(), started.id, "Doing")
+                .unwrap();
+            model
+                .repo
+                .set_card_status(1.into(), finished.id, "Done")
+                .unwrap();
+
+            model
+                .repo
+                .conn
+                .execute(
+                    "
+                    update cards
+                    set
+                        inserted_at = datetime('now', '-10 days'),
+                        doing_at = datetime('now', '-3 days'),
+                        done_at = datetime('now', '-1 days')
+                    where id = ?
+                    ",
+                    [finished.id],
+                )
+                .unwrap();
+
+            let metrics = model.repo.get_board_metrics(1.into()).unwrap();
+
+            assert_eq!(metrics.cycle_times.len(), 1);
+            assert!((metrics.cycle_times[0] - 2.0).abs() < 0.001);
+            assert_eq!(metrics.lead_times.len(), 1);
+            assert!((metrics.lead_times[0] - 9.0).abs() < 0.001);
+            assert_eq!(metrics.work_in_progress, 1);
+            assert_eq!(
+                metrics.weekly_throughput,
+                vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+            );
+            assert_eq!(
+                metrics.cycle_time_histogram(),
+                vec![
+                    ("<1d", 0),
+                    ("1-2d", 0),
+                    ("2-4d", 1),
+                    ("4-8d", 0),
+                    ("8-16d", 0),
+                    ("16d+", 0)
+                ]
+            );
+        }
+
+        #[test]
+        fn percentiles() {
+            let days = [5.0, 1.0, 4.0, 2.0, 3.0];
+
+            assert_eq!(BoardMetrics::percentile(&days, 50.0), Some(3.0));
+            assert_eq!(BoardMetrics::percentile(&days, 85.0), Some(5.0));
+            assert_eq!(BoardMetrics::percentile(&days, 0.0), Some(1.0));
+            assert_eq!(BoardMetrics::percentile(&[], 50.0), None);
+            assert_eq!(BoardMetrics::mean(&days), Some(3.0));
+        }
+
+        #[test]
+        fn switches_to_and_from_metrics_mode() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.selected.board_index = Some(0);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            update(&mut model, Message::ViewMetricsMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingMetrics);
+            assert_eq!(
+                model.metrics,
+                Some(BoardMetrics {
+                    weekly_throughput: vec![0; BoardMetrics::THROUGHPUT_WEEKS],
+                    ..Default::default()
+                })
+            );
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert!(model.metrics.is_none());
+        }
+    }
+
+    mod card_history {
+        use super::helpers::{model_with_cards, press};
+        use crate::{
+            CardEventKind, Message, Mode, Model, Options, update, update_with_run_editor_fn, view,
+        };
+        use crossterm::event::KeyCode;
+
+        #[test]
+        fn records_created_moved_edited_and_deleted() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            model.create_board("Board1", &["Todo", "Done"]).unwrap();
+
+            let card = model.repo.insert_card(1.into(), "first", "draft").unwrap();
+
+            model
+                .repo
+                .set_card_status(1.into(), card.id, "Done")
+                .unwrap();
+            // moving to the column it is already in is not a move
+            model
+                .repo
+                .set_card_status(1.into(), card.id, "Done")
+                .unwrap();
+            model.repo.update_card(card.id, "second", "final").unwrap();
+            // neither is an edit that changes nothing
+            model.repo.update_card(card.id, "second", "final").unwrap();
+            model.repo.delete_card(card.id).unwrap();
+
+            let events = model.repo.get_card_events(card.id).unwrap();
+
+            assert_eq!(
+                events
+                    .iter()
+                    .map(|event| (
+                        event.kind,
+                        event.title.as_str(),
+                        event.body.as_str(),
+                        event.previous_status.as_deref(),
+                        event.status.as_str()
+                    ))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (CardEventKind::Deleted, "second", "final", None, "Done"),
+                    (CardEventKind::Edited, "second", "final", None, "Done"),
+                    (CardEventKind::Moved, "first", "draft", Some("Todo"), "Done"),
+                    (CardEventKind::Created, "first", "draft", None, "Todo"),
+                ]
+            );
+
+            assert_eq!(events[2].describe(), "moved Todo → Done");
+
+            // the history outlives the card, but not the board
+            assert_eq!(model.repo.get_board_events(1.into()).unwrap().len(), 4);
+
+            model.repo.delete_board(1.into()).unwrap();
+
+            assert!(model.repo.get_card_events(card.id).unwrap().is_empty());
+        }
+
+        #[test]
+        fn removing_a_column_records_moves() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo", "Done"]).unwrap();
+            let card = model.repo.insert_card(1.into(), "card", "").unwrap();
+
+            model.switch_to_viewing_boards_mode().unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n======\n\n- Done".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ChoosingColumnDestination);
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::ConfirmChoice,
+                &mut terminal,
+                |_terminal, _template| unreachable!(),
+            )
+            .unwrap();
+
+            let events = model.repo.get_card_events(card.id).unwrap();
+
+            assert_eq!(events[0].kind, CardEventKind::Moved);
+            assert_eq!(events[0].describe(), "moved Todo → Done");
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+        }
+
+        #[test]
+        fn views_card_history_and_board_activity() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo", "Done"]).unwrap();
+            model.selected.board_index = Some(0);
+
+            let first = model.repo.insert_card(1.into(), "first", "").unwrap();
+            model.repo.insert_card(1.into(), "second", "").unwrap();
+            model
+                .repo
+                .set_card_status(1.into(), first.id, "Done")
+                .unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::ViewBoardMode,
+                &mut terminal,
+                |_terminal, _template| unreachable!(),
+            )
+            .unwrap();
+
+            // `second` is selected, at the top of Todo
+            update_with_run_editor_fn(
+                &mut model,
+                Message::ViewCardHistoryMode,
+                &mut terminal,
+                |_terminal, _template| unreachable!(),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingActivity);
+            let activity = model.activity.as_ref().unwrap();
+            assert_eq!(activity.title, "2 second");
+            assert_eq!(activity.events.len(), 1);
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::ViewBoardMode,
+                &mut terminal,
+                |_terminal, _template| unreachable!(),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert!(model.activity.is_none());
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::ViewBoardActivityMode,
+                &mut terminal,
+                |_terminal, _template| unreachable!(),
+            )
+            .unwrap();
+
+            assert_eq!(model.activity.as_ref().unwrap().events.len(), 3);
+
+            for _ in 0..5 {
+                update_with_run_editor_fn(
+                    &mut model,
+                    Message::NavigateDown,
+                    &mut terminal,
+                    |_terminal, _template| unreachable!(),
+                )
+                .unwrap();
+            }
+
+            let activity = model.activity.as_ref().unwrap();
+            assert_eq!(activity.selected, 2);
+            assert_eq!(activity.events[activity.selected].title, "first");
+            assert_eq!(
+                activity.events[activity.selected].kind,
+                CardEventKind::Created
+            );
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+        }
+
+        #[test]
+        fn card_history_goes_back_to_the_card() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, Message::ViewCardDetailMode, &mut terminal).unwrap();
+            update(&mut model, Message::ViewCardHistoryMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ViewingActivity);
+
+            press(&mut model, &mut terminal, KeyCode::Esc).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingCardDetail);
+            assert!(model.activity.is_none());
+        }
+    }
+
+    mod undo_redo {
+        use super::helpers::{card_titles, key, model_with_cards};
+        use crate::{
+            CardEventKind, ConfirmationState, Event, Message, Model, Operation, handle_event,
+            update, update_with_run_editor_fn,
+        };
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        #[test]
+        fn keys() {
+            let model = model_with_cards();
+
+            assert_eq!(key(&model, KeyCode::Char('u')), Some(Message::Undo));
+            assert_eq!(
+                handle_event(
+                    Event::KeyEvent(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+                    &model
+                ),
+                Some(Message::Redo)
+            );
+            assert_eq!(key(&model, KeyCode::Char('r')), None);
+        }
+
+        #[test]
+        fn undo_and_redo_a_move() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardRight, &mut terminal).unwrap();
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Done"), vec!["card3"]);
+
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Todo"), vec!["card3", "card2", "card1"]);
+            assert!(card_titles(&model, "Done").is_empty());
+            assert_eq!(model.selected.column_index, Some(0));
+            assert_eq!(model.selected_card().unwrap().title, "card3");
+
+            // nothing left to undo
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            update(&mut model, Message::Redo, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Done"), vec!["card3"]);
+            assert_eq!(model.selected.column_index, Some(1));
+            assert_eq!(model.selected_card().unwrap().title, "card3");
+        }
+
+        #[test]
+        fn undo_and_redo_an_edit() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditCard,
+                &mut terminal,
+                |_terminal, _template| Ok("edited\n======\n\nnew body".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(
+                card_titles(&model, "Todo"),
+                vec!["edited", "card2", "card1"]
+            );
+
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Todo"), vec!["card3", "card2", "card1"]);
+            assert_eq!(model.selected_card().unwrap().body, "");
+
+            update(&mut model, Message::Redo, &mut terminal).unwrap();
+
+            assert_eq!(model.selected_card().unwrap().title, "edited");
+            assert_eq!(model.selected_card().unwrap().body, "new body");
+        }
+
+        #[test]
+        fn undo_a_delete_restores_the_card_where_it_was() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
+
+            let deleted = model.selected_card().unwrap();
+            let (card_id, external_id, inserted_at) =
+                (deleted.id, deleted.external_id, deleted.inserted_at.clone());
+
+            update(&mut model, Message::DeleteCard, &mut terminal).unwrap();
+            model.confirmation_state = ConfirmationState::Yes;
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Todo"), vec!["card3", "card1"]);
+
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Todo"), vec!["card3", "card2", "card1"]);
+
+            let restored = model.selected_card().unwrap();
+            assert_eq!(model.selected.card_index, Some(1));
+            assert_eq!(restored.id, card_id);
+            assert_eq!(restored.external_id, external_id);
+            assert_eq!(restored.inserted_at, inserted_at);
+
+            assert_eq!(
+                model.repo.get_card_events(card_id).unwrap()[0].kind,
+                CardEventKind::Restored
+            );
+
+            update(&mut model, Message::Redo, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Todo"), vec!["card3", "card1"]);
+        }
+
+        #[test]
+        fn restoring_a_card_whose_id_was_taken() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            // card3 has the highest id, so the next card gets it
+            update(&mut model, Message::DeleteCard, &mut terminal).unwrap();
+            model.confirmation_state = ConfirmationState::Yes;
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            let card4 = model.repo.insert_card(1.into(), "card4", "").unwrap();
+
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert_eq!(
+                card_titles(&model, "Todo"),
+                vec!["card3", "card4", "card2", "card1"]
+            );
+
+            let restored = model.selected_card().unwrap();
+            assert_eq!(restored.title, "card3");
+            assert_ne!(restored.id, card4.id);
+            assert_eq!(restored.external_id, 3.into());
+
+            // redo deletes the restored card, not the one that took its id
+            update(&mut model, Message::Redo, &mut terminal).unwrap();
+
+            assert_eq!(card_titles(&model, "Todo"), vec!["card4", "card2", "card1"]);
+        }
+
+        #[test]
+        fn new_operations_clear_redo() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardRight, &mut terminal).unwrap();
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert_eq!(model.redo_stack.len(), 1);
+
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardRight, &mut terminal).unwrap();
+
+            assert!(model.redo_stack.is_empty());
+            assert_eq!(model.undo_stack.len(), 1);
+
+            for _ in 0..Model::UNDO_LIMIT + 1 {
+                update(&mut model, Message::MoveCardLeft, &mut terminal).unwrap();
+                update(&mut model, Message::MoveCardRight, &mut terminal).unwrap();
+            }
+
+            assert_eq!(model.undo_stack.len(), Model::UNDO_LIMIT);
+        }
+
+        #[test]
+        fn a_batch_that_fails_is_undone_all_or_nothing() {
+            let mut model = model_with_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            let card_ids = model.board.as_ref().unwrap().columns[0]
+                .cards
+                .iter()
+                .map(|card| card.id)
+                .collect::<Vec<_>>();
+
+            model
+                .repo
+                .set_card_status(1.into(), card_ids[0], "Done")
+                .unwrap();
+            model
+                .repo
+                .set_card_status(1.into(), card_ids[1], "Done")
+                .unwrap();
+
+            // undone last first, so the good move happens before the bad one fails
+            model.record_operation(Operation::Batch(vec![
+                Operation::Move {
+                    card_id: card_ids[0],
+                    from: "Gone".to_string(),
+                    to: "Done".to_string(),
+                },
+                Operation::Move {
+                    card_id: card_ids[1],
+                    from: "Todo".to_string(),
+                    to: "Done".to_string(),
+                },
+            ]));
+
+            assert!(update(&mut model, Message::Undo, &mut terminal).is_err());
+
+            assert_eq!(card_titles(&model, "Done"), vec!["card2", "card3"]);
+            assert_eq!(model.undo_stack.len(), 1);
+            assert!(model.redo_stack.is_empty());
+        }
     }
-}
 
-fn update<B>(
-    model: &mut Model,
-    msg: Message,
-    terminal: &mut Terminal<B>,
-) -> anyhow::Result<Option<Message>>
-where
-    B: Backend,
-    B::Error: Send + Sync + 'static,
-{
-    update_with_run_editor_fn(model, msg, terminal, run_editor)
-}
+    mod tags {
+        use super::helpers::titles;
+        use crate::{
+            CardEventKind, CardText, Message, Mode, Model, Options, parse_raw_card_text, update,
+            update_with_run_editor_fn, view,
+        };
 
-/// this exists only so we can mock out the run_editor function,
-/// which in the real program actually opens the user's editor.
-/// we can't do this in tests, so we need to mock it out
-/// with a function that just returns whatever data
-/// we tell it to, depending on the desired test condition
-fn update_with_run_editor_fn<F, B>(
-    model: &mut Model,
-    msg: Message,
-    terminal: &mut Terminal<B>,
-    run_editor_fn: F,
-) -> anyhow::Result<Option<Message>>
-where
-    F: Fn(&mut Terminal<B>, &str) -> anyhow::Result<String>,
-    B: Backend,
-{
-    match model.mode {
-        Mode::ViewingBoard => {
-            match msg {
-                Message::ViewBoardsMode => model.switch_to_viewing_boards_mode()?,
-                Message::MoveCardMode => {
-                    if model.selected.card_index.is_some() {
-                        model.mode = Mode::MovingCard
-                    }
-                }
-                Message::ViewCardDetailMode => {
-                    if let Some(column) = model.selected_column()
-                        && !column.cards.is_empty()
-                    {
-                        model.mode = Mode::ViewingCardDetail
-                    }
-                }
-                Message::Quit => model.running_state = RunningState::Done,
-                Message::NavigateLeft => model.navigate_left(),
-                Message::NavigateDown => {
-                    model.selected.card_index = model.selected.card_index.map(|i| {
-                        min(
-                            i.saturating_add(1),
-                            model
-                                .selected_column()
-                                .map(|column| column.cards.len().saturating_sub(1))
-                                .unwrap_or(usize::MAX),
-                        )
-                    })
+        fn model_with_tagged_cards() -> Model {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo", "Done"]).unwrap();
+            model.selected.board_index = Some(0);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            for raw_card_text in [
+                "chore\n=====\ntags: chore\n\n",
+                "bug one\n=====\ntags: bug, ui\n\n",
+                "feature\n=====\n\n",
+                "bug two\n=====\ntags: bug\n\n",
+            ] {
+                update_with_run_editor_fn(
+                    &mut model,
+                    Message::NewCard,
+                    &mut terminal,
+                    |_terminal, _template| Ok(raw_card_text.to_string()),
+                )
+                .unwrap();
+            }
+
+            model
+        }
+
+        #[test]
+        fn parses_tags_from_the_card_template() {
+            assert_eq!(
+                parse_raw_card_text("title\n=====\ntags: ui,  bug, ui,\n\nbody\n\nmore").unwrap(),
+                CardText {
+                    title: "title".to_string(),
+                    body: "body\n\nmore".to_string(),
+                    tags: vec!["bug".to_string(), "ui".to_string()],
+                    due_on: None,
+                    priority: None,
                 }
-                Message::NavigateUp => {
-                    model.selected.card_index =
-                        model.selected.card_index.map(|i| i.saturating_sub(1))
+            );
+
+            // fields are optional
+            assert_eq!(
+                parse_raw_card_text("title\n=====\n\nbody").unwrap(),
+                CardText {
+                    title: "title".to_string(),
+                    body: "body".to_string(),
+                    tags: vec![],
+                    due_on: None,
+                    priority: None,
                 }
-                Message::NavigateRight => model.navigate_right(),
-                Message::NewCard => {
-                    let raw_card_text =
-                        run_editor_fn(terminal, "Title\n==========\n\nContent goes here")?;
-                    let (title, body) = parse_raw_card_text(&raw_card_text)?;
+            );
 
-                    let board_id = if let Some(board) = &model.board {
-                        board.id
-                    } else {
-                        panic!()
-                    };
+            assert_eq!(
+                parse_raw_card_text("title\n=====\ntags:\n\nbody")
+                    .unwrap()
+                    .tags,
+                Vec::<String>::new()
+            );
+
+            assert!(parse_raw_card_text("title\n=====\ncolour: red\n\nbody").is_err());
+        }
+
+        #[test]
+        fn tags_round_trip_through_the_editor() {
+            let mut model = model_with_tagged_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            // "bug two" is on top
+            assert_eq!(model.selected_card().unwrap().tags, vec!["bug"]);
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditCard,
+                &mut terminal,
+                |_terminal, template| {
+                    assert_eq!(
+                        template,
+                        "bug two\n==========\npriority: \ntags: bug\ndue: \n\n"
+                    );
+                    Ok("bug two\n=====\ntags: bug, urgent\n\n".to_string())
+                },
+            )
+            .unwrap();
+
+            assert_eq!(model.selected_card().unwrap().tags, vec!["bug", "urgent"]);
+            assert_eq!(
+                model.repo.get_board_tags(1.into()).unwrap(),
+                vec!["bug", "chore", "ui", "urgent"]
+            );
+
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert_eq!(model.selected_card().unwrap().tags, vec!["bug"]);
+            // tags nothing uses anymore go away
+            assert_eq!(
+                model.repo.get_board_tags(1.into()).unwrap(),
+                vec!["bug", "chore", "ui"]
+            );
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+        }
+
+        #[test]
+        fn filtering_by_tag_hides_other_cards() {
+            let mut model = model_with_tagged_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update(&mut model, Message::FilterByTagMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ChoosingTagFilter);
+            assert_eq!(
+                model.tag_picker.as_ref().unwrap().tags,
+                vec!["bug", "chore", "ui"]
+            );
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(model.tag_filter.as_deref(), Some("bug"));
+            assert_eq!(titles(&model, 0), vec!["bug two", "bug one"]);
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            // reordering a filtered column leaves hidden cards where they were
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardDown, &mut terminal).unwrap();
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert_eq!(titles(&model, 0), vec!["bug one", "bug two"]);
+
+            update(&mut model, Message::ClearTagFilter, &mut terminal).unwrap();
+
+            assert_eq!(
+                titles(&model, 0),
+                vec!["bug one", "feature", "bug two", "chore"]
+            );
+            assert_eq!(model.selected_card().unwrap().title, "bug two");
+        }
+
+        #[test]
+        fn new_cards_get_the_filtered_tag() {
+            let mut model = model_with_tagged_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.set_tag_filter(Some("chore".to_string())).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::NewCard,
+                &mut terminal,
+                |_terminal, template| {
+                    assert!(template.contains("\ntags: chore\n"));
+                    Ok(template.replace("Title", "another chore"))
+                },
+            )
+            .unwrap();
+
+            assert_eq!(titles(&model, 0), vec!["another chore", "chore"]);
+        }
+
+        #[test]
+        fn no_tags_to_filter_by() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.selected.board_index = Some(0);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            assert!(update(&mut model, Message::FilterByTagMode, &mut terminal).is_err());
+            assert_eq!(model.mode, Mode::ViewingBoard);
+        }
+
+        #[test]
+        fn changing_only_tags_is_an_edit() {
+            let mut model = model_with_tagged_cards();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            let card_id = model.selected_card().unwrap().id;
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditCard,
+                &mut terminal,
+                |_terminal, template| Ok(template.replace("tags: bug", "tags: bug, urgent")),
+            )
+            .unwrap();
+
+            assert_eq!(model.selected_card().unwrap().tags, ["bug", "urgent"]);
+
+            let kinds = model
+                .repo
+                .get_card_events(card_id)
+                .unwrap()
+                .into_iter()
+                .map(|event| event.kind)
+                .collect::<Vec<_>>();
+
+            assert!(kinds.contains(&CardEventKind::Edited));
+        }
+
+        #[test]
+        fn tags_cant_have_commas() {
+            let mut model = model_with_tagged_cards();
+
+            let board_id = model.board.as_ref().unwrap().id;
+            let card = model.selected_card().unwrap().clone();
+
+            let result = model.repo.edit_card(
+                board_id,
+                card.id,
+                &CardText {
+                    title: "renamed".to_string(),
+                    tags: vec!["a,b".to_string()],
+                    ..card.text()
+                },
+            );
+
+            assert!(result.is_err());
+            // nothing else about the card was written either
+            assert_eq!(
+                model.repo.get_card(card.id).unwrap().unwrap().title,
+                card.title
+            );
+        }
+    }
+
+    mod due_dates {
+        use crate::{
+            DueStatus, DueThresholds, Message, Mode, Model, Options, is_valid_date,
+            parse_raw_card_text, update, update_with_run_editor_fn, view,
+        };
+
+        #[test]
+        fn parses_due_dates_from_the_card_template() {
+            assert_eq!(
+                parse_raw_card_text("title\n=====\ndue: 2024-02-29\n\nbody")
+                    .unwrap()
+                    .due_on
+                    .as_deref(),
+                Some("2024-02-29")
+            );
+            assert_eq!(
+                parse_raw_card_text("title\n=====\ntags: a\ndue:\n\nbody")
+                    .unwrap()
+                    .due_on,
+                None
+            );
+            assert!(parse_raw_card_text("title\n=====\ndue: tomorrow\n\nbody").is_err());
+
+            assert!(is_valid_date("2000-02-29"));
+            assert!(!is_valid_date("1900-02-29"));
+            assert!(!is_valid_date("2023-02-29"));
+            assert!(!is_valid_date("2024-04-31"));
+            assert!(!is_valid_date("2024-13-01"));
+            assert!(!is_valid_date("2024-1-01"));
+        }
+
+        #[test]
+        fn due_status() {
+            let due_thresholds = DueThresholds {
+                today: "2024-06-10".to_string(),
+                soon: "2024-06-13".to_string(),
+            };
+
+            assert_eq!(due_thresholds.status("2024-06-09"), DueStatus::Overdue);
+            assert_eq!(due_thresholds.status("2024-06-10"), DueStatus::Soon);
+            assert_eq!(due_thresholds.status("2024-06-13"), DueStatus::Soon);
+            assert_eq!(due_thresholds.status("2024-06-14"), DueStatus::Later);
+        }
+
+        #[test]
+        fn due_dates_round_trip_through_the_editor() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.selected.board_index = Some(0);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::NewCard,
+                &mut terminal,
+                |_terminal, _template| Ok("release\n=====\ndue: 2000-01-01\n\n".to_string()),
+            )
+            .unwrap();
+
+            assert_eq!(
+                model.selected_card().unwrap().due_on.as_deref(),
+                Some("2000-01-01")
+            );
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditCard,
+                &mut terminal,
+                |_terminal, template| {
+                    assert_eq!(
+                        template,
+                        "release\n==========\npriority: \ntags: \ndue: 2000-01-01\n\n"
+                    );
+                    Ok("release\n=====\ndue:\n\n".to_string())
+                },
+            )
+            .unwrap();
+
+            assert_eq!(model.selected_card().unwrap().due_on, None);
+            assert_eq!(
+                model.repo.cards_for_column(1.into(), "Todo").unwrap()[0].due_on,
+                None
+            );
+        }
+
+        #[test]
+        fn agenda_lists_due_cards_across_boards() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model
+                .create_board("Board1", &["Todo", "Done [done]"])
+                .unwrap();
+            model.create_board("Board2", &["Todo"]).unwrap();
+            model.create_board("Archived", &["Todo"]).unwrap();
+
+            let mut due = |board_id: i64, title: &str, due_on: Option<&str>| {
+                let card = model.repo.insert_card(board_id.into(), title, "").unwrap();
+                model
+                    .repo
+                    .conn
+                    .execute(
+                        "update cards set due_on = ? where id = ?",
+                        rusqlite::params![due_on, card.id],
+                    )
+                    .unwrap();
+                card
+            };
+
+            due(1, "later", Some("2999-01-01"));
+            let finished = due(1, "finished", Some("2000-01-01"));
+            due(1, "whenever", None);
+            let overdue = due(2, "overdue", Some("2000-01-02"));
+            due(3, "archived", Some("2000-01-01"));
+
+            model
+                .repo
+                .set_card_status(1.into(), finished.id, "Done")
+                .unwrap();
+            model.repo.set_board_archived(3.into(), true).unwrap();
+
+            model.switch_to_viewing_boards_mode().unwrap();
+
+            update(&mut model, Message::ViewAgendaMode, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingAgenda);
+            assert_eq!(
+                model
+                    .agenda
+                    .as_ref()
+                    .unwrap()
+                    .items
+                    .iter()
+                    .map(|item| (item.board_name.as_str(), item.card.title.as_str()))
+                    .collect::<Vec<_>>(),
+                vec![("Board2", "overdue"), ("Board1", "later")]
+            );
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            // back to where it was opened from
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ViewingBoards);
+
+            update(&mut model, Message::ViewAgendaMode, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-                    let card = model.repo.insert_card(board_id, title, body)?;
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert!(model.agenda.is_none());
+            assert_eq!(model.board.as_ref().unwrap().name, "Board2");
+            assert_eq!(model.selected_card_id(), Some(overdue.id));
 
-                    model.mode = Mode::ViewingBoard;
-                    model.selected.column_index = Some(0);
-                    model.selected.card_index = Some(0);
+            update(&mut model, Message::ViewAgendaMode, &mut terminal).unwrap();
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ViewingBoard);
+        }
 
-                    model.add_card_to_selected_column(card);
-                }
-                Message::EditCard => {
-                    if let Some(card) = model.selected_card() {
-                        let card_for_editor =
-                            format!("{}\n==========\n\n{}", card.title, card.body);
+        #[test]
+        fn enter_on_empty_agenda_keeps_it_open() {
+            let mut model = Model::new(Options::test_options()).unwrap();
 
-                        let raw_card_text = run_editor_fn(terminal, &card_for_editor)?;
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-                        let (title, body) = parse_raw_card_text(&raw_card_text)?;
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.repo.insert_card(1.into(), "undated", "").unwrap();
+            model.switch_to_viewing_boards_mode().unwrap();
 
-                        let updated_at = model.repo.update_card(card.id, title, body)?;
+            update(&mut model, Message::ViewAgendaMode, &mut terminal).unwrap();
+            assert!(model.agenda.as_ref().unwrap().items.is_empty());
 
-                        // dumb but necessary to reborrow because we previously borrow the model immutably
-                        if let Some(card) = model.selected_card_mut() {
-                            card.title = title.to_string();
-                            card.body = body.to_string();
-                            card.updated_at = updated_at;
-                        }
-                    }
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-                    model.mode = Mode::ViewingBoard;
-                }
-                Message::DeleteCard => model.confirm_card_delete()?,
-                Message::SetError(e) => {
-                    model.error = e;
-                    let internal_event_tx = model.internal_event_tx.clone();
-                    std::thread::spawn(move || {
-                        std::thread::sleep(std::time::Duration::from_secs(10));
-                        let _ =
-                            internal_event_tx.send(Event::InternalEvent(InternalEvent::ClearError));
-                    });
-                }
-                m => panic!("unhandled message: {:?}", m),
-            };
+            assert_eq!(model.mode, Mode::ViewingAgenda);
+            assert!(model.agenda.is_some());
+            terminal.draw(|f| view(&mut model, f)).unwrap();
         }
-        Mode::ViewingCardDetail => match msg {
-            Message::ViewBoardMode => model.mode = Mode::ViewingBoard,
-            Message::Quit => model.running_state = RunningState::Done,
-            Message::EditCard => {
-                if let Some(card) = model.selected_card() {
-                    let card_for_editor = format!("{}\n==========\n\n{}", card.title, card.body);
+    }
 
-                    let raw_card_text = run_editor_fn(terminal, &card_for_editor)?;
+    #[test]
+    fn archive_and_unarchive_board() {
+        let mut model = Model::new(Options::test_options()).unwrap();
 
-                    let (title, body) = parse_raw_card_text(&raw_card_text)?;
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-                    let updated_at = model.repo.update_card(card.id, title, body)?;
+        model.create_board("Board1", &["Todo"]).unwrap();
+        model.selected.board_index = Some(0);
 
-                    // dumb but necessary to reborrow because we previously borrow the model immutably
-                    if let Some(card) = model.selected_card_mut() {
-                        card.title = title.to_string();
-                        card.body = body.to_string();
-                        card.updated_at = updated_at;
-                    }
-                }
-            }
-            m => panic!("unhandled message: {:?}", m),
-        },
-        Mode::MovingCard => match msg {
-            Message::MoveCardLeft => model.move_selected_card_left()?,
-            Message::MoveCardRight => model.move_selected_card_right()?,
-            Message::ViewBoardMode => {
-                model.mode = Mode::ViewingBoard;
-                if let Some(board) = model.board.as_mut() {
-                    for column in &mut board.columns {
-                        column.cards.sort_unstable_by(|a, b| b.id.cmp(&a.id));
-                    }
-                }
-            }
-            m => panic!("unhandled message: {:?}", m),
-        },
-        Mode::ConfirmCardDeletion => match msg {
-            Message::ConfirmChoice => match model.confirmation_state {
-                ConfirmationState::Yes => {
-                    model.delete_selected_card()?;
-                    model.mode = Mode::ViewingBoard;
-                    model.confirmation_state = ConfirmationState::No;
-                }
-                ConfirmationState::No => model.mode = Mode::ViewingBoard,
-            },
-            Message::NavigateLeft | Message::NavigateRight => model.toggle_confirmation_state(),
-            Message::ViewBoardMode => model.mode = Mode::ViewingBoard,
-            m => panic!("unhandled message: {:?}", m),
-        },
-        Mode::ViewingBoards => match msg {
-            Message::NavigateUp => {
-                model.selected.board_index =
-                    model.selected.board_index.map(|i| i.saturating_sub(1));
-            }
-            Message::NavigateDown => {
-                model.selected.board_index = model
-                    .selected
-                    .board_index
-                    .map(|i| min(model.board_metas.len().saturating_sub(1), i + 1));
-            }
-            Message::NewBoard => {
-                let raw_board_text = run_editor_fn(
-                    terminal,
-                    "Board Name\n==========\n\n- Column #1\n- Column #2\n- Column #3",
-                )?;
-                let (name, column_names) = parse_raw_board_text(&raw_board_text)?;
+        update(
+            &mut model,
+            crate::Message::ToggleArchiveBoard,
+            &mut terminal,
+        )
+        .unwrap();
 
-                // TODO
-                // 1. create board, get board_id
-                model.create_board(name, &column_names)?;
-                // 2. insert columns, get columns ids
-                model.selected.board_index = Some(0);
-                model.selected.column_index = Some(0);
-            }
-            Message::EditBoard => {
-                let selected_board = &model.board_metas[model.selected.board_index.unwrap()];
-                let mut board_for_editor = format!("{}\n==========\n\n", selected_board.name);
+        assert!(model.board_metas.is_empty());
+        assert_eq!(model.selected.board_index, None);
+        assert!(model.repo.get_board_metas().unwrap().is_empty());
+        assert!(
+            model
+                .repo
+                .load_most_recently_viewed_board()
+                .unwrap()
+                .is_none()
+        );
 
-                for column_name in &selected_board.columns {
-                    board_for_editor.push_str("- ");
-                    board_for_editor.push_str(column_name);
-                    board_for_editor.push('\n');
-                }
+        update(
+            &mut model,
+            crate::Message::ToggleArchivedBoardsView,
+            &mut terminal,
+        )
+        .unwrap();
 
-                let raw_board_text = run_editor_fn(terminal, &board_for_editor)?;
-                let (name, column_names) = parse_raw_board_text(&raw_board_text)?;
+        assert_eq!(model.board_metas.len(), 1);
+        assert_eq!(model.selected.board_index, Some(0));
 
-                model.update_selected_board(name, column_names)?;
-            }
-            Message::ViewBoardMode => {
-                model.mode = Mode::ViewingBoard;
-                model.load_selected_board()?;
-            }
-            Message::Quit => model.running_state = RunningState::Done,
-            m => panic!("unhandled message: {:?}", m),
-        },
+        update(
+            &mut model,
+            crate::Message::ToggleArchiveBoard,
+            &mut terminal,
+        )
+        .unwrap();
+
+        assert!(model.board_metas.is_empty());
+        assert_eq!(model.repo.get_board_metas().unwrap()[0].name, "Board1");
     }
 
-    Ok(None)
-}
+    #[test]
+    fn migrations_only_run_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("kk.db");
 
-fn parse_raw_card_text(raw_card_text: &str) -> anyhow::Result<(&str, &str)> {
-    let card_regex = Regex::new(r#"(?s)(?<title>[^=\n]+)\n=+\n\n(?<body>.*)"#).unwrap();
+        let repo = crate::Repo::new(Some(database_path.clone())).unwrap();
+        drop(repo);
 
-    let m = card_regex.captures(raw_card_text);
+        let repo = crate::Repo::new(Some(database_path)).unwrap();
 
-    if let Some(captures) = m
-        && let Some(title) = captures.name("title")
-        && let Some(body) = captures.name("body")
-    {
-        Ok((title.as_str(), body.as_str()))
-    } else {
-        Err(anyhow!("could not parse raw card text"))
+        let user_version: i64 = repo
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(user_version as usize, crate::Repo::MIGRATIONS.len());
     }
-}
 
-fn parse_raw_board_text(raw_board_text: &str) -> anyhow::Result<(&str, Vec<&str>)> {
-    let board_regex = Regex::new(r#"(?<name>[^=\n]+)\n=+\n\n"#).unwrap();
+    #[test]
+    fn migrations_keep_updated_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("kk.db");
 
-    let columns_regex = Regex::new(r#"- (?<column>[^\n]+)"#).unwrap();
+        // a database from before migrations, when cards_updated fired on any update
+        let conn = rusqlite::Connection::open(&database_path).unwrap();
+        conn.execute_batch(
+            "
+            create table boards (
+                id integer primary key,
+                name text not null,
+                card_id integer not null default 1,
+                inserted_at timestamp not null default current_timestamp,
+                updated_at timestamp not null default current_timestamp,
+                viewed_at timestamp not null default current_timestamp
+            );
 
-    let m_name = board_regex.captures(raw_board_text);
-    let m_columns = columns_regex.captures_iter(raw_board_text);
+            create unique index boards_name on boards (name);
 
-    if let Some(captures_name) = m_name
-        && let Some(name) = captures_name.name("name")
-    {
-        let mut columns = vec![];
+            create table statuses (
+                id integer primary key,
+                name text not null,
+                column_order integer not null,
+                board_id integer not null,
+                inserted_at timestamp not null default current_timestamp,
+                updated_at timestamp not null default current_timestamp,
 
-        for cap in m_columns {
-            if let Some(column) = cap.name("column") {
-                columns.push(column.as_str())
-            }
+                foreign key(board_id) references boards(id) on delete cascade
+            );
+
+            create table cards (
+                id integer primary key,
+                external_id integer not null,
+                board_id integer not null,
+                title text not null,
+                status_id integer not null,
+                body text not null,
+                doing_at timestamp,
+                done_at timestamp,
+                inserted_at timestamp not null default current_timestamp,
+                updated_at timestamp not null default current_timestamp,
+
+                foreign key(board_id) references boards(id) on delete cascade,
+                foreign key(status_id) references statuses(id) on delete cascade
+            );
+
+            create trigger cards_updated after update on cards
+            for each row
+            begin
+                update cards
+                set updated_at = current_timestamp
+                where cards.id = NEW.id;
+
+                update boards
+                set updated_at = current_timestamp
+                where boards.id = NEW.board_id;
+            end;
+
+            insert into boards (name, card_id, updated_at) values ('Board1', 2, '2020-01-01 00:00:00');
+            insert into statuses (name, column_order, board_id) values ('Todo', 0, 1);
+            insert into cards (external_id, board_id, title, status_id, body, updated_at)
+            values (1, 1, 'one', 1, '', '2020-01-01 00:00:00');
+            ",
+        )
+        .unwrap();
+        drop(conn);
+
+        let repo = crate::Repo::new(Some(database_path)).unwrap();
+
+        let (card_updated_at, board_updated_at): (String, String) = repo
+            .conn
+            .query_one(
+                "
+                select cards.updated_at, boards.updated_at
+                from cards
+                inner join boards
+                    on boards.id = cards.board_id
+                ",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+
+        assert_eq!(card_updated_at, "2020-01-01 00:00:00");
+        assert_eq!(board_updated_at, "2020-01-01 00:00:00");
+    }
+
+    mod db_constraits {
+        use crate::{Model, Options, update_with_run_editor_fn};
+
+        #[test]
+        fn board_names_are_unique() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            )
+            .unwrap();
+
+            let when_inserting_a_second_board_with_duplicate_name = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            );
+
+            assert!(when_inserting_a_second_board_with_duplicate_name.is_err());
+        }
+        #[test]
+        fn columns_are_unique_per_board() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            let when_creating_duplicate_columns_on_board = update_with_run_editor_fn(
+                &mut model,
+                crate::Message::NewBoard,
+                &mut terminal,
+                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n- Todo\n".to_string()),
+            );
+
+            assert!(when_creating_duplicate_columns_on_board.is_err());
         }
+    }
+
+    mod cli {
+        use crate::{CliCommand, Repo, run_command};
+
+        fn run(repo: &mut Repo, command: CliCommand) -> anyhow::Result<String> {
+            let mut out = vec![];
+            run_command(repo, command, &mut out)?;
+            Ok(String::from_utf8(out).unwrap())
+        }
+
+        #[test]
+        fn add_show_move_edit_rm() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            repo.create_board("Board1", &["Todo", "Done"], &[]).unwrap();
+
+            let output = run(
+                &mut repo,
+                CliCommand::Add {
+                    title: "a card".to_string(),
+                    body: "a body".to_string(),
+                    column: None,
+                    tags: vec![],
+                    due: None,
+                    priority: None,
+                    board: None,
+                },
+            )
+            .unwrap();
+
+            assert_eq!(output, "1\n");
+
+            run(
+                &mut repo,
+                CliCommand::Move {
+                    id: 1,
+                    column: "Done".to_string(),
+                    board: Some("Board1".to_string()),
+                },
+            )
+            .unwrap();
+
+            run(
+                &mut repo,
+                CliCommand::Edit {
+                    id: 1,
+                    title: Some("a new title".to_string()),
+                    body: None,
+                    board: None,
+                },
+            )
+            .unwrap();
+
+            let output = run(
+                &mut repo,
+                CliCommand::Show {
+                    id: 1,
+                    board: None,
+                    json: false,
+                },
+            )
+            .unwrap();
+
+            assert!(output.starts_with("1 a new title\ncolumn: Done\n"));
+            assert!(output.ends_with("\na body\n"));
+
+            run(&mut repo, CliCommand::Rm { id: 1, board: None }).unwrap();
 
-        if columns.is_empty() {
-            return Err(anyhow!("could not parse raw board text: bad columns"));
+            assert!(
+                run(
+                    &mut repo,
+                    CliCommand::Show {
+                        id: 1,
+                        board: None,
+                        json: false,
+                    }
+                )
+                .is_err()
+            );
         }
 
-        Ok((name.as_str(), columns))
-    } else {
-        Err(anyhow!("could not parse raw board text: bad board name"))
-    }
-}
+        #[test]
+        fn move_to_the_same_column_does_nothing() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            repo.create_board("Board1", &["Todo", "Done"], &[]).unwrap();
+            let board_id = repo.get_board_id_by_name("Board1").unwrap();
+            let card = repo.insert_card(board_id, "one", "").unwrap();
+            repo.insert_card(board_id, "two", "").unwrap();
+
+            run(
+                &mut repo,
+                CliCommand::Move {
+                    id: 1,
+                    column: "Todo".to_string(),
+                    board: None,
+                },
+            )
+            .unwrap();
 
-#[derive(Parser)]
-#[command(author, version, about, name = "kk")]
-struct Options {
-    #[arg(short, long, env)]
-    database_path: Option<PathBuf>,
-    #[arg(short = 'c', long, env, default_value = "#FF96A7")]
-    highlight_color: String,
-}
+            let titles = repo
+                .cards_for_column(board_id, "Todo")
+                .unwrap()
+                .into_iter()
+                .map(|card| card.title)
+                .collect::<Vec<_>>();
+            assert_eq!(titles, ["two", "one"]);
+            assert_eq!(repo.get_card_events(card.id).unwrap().len(), 1);
+        }
 
-fn main() -> anyhow::Result<()> {
-    let options = Options::parse();
+        #[test]
+        fn add_to_column() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            repo.create_board("Board1", &["Todo", "Done"], &[]).unwrap();
+
+            run(
+                &mut repo,
+                CliCommand::Add {
+                    title: "a card".to_string(),
+                    body: "".to_string(),
+                    column: Some("Done".to_string()),
+                    tags: vec![],
+                    due: None,
+                    priority: None,
+                    board: None,
+                },
+            )
+            .unwrap();
 
-    let mut model = Model::new(options)?;
+            let output = run(
+                &mut repo,
+                CliCommand::List {
+                    board: Some("Board1".to_string()),
+                    json: false,
+                },
+            )
+            .unwrap();
 
-    if let Some(board) = &model.board
-        && let Some(first_column) = board.columns.first()
-        && !first_column.cards.is_empty()
-    {
-        model.selected.card_index = Some(0);
-    }
+            assert_eq!(output, "Todo\nDone\n  1\ta card\n");
+        }
 
-    ratatui::run(|terminal| -> anyhow::Result<()> {
-        while model.running_state != RunningState::Done {
-            // Render the current view
-            terminal.draw(|f| view(&mut model, f))?;
+        #[test]
+        fn add_with_tags() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            repo.create_board("Board1", &["Todo", "Done"], &[]).unwrap();
+
+            run(
+                &mut repo,
+                CliCommand::Add {
+                    title: "a card".to_string(),
+                    body: "".to_string(),
+                    column: None,
+                    tags: vec!["ui".to_string(), "bug".to_string(), "ui".to_string()],
+                    due: None,
+                    priority: None,
+                    board: None,
+                },
+            )
+            .unwrap();
 
-            // Handle events and map to a Message
-            let mut current_msg = receive_event(&model)?;
+            let output = run(
+                &mut repo,
+                CliCommand::Show {
+                    id: 1,
+                    board: None,
+                    json: false,
+                },
+            )
+            .unwrap();
 
-            // Process updates as long as they return a non-None message
-            while let Some(m) = current_msg {
-                match update(&mut model, m, terminal) {
-                    Ok(m) => current_msg = m,
-                    Err(e) => current_msg = Some(Message::SetError(Some(e.to_string()))),
-                }
+            assert!(output.contains("\ntags: bug, ui\n"));
+        }
+
+        #[test]
+        fn bad_arguments_add_nothing() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            repo.create_board("Board1", &["Todo", "Done"], &[]).unwrap();
+            let board_id = repo.get_board_id_by_name("Board1").unwrap();
+
+            for (due, tags) in [
+                (Some("tomorrow".to_string()), vec![]),
+                (None, vec!["a,b".to_string()]),
+            ] {
+                assert!(
+                    run(
+                        &mut repo,
+                        CliCommand::Add {
+                            title: "a card".to_string(),
+                            body: "".to_string(),
+                            column: Some("Done".to_string()),
+                            tags,
+                            due,
+                            priority: None,
+                            board: None,
+                        },
+                    )
+                    .is_err()
+                );
             }
+
+            assert!(
+                repo.get_cards_for_board(board_id)
+                    .unwrap()
+                    .iter()
+                    .all(|column| column.cards.is_empty())
+            );
         }
 
-        Ok(())
-    })?;
+        #[test]
+        fn unknown_board_or_column() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            assert!(
+                run(
+                    &mut repo,
+                    CliCommand::List {
+                        board: None,
+                        json: false,
+                    }
+                )
+                .is_ok()
+            );
 
-    Ok(())
-}
+            assert!(
+                run(
+                    &mut repo,
+                    CliCommand::Add {
+                        title: "a card".to_string(),
+                        body: "".to_string(),
+                        column: None,
+                        tags: vec![],
+                        due: None,
+                        priority: None,
+                        board: None,
+                    },
+                )
+                .is_err()
+            );
 
-#[cfg(test)]
-mod tests {
-    use ratatui::Terminal;
+            repo.create_board("Board1", &["Todo"], &[]).unwrap();
+
+            assert!(
+                run(
+                    &mut repo,
+                    CliCommand::Add {
+                        title: "a card".to_string(),
+                        body: "".to_string(),
+                        column: Some("Nope".to_string()),
+                        tags: vec![],
+                        due: None,
+                        priority: None,
+                        board: None,
+                    },
+                )
+                .is_err()
+            );
 
-    use crate::{
-        BoardId, Card, CardId, ConfirmationState, ExternalCardId, Mode, Model, Options,
-        RunningState, update, update_with_run_editor_fn,
-    };
+            assert!(
+                run(
+                    &mut repo,
+                    CliCommand::List {
+                        board: Some("Nope".to_string()),
+                        json: false,
+                    },
+                )
+                .is_err()
+            );
 
-    impl From<i64> for BoardId {
-        fn from(value: i64) -> Self {
-            Self(value)
+            assert_eq!(repo.cards_for_column(1.into(), "Todo").unwrap(), vec![]);
         }
-    }
 
-    impl From<i64> for CardId {
-        fn from(value: i64) -> Self {
-            Self(value)
-        }
-    }
+        #[test]
+        fn json_output() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            repo.create_board("Board1", &["Todo", "Done"], &[]).unwrap();
+            repo.insert_card(1.into(), "a card", "a body").unwrap();
+
+            let boards: serde_json::Value = serde_json::from_str(
+                &run(
+                    &mut repo,
+                    CliCommand::List {
+                        board: None,
+                        json: true,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
 
-    impl From<i64> for ExternalCardId {
-        fn from(value: i64) -> Self {
-            Self(value)
-        }
-    }
+            assert_eq!(boards[0]["name"], "Board1");
+            assert_eq!(boards[0]["columns"], serde_json::json!(["Todo", "Done"]));
 
-    /// right now, we don't care about comparing whether cards
-    /// have the same inserted_at and updated_at.
-    ///
-    /// we don't even use PartialEq in application code
-    impl PartialEq for Card {
-        fn eq(&self, other: &Self) -> bool {
-            self.id == other.id && self.title == other.title && self.body == other.body
-        }
-    }
+            let board: serde_json::Value = serde_json::from_str(
+                &run(
+                    &mut repo,
+                    CliCommand::List {
+                        board: Some("Board1".to_string()),
+                        json: true,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
 
-    impl Options {
-        fn test_options() -> Options {
-            Options {
-                database_path: Some(":memory:".into()),
-                highlight_color: "#FF96A7".to_string(),
-            }
+            assert_eq!(board["columns"][0]["name"], "Todo");
+            assert_eq!(board["columns"][0]["cards"][0]["external_id"], 1);
+            assert_eq!(board["columns"][0]["cards"][0]["title"], "a card");
+
+            let card: serde_json::Value = serde_json::from_str(
+                &run(
+                    &mut repo,
+                    CliCommand::Show {
+                        id: 1,
+                        board: None,
+                        json: true,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+            assert_eq!(card["body"], "a body");
+            assert_eq!(card["column"], "Todo");
         }
     }
 
-    mod create_board {
-        use ratatui::Terminal;
+    mod priority_and_sorting {
+        use super::helpers::titles;
+        use crate::{
+            Message, Mode, Model, Options, Priority, SortMode, parse_raw_card_text, update,
+            update_with_run_editor_fn, view,
+        };
 
-        use crate::{Message, Model, Options, RunningState, update, update_with_run_editor_fn};
+        #[test]
+        fn parses_priorities_from_the_card_template() {
+            assert_eq!(
+                parse_raw_card_text("title\n=====\npriority: P1\n\nbody")
+                    .unwrap()
+                    .priority,
+                Some(Priority(1))
+            );
+            assert_eq!(
+                parse_raw_card_text("title\n=====\npriority: p0\n\nbody")
+                    .unwrap()
+                    .priority,
+                Some(Priority(0))
+            );
+            assert_eq!(
+                parse_raw_card_text("title\n=====\npriority:\n\nbody")
+                    .unwrap()
+                    .priority,
+                None
+            );
+            assert!(parse_raw_card_text("title\n=====\npriority: P4\n\nbody").is_err());
+            assert!(parse_raw_card_text("title\n=====\npriority: high\n\nbody").is_err());
+        }
 
         #[test]
-        fn with_zero_columns() {
+        fn sort_modes() {
             let mut model = Model::new(Options::test_options()).unwrap();
 
             let mut terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            let update_result = update_with_run_editor_fn(
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.selected.board_index = Some(0);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            for text in [
+                "a\n=====\npriority: P2\n\n",
+                "b\n=====\n\n",
+                "c\n=====\npriority: P0\n\n",
+            ] {
+                update_with_run_editor_fn(
+                    &mut model,
+                    Message::NewCard,
+                    &mut terminal,
+                    |_terminal, _template| Ok(text.to_string()),
+                )
+                .unwrap();
+            }
+
+            assert_eq!(titles(&model, 0), ["c", "b", "a"]);
+
+            update(&mut model, Message::CycleSortMode, &mut terminal).unwrap();
+
+            assert_eq!(titles(&model, 0), ["c", "a", "b"]);
+            assert_eq!(model.selected_card().unwrap().title, "c");
+            assert_eq!(
+                model.repo.get_board_sort_mode(1.into()).unwrap(),
+                SortMode::Priority
+            );
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            // reordering by hand only makes sense in manual order
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            assert!(update(&mut model, Message::MoveCardDown, &mut terminal).is_err());
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            // edits keep the column sorted and the card selected
+            update_with_run_editor_fn(
                 &mut model,
-                crate::Message::NewBoard,
+                Message::EditCard,
                 &mut terminal,
-                // replace default run_editor_fn with a stub that returns invalid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Some Board Name\n==========\n\n".to_string())
-                },
-            );
+                |_terminal, _template| Ok("c\n=====\npriority: P3\n\n".to_string()),
+            )
+            .unwrap();
 
-            assert!(update_result.is_err());
+            assert_eq!(titles(&model, 0), ["a", "c", "b"]);
+            assert_eq!(model.selected_card().unwrap().title, "c");
 
-            assert_eq!(model.running_state, RunningState::Running);
+            while model.board.as_ref().unwrap().sort_mode != SortMode::Id {
+                update(&mut model, Message::CycleSortMode, &mut terminal).unwrap();
+            }
+
+            assert_eq!(titles(&model, 0), ["a", "b", "c"]);
+
+            update(&mut model, Message::CycleSortMode, &mut terminal).unwrap();
+
+            assert_eq!(titles(&model, 0), ["c", "b", "a"]);
+            assert_eq!(model.mode, Mode::ViewingBoard);
         }
+    }
+
+    mod search {
+        use super::helpers::type_text;
+        use crate::{Message, Mode, Model, Options, update, update_with_run_editor_fn, view};
 
         #[test]
-        fn with_at_least_one_column() {
+        fn finds_cards_on_every_board() {
             let mut model = Model::new(Options::test_options()).unwrap();
 
             let mut terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            let update_result = update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                // replace default run_editor_fn with a stub that returns invalid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Some Board Name\n==========\n\n- Todo".to_string())
-                },
-            );
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.create_board("Board2", &["Todo", "Done"]).unwrap();
+
+            let board1 = model.repo.get_board_id_by_name("Board1").unwrap();
+            let board2 = model.repo.get_board_id_by_name("Board2").unwrap();
+
+            model
+                .repo
+                .insert_card(board1, "groceries", "milk and eggs")
+                .unwrap();
+            model
+                .repo
+                .insert_card(board2, "fix the flaky test", "it fails on tuesdays")
+                .unwrap();
+            let card = model
+                .repo
+                .insert_card(board2, "deploy", "after the flaky test is fixed")
+                .unwrap();
+            model.repo.set_card_status(board2, card.id, "Done").unwrap();
+
+            model.open_board(board1, None).unwrap();
+
+            update(&mut model, Message::SearchMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::Searching);
+
+            type_text(&mut model, &mut terminal, "fla tuesday");
+
+            let search = model.search.as_ref().unwrap();
+            assert_eq!(search.results.len(), 1);
+            assert_eq!(search.results[0].card.title, "fix the flaky test");
+            assert_eq!(search.results[0].board_name, "Board2");
+            assert_eq!(search.results[0].snippet, "fix the \u{1}flaky\u{2} test");
+
+            for _ in " tuesday".chars() {
+                update(&mut model, Message::DeleteChar, &mut terminal).unwrap();
+            }
 
-            assert!(update_result.is_ok());
+            assert_eq!(model.search.as_ref().unwrap().query, "fla");
+            assert_eq!(model.search.as_ref().unwrap().results.len(), 2);
 
-            assert_eq!(model.running_state, RunningState::Running);
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            // quotes and other query syntax are searched for, not interpreted
+            type_text(&mut model, &mut terminal, "ky\" OR (");
+            assert!(model.search.as_ref().unwrap().results.is_empty());
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert!(model.search.is_none());
+
+            update(&mut model, Message::SearchMode, &mut terminal).unwrap();
+            type_text(&mut model, &mut terminal, "deploy");
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(model.board.as_ref().unwrap().name, "Board2");
+            assert_eq!(model.selected.column_index, Some(1));
+            assert_eq!(model.selected_card().unwrap().title, "deploy");
         }
 
         #[test]
-        fn new_card_goes_to_correct_board_when_multiple_boards() {
+        fn index_follows_edits_and_deletes() {
             let mut model = Model::new(Options::test_options()).unwrap();
 
             let mut terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
+            model.create_board("Board1", &["Todo"]).unwrap();
+            model.selected.board_index = Some(0);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
             update_with_run_editor_fn(
                 &mut model,
-                crate::Message::NewBoard,
+                Message::NewCard,
                 &mut terminal,
-                // replace default run_editor_fn with a stub that returns invalid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Board1\n==========\n\n- Todo".to_string())
-                },
+                |_terminal, _template| Ok("apples\n=====\n\n".to_string()),
             )
             .unwrap();
 
-            assert_eq!(model.selected.board_index, Some(0));
+            assert_eq!(model.repo.search_cards("apples").unwrap().len(), 1);
 
             update_with_run_editor_fn(
                 &mut model,
-                crate::Message::NewBoard,
+                Message::EditCard,
                 &mut terminal,
-                // replace default run_editor_fn with a stub that returns invalid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Board2\n==========\n\n- Todo".to_string())
-                },
+                |_terminal, _template| Ok("oranges\n=====\n\n".to_string()),
             )
             .unwrap();
 
-            assert_eq!(model.selected.board_index, Some(0));
+            assert!(model.repo.search_cards("apples").unwrap().is_empty());
+            assert_eq!(model.repo.search_cards("oranges").unwrap().len(), 1);
 
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            update(&mut model, Message::DeleteCard, &mut terminal).unwrap();
+            update(&mut model, Message::NavigateLeft, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                // replace default run_editor_fn with a stub that returns invalid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Card1\n==========\n\n- Todo".to_string())
-                },
-            )
-            .unwrap();
+            assert!(model.repo.search_cards("oranges").unwrap().is_empty());
+
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert_eq!(model.repo.search_cards("oranges").unwrap().len(), 1);
+
+            model.repo.delete_board(1.into()).unwrap();
+
+            assert!(model.repo.search_cards("oranges").unwrap().is_empty());
+        }
+    }
+
+    mod board_filter {
+        use super::helpers::{key, press, selected_title};
+        use crate::{Message, Mode, Model, Options, view};
+        use crossterm::event::KeyCode;
+
+        #[test]
+        fn filters_and_jumps_between_matches() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model
+                .create_board("Board1", &["Todo", "Doing", "Done"])
+                .unwrap();
+            let board_id = model.repo.get_board_id_by_name("Board1").unwrap();
+
+            for (title, body, column) in [
+                ("write docs", "", "Todo"),
+                ("fix login bug", "", "Todo"),
+                ("refactor", "a BUG hides in here", "Doing"),
+                ("ship it", "", "Done"),
+            ] {
+                let card = model.repo.insert_card(board_id, title, body).unwrap();
+                model
+                    .repo
+                    .set_card_status(board_id, card.id, column)
+                    .unwrap();
+            }
+
+            model.open_board(board_id, None).unwrap();
+
+            assert_eq!(selected_title(&model), "fix login bug");
+
+            press(&mut model, &mut terminal, KeyCode::Char('f')).unwrap();
+            assert_eq!(model.mode, Mode::FilteringBoard);
+
+            // the selection moves as soon as its card stops matching
+            for c in "docs".chars() {
+                press(&mut model, &mut terminal, KeyCode::Char(c)).unwrap();
+            }
+            assert_eq!(selected_title(&model), "write docs");
+
+            for _ in 0..4 {
+                press(&mut model, &mut terminal, KeyCode::Backspace).unwrap();
+            }
+            for c in "bug".chars() {
+                press(&mut model, &mut terminal, KeyCode::Char(c)).unwrap();
+            }
+            press(&mut model, &mut terminal, KeyCode::Enter).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(model.board_filter.as_deref(), Some("bug"));
+            // "write docs" came after "fix login bug", so the next match is in the next column
+            assert_eq!(model.selected.column_index, Some(1));
+            assert_eq!(selected_title(&model), "refactor");
 
-            assert!(model.board.is_some());
+            terminal.draw(|f| view(&mut model, f)).unwrap();
 
-            let board = model.board.unwrap();
+            // wraps around at the end
+            press(&mut model, &mut terminal, KeyCode::Char('n')).unwrap();
+            assert_eq!(model.selected.column_index, Some(0));
+            assert_eq!(selected_title(&model), "fix login bug");
 
-            assert_eq!(board.columns[0].cards[0].id, 1.into());
-            assert_eq!(board.columns[0].cards[0].title, "Card1");
-        }
-    }
+            press(&mut model, &mut terminal, KeyCode::Char('n')).unwrap();
+            assert_eq!(selected_title(&model), "refactor");
 
-    mod new_card {
-        use crate::{
-            Card, Message, Model, Options, RunningState, update, update_with_run_editor_fn,
-        };
-        use ratatui::Terminal;
+            press(&mut model, &mut terminal, KeyCode::Char('N')).unwrap();
+            assert_eq!(selected_title(&model), "fix login bug");
+
+            press(&mut model, &mut terminal, KeyCode::Esc).unwrap();
+            assert_eq!(model.board_filter, None);
+
+            // / is for searching every board, like it was before filters
+            assert_eq!(key(&model, KeyCode::Char('/')), Some(Message::SearchMode));
+
+            // with no filter, n is for new cards again
+            assert_eq!(key(&model, KeyCode::Char('n')), Some(Message::NewCard));
+        }
 
         #[test]
-        fn with_bad_input() {
+        fn no_matches() {
             let mut model = Model::new(Options::test_options()).unwrap();
 
             let mut terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            model.create_board("Board1", &["Todo"]).unwrap();
+            let board_id = model.repo.get_board_id_by_name("Board1").unwrap();
+            model.repo.insert_card(board_id, "a card", "").unwrap();
+            model.open_board(board_id, None).unwrap();
 
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            press(&mut model, &mut terminal, KeyCode::Char('f')).unwrap();
+            press(&mut model, &mut terminal, KeyCode::Char('z')).unwrap();
+            press(&mut model, &mut terminal, KeyCode::Enter).unwrap();
 
-            let update_result = update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                // replace default run_editor_fn with a stub that returns invalid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("bad input".to_string())
-                },
-            );
+            assert_eq!(selected_title(&model), "a card");
+            assert!(press(&mut model, &mut terminal, KeyCode::Char('n')).is_err());
 
-            assert!(update_result.is_err());
+            // an empty filter doesn't stick around
+            press(&mut model, &mut terminal, KeyCode::Esc).unwrap();
+            press(&mut model, &mut terminal, KeyCode::Char('f')).unwrap();
+            press(&mut model, &mut terminal, KeyCode::Enter).unwrap();
 
-            assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(model.board_filter, None);
         }
+    }
+
+    mod jump_to_card {
+        use super::helpers::type_keys;
+        use crate::{CardText, Mode, Model, Options};
 
         #[test]
-        fn with_valid_input() {
+        fn jumps_to_a_card_by_number() {
             let mut model = Model::new(Options::test_options()).unwrap();
 
             let mut terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
-
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            model.create_board("Board1", &["Todo", "Done"]).unwrap();
+            let board_id = model.repo.get_board_id_by_name("Board1").unwrap();
+
+            for i in 1..=12 {
+                let card = model
+                    .repo
+                    .insert_card(board_id, &format!("card {}", i), "")
+                    .unwrap();
+                if i % 2 == 0 {
+                    model
+                        .repo
+                        .set_card_status(board_id, card.id, "Done")
+                        .unwrap();
+                }
+            }
 
-            let update_result = update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                // replace default run_editor_fn with a stub that returns valid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Valid Title\n==========\n\nValid card body".to_string())
-                },
-            );
+            let card = model.repo.insert_card(board_id, "tagged", "").unwrap();
+            model
+                .repo
+                .set_card_fields(
+                    board_id,
+                    card.id,
+                    &CardText {
+                        tags: vec!["bug".to_string()],
+                        ..card.text()
+                    },
+                )
+                .unwrap();
 
-            assert!(update_result.is_ok());
+            model.open_board(board_id, None).unwrap();
 
-            assert_eq!(
-                model.board.unwrap().columns[0].cards,
-                vec![Card {
-                    id: 1.into(),
-                    external_id: 1.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
-                }]
-            );
+            type_keys(&mut model, &mut terminal, ":10\n").unwrap();
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(model.selected.column_index, Some(1));
+            assert_eq!(model.selected_card().unwrap().title, "card 10");
 
+            // letters aren't card numbers, so they're ignored
+            type_keys(&mut model, &mut terminal, "#x3\n").unwrap();
             assert_eq!(model.selected.column_index, Some(0));
-            assert_eq!(model.selected.card_index, Some(0));
+            assert_eq!(model.selected_card().unwrap().title, "card 3");
 
-            assert_eq!(
-                model.repo.cards_for_column(1.into(), "Todo").unwrap(),
-                vec![Card {
-                    id: 1.into(),
-                    external_id: 1.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
-                }]
-            );
+            model.set_tag_filter(Some("bug".to_string())).unwrap();
 
-            assert_eq!(model.running_state, RunningState::Running);
+            type_keys(&mut model, &mut terminal, "#5\n").unwrap();
+            assert_eq!(model.tag_filter, None);
+            assert_eq!(model.selected_card().unwrap().title, "card 5");
+
+            assert!(type_keys(&mut model, &mut terminal, "#99\n").is_err());
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(model.selected_card().unwrap().title, "card 5");
         }
     }
 
-    mod edit_card {
+    mod send_to_board {
         use crate::{
-            Card, Message, Model, Options, RunningState, update, update_with_run_editor_fn,
+            CardEventKind, CardText, ExternalCardId, Message, Mode, Model, Options, update, view,
         };
-        use ratatui::Terminal;
 
         #[test]
-        fn with_bad_input() {
+        fn sends_a_card_to_another_board() {
             let mut model = Model::new(Options::test_options()).unwrap();
 
             let mut terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            model.create_board("Inbox", &["Todo"]).unwrap();
+            model
+                .create_board("Project", &["Backlog", "Doing"])
+                .unwrap();
+            let inbox = model.repo.get_board_id_by_name("Inbox").unwrap();
+            let project = model.repo.get_board_id_by_name("Project").unwrap();
+
+            model.repo.insert_card(project, "one", "").unwrap();
+            model.repo.insert_card(project, "two", "").unwrap();
+            model.repo.insert_card(inbox, "stays", "").unwrap();
+
+            let card = model.repo.insert_card(inbox, "triaged", "").unwrap();
+            model
+                .repo
+                .set_card_fields(
+                    inbox,
+                    card.id,
+                    &CardText {
+                        tags: vec!["bug".to_string()],
+                        ..card.text()
+                    },
+                )
+                .unwrap();
+
+            model.open_board(inbox, Some(card.id)).unwrap();
+
+            model
+                .edit_selected_card(CardText {
+                    body: "some details".to_string(),
+                    ..model.selected_card().unwrap().text()
+                })
+                .unwrap();
 
+            update(&mut model, Message::SendCardMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::SendingCard);
+
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+
+            // Project is the only other board
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            // back out to the boards and in again
             update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-            let update_result = update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                // replace default run_editor_fn with a stub that returns valid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Valid Title\n==========\n\nValid card body".to_string())
-                },
-            );
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-            assert!(update_result.is_ok());
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(model.selected_card().unwrap().title, "stays");
+            assert_eq!(model.board.as_ref().unwrap().columns[0].cards.len(), 1);
 
-            model.selected.column_index = Some(0);
-            model.selected.card_index = Some(0);
+            // the edit was to a card that isn't here anymore
+            assert!(model.undo_stack.is_empty());
 
-            assert_eq!(model.selected.column_index, Some(0));
-            assert_eq!(model.selected.card_index, Some(0));
+            let (sent, column) = model
+                .repo
+                .get_card_by_external_id(project, ExternalCardId(3))
+                .unwrap();
 
-            let update_result = update_with_run_editor_fn(
-                &mut model,
-                crate::Message::EditCard,
-                &mut terminal,
-                // replace default run_editor_fn with a stub that returns invalid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Bad input".to_string())
-                },
-            );
+            assert_eq!(sent.id, card.id);
+            assert_eq!(sent.title, "triaged");
+            assert_eq!(sent.body, "some details");
+            assert_eq!(column, "Doing");
+            assert_eq!(sent.tags, ["bug"]);
+            assert_eq!(sent.sent_from.as_deref(), Some("Inbox #2"));
 
-            assert!(update_result.is_err());
+            assert!(model.repo.get_board_tags(inbox).unwrap().is_empty());
+            assert_eq!(model.repo.get_board_tags(project).unwrap(), ["bug"]);
 
+            // the next card on Project gets the next number
             assert_eq!(
-                model.board.unwrap().columns[0].cards,
-                vec![Card {
-                    id: 1.into(),
-                    external_id: 1.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
-                }]
+                model
+                    .repo
+                    .insert_card(project, "four", "")
+                    .unwrap()
+                    .external_id,
+                ExternalCardId(4)
             );
 
-            assert_eq!(model.selected.column_index, Some(0));
-            assert_eq!(model.selected.card_index, Some(0));
+            let events = model.repo.get_card_events(card.id).unwrap();
+            assert_eq!(events[0].kind, CardEventKind::Received);
+            assert_eq!(events[0].describe(), "sent here from Inbox, into Doing");
+            assert_eq!(events[1].kind, CardEventKind::Sent);
 
-            assert_eq!(
-                model.repo.cards_for_column(1.into(), "Todo").unwrap(),
-                vec![Card {
-                    id: 1.into(),
-                    external_id: 1.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
-                }]
-            );
+            let inbox_events = model.repo.get_board_events(inbox).unwrap();
+            assert_eq!(inbox_events[0].describe(), "sent from Todo to Project");
+        }
 
-            assert_eq!(model.running_state, RunningState::Running);
+        #[test]
+        fn needs_another_board() {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model.create_board("Inbox", &["Todo"]).unwrap();
+            let inbox = model.repo.get_board_id_by_name("Inbox").unwrap();
+            model.repo.insert_card(inbox, "a card", "").unwrap();
+            model.open_board(inbox, None).unwrap();
+
+            assert!(update(&mut model, Message::SendCardMode, &mut terminal).is_err());
+            assert_eq!(model.mode, Mode::ViewingBoard);
         }
 
         #[test]
-        fn with_valid_input() {
+        fn needs_a_column_to_send_to() {
             let mut model = Model::new(Options::test_options()).unwrap();
 
             let mut terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            model.create_board("Inbox", &["Todo"]).unwrap();
+            model.create_board("Project", &["Backlog"]).unwrap();
+            let inbox = model.repo.get_board_id_by_name("Inbox").unwrap();
+            model.repo.insert_card(inbox, "a card", "").unwrap();
+            model.open_board(inbox, None).unwrap();
 
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            update(&mut model, Message::SendCardMode, &mut terminal).unwrap();
 
-            let update_result = update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                // replace default run_editor_fn with a stub that returns valid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Valid Title\n==========\n\nValid card body".to_string())
-                },
-            );
+            // as if Project lost its columns while the list was up
+            model.send_card.as_mut().unwrap().boards[0].columns.clear();
 
-            assert!(update_result.is_ok());
+            assert!(update(&mut model, Message::ConfirmChoice, &mut terminal).is_err());
+            assert_eq!(model.mode, Mode::SendingCard);
+            assert_eq!(model.send_card.as_ref().unwrap().column_index, None);
+        }
+    }
 
-            model.selected.column_index = Some(0);
-            model.selected.card_index = Some(0);
+    mod move_to_column {
+        use super::helpers::key;
+        use crate::{Message, Model, Options, update, view};
+        use crossterm::event::KeyCode;
 
-            assert_eq!(model.selected.column_index, Some(0));
-            assert_eq!(model.selected.card_index, Some(0));
+        #[test]
+        fn number_keys_move_straight_to_a_column() {
+            let mut model = Model::new(Options::test_options()).unwrap();
 
-            let update_result = update_with_run_editor_fn(
-                &mut model,
-                crate::Message::EditCard,
-                &mut terminal,
-                // replace default run_editor_fn with a stub that returns valid data
-                |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                    Ok("Valid Title\n==========\n\nValid card body".to_string())
-                },
-            );
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            assert!(update_result.is_ok());
+            let columns = ["1", "2", "3", "4", "5", "6", "7", "8"];
+            model.create_board("Board1", &columns).unwrap();
+            let board_id = model.repo.get_board_id_by_name("Board1").unwrap();
+            let card = model.repo.insert_card(board_id, "a card", "").unwrap();
+            model.open_board(board_id, None).unwrap();
 
-            assert_eq!(
-                model.board.unwrap().columns[0].cards,
-                vec![Card {
-                    id: 1.into(),
-                    external_id: 1.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
-                }]
-            );
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            terminal.draw(|f| view(&mut model, f)).unwrap();
 
-            assert_eq!(model.selected.column_index, Some(0));
-            assert_eq!(model.selected.card_index, Some(0));
+            let message = key(&model, KeyCode::Char('7')).unwrap();
+            assert_eq!(message, Message::MoveCardToColumn(6));
 
+            update(&mut model, message, &mut terminal).unwrap();
+
+            assert_eq!(model.selected.column_index, Some(6));
+            assert_eq!(model.selected_card().unwrap().id, card.id);
             assert_eq!(
-                model.repo.cards_for_column(1.into(), "Todo").unwrap(),
-                vec![Card {
-                    id: 1.into(),
-                    external_id: 1.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
-                }]
+                model
+                    .repo
+                    .get_card_by_external_id(board_id, card.external_id)
+                    .unwrap()
+                    .1,
+                "7"
             );
 
-            assert_eq!(model.running_state, RunningState::Running);
+            // one move, straight there
+            let events = model.repo.get_card_events(card.id).unwrap();
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].describe(), "moved 1 → 7");
+
+            // there is no ninth column
+            update(&mut model, Message::MoveCardToColumn(8), &mut terminal).unwrap();
+            assert_eq!(model.selected.column_index, Some(6));
+
+            update(&mut model, Message::MoveCardToColumn(0), &mut terminal).unwrap();
+            assert_eq!(model.selected.column_index, Some(0));
+            assert_eq!(model.board.as_ref().unwrap().columns[0].cards.len(), 1);
+
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+            assert_eq!(model.selected.column_index, Some(6));
         }
     }
 
-    #[test]
-    fn update_quit() {
-        let mut model = Model::new(Options::test_options()).unwrap();
+    mod visual_mode {
+        use super::helpers::{titles, type_text};
+        use crate::{
+            BoardId, ConfirmationState, ExternalCardId, Message, Mode, Model, Options, update, view,
+        };
+
+        /// three columns of three cards, with cards 3, 2, 1 top to bottom in "Todo"
+        fn setup() -> (
+            Model,
+            ratatui::Terminal<ratatui::backend::TestBackend>,
+            BoardId,
+        ) {
+            let mut model = Model::new(Options::test_options()).unwrap();
+
+            let terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+
+            model
+                .create_board("Board1", &["Todo", "Doing", "Done"])
+                .unwrap();
+            let board_id = model.repo.get_board_id_by_name("Board1").unwrap();
+
+            for (i, column) in ["Todo", "Doing", "Done"].iter().enumerate() {
+                for j in 1..=3 {
+                    let card = model
+                        .repo
+                        .insert_card(board_id, &format!("card {}", i * 3 + j), "")
+                        .unwrap();
+                    model
+                        .repo
+                        .set_card_status(board_id, card.id, column)
+                        .unwrap();
+                }
+            }
+
+            model.open_board(board_id, None).unwrap();
+
+            (model, terminal, board_id)
+        }
+
+        /// marks "card 3" and "card 1" in Todo and "card 5" in Doing
+        fn mark_cards(
+            model: &mut Model,
+            terminal: &mut ratatui::Terminal<ratatui::backend::TestBackend>,
+        ) {
+            for message in [
+                Message::SelectCardsMode,
+                Message::NavigateDown,
+                Message::NavigateDown,
+                Message::ToggleMark,
+                Message::NavigateRight,
+                Message::NavigateUp,
+                Message::ToggleMark,
+            ] {
+                update(model, message, terminal).unwrap();
+            }
+
+            assert_eq!(model.mode, Mode::SelectingCards);
+            assert_eq!(model.marked.len(), 3);
+
+            terminal.draw(|f| view(model, f)).unwrap();
+        }
+
+        #[test]
+        fn bulk_delete_asks_once_and_undoes_in_one_step() {
+            let (mut model, mut terminal, _board_id) = setup();
+
+            mark_cards(&mut model, &mut terminal);
 
-        let mut terminal =
-            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            update(&mut model, Message::DeleteCard, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ConfirmCardDeletion);
+            terminal.draw(|f| view(&mut model, f)).unwrap();
 
-        update(&mut model, crate::Message::Quit, &mut terminal).unwrap();
+            // saying no goes back to the marked cards
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::SelectingCards);
 
-        assert_eq!(model.running_state, RunningState::Done);
-    }
+            update(&mut model, Message::DeleteCard, &mut terminal).unwrap();
+            model.confirmation_state = ConfirmationState::Yes;
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-    mod navigate_left {
-        use crate::{
-            Board, Card, Column, Message, Model, Options, RunningState, SelectedState, update,
-            update_with_run_editor_fn,
-        };
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert!(model.marked.is_empty());
+            assert_eq!(titles(&model, 0), ["card 2"]);
+            assert_eq!(titles(&model, 1), ["card 6", "card 4"]);
 
-        #[test]
-        fn when_left() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            assert_eq!(titles(&model, 0), ["card 3", "card 2", "card 1"]);
+            assert_eq!(titles(&model, 1), ["card 6", "card 5", "card 4"]);
 
-            update_with_run_editor_fn(
-                &mut model,
-                Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            update(&mut model, Message::Redo, &mut terminal).unwrap();
 
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            assert_eq!(titles(&model, 0), ["card 2"]);
+            assert_eq!(titles(&model, 1), ["card 6", "card 4"]);
+        }
 
-            update(&mut model, Message::NavigateLeft, &mut terminal).unwrap();
+        #[test]
+        fn bulk_move() {
+            let (mut model, mut terminal, _board_id) = setup();
 
-            assert_eq!(model.running_state, RunningState::Running);
+            mark_cards(&mut model, &mut terminal);
+
+            update(&mut model, Message::MoveCardToColumn(2), &mut terminal).unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            // in the order they were shown in
             assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(0),
-                    card_index: None
-                }
+                titles(&model, 2),
+                ["card 3", "card 1", "card 5", "card 9", "card 8", "card 7"]
             );
+            assert_eq!(model.selected_card().unwrap().title, "card 3");
+
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert_eq!(titles(&model, 2), ["card 9", "card 8", "card 7"]);
+            assert_eq!(titles(&model, 0).len(), 3);
+            assert_eq!(titles(&model, 1).len(), 3);
         }
 
         #[test]
-        fn when_right_with_card() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn bulk_tag() {
+            let (mut model, mut terminal, _board_id) = setup();
 
-            model.board = Some(Board {
-                id: 1.into(),
-                name: "Board".to_string(),
-                columns: vec![
-                    Column {
-                        name: "Todo".to_string(),
-                        cards: vec![Card {
-                            id: 1.into(),
-                            external_id: 1.into(),
-                            title: "great card".to_string(),
-                            body: "great body".to_string(),
-                            inserted_at: "".to_string(),
-                            updated_at: "".to_string(),
-                        }],
-                    },
-                    Column {
-                        name: "Doing".to_string(),
-                        cards: vec![Card {
-                            id: 2.into(),
-                            external_id: 2.into(),
-                            title: "title 2".to_string(),
-                            body: "body 2".to_string(),
-                            inserted_at: "".to_string(),
-                            updated_at: "".to_string(),
-                        }],
-                    },
-                ],
-            });
+            mark_cards(&mut model, &mut terminal);
 
-            model.selected.column_index = Some(1);
-            model.selected.card_index = Some(0);
+            update(&mut model, Message::TagCardsMode, &mut terminal).unwrap();
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            assert!(update(&mut model, Message::ConfirmChoice, &mut terminal).is_err());
+            assert_eq!(model.mode, Mode::TaggingCards);
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            type_text(&mut model, &mut terminal, "sprint 4");
+            terminal.draw(|f| view(&mut model, f)).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-            update(&mut model, crate::Message::NavigateLeft, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ViewingBoard);
 
-            assert_eq!(model.running_state, RunningState::Running);
-            assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(0),
-                    card_index: Some(0)
-                }
+            let tagged = model.board.as_ref().unwrap().columns[..2]
+                .iter()
+                .flat_map(|column| &column.cards)
+                .filter(|card| card.tags == ["sprint 4"])
+                .map(|card| card.title.as_str())
+                .collect::<Vec<_>>();
+
+            assert_eq!(tagged, ["card 3", "card 1", "card 5"]);
+
+            update(&mut model, Message::Undo, &mut terminal).unwrap();
+
+            assert!(
+                model
+                    .board
+                    .as_ref()
+                    .unwrap()
+                    .columns
+                    .iter()
+                    .flat_map(|column| &column.cards)
+                    .all(|card| card.tags.is_empty())
             );
         }
 
         #[test]
-        fn when_right_without_card() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn bulk_send() {
+            let (mut model, mut terminal, _board_id) = setup();
 
-            model.board = Some(Board {
-                id: 1.into(),
-                name: "Board".to_string(),
-                columns: vec![
-                    Column {
-                        name: "Todo".to_string(),
-                        cards: vec![],
-                    },
-                    Column {
-                        name: "Doing".to_string(),
-                        cards: vec![Card {
-                            id: 2.into(),
-                            external_id: 1.into(),
-                            title: "title 2".to_string(),
-                            body: "body 2".to_string(),
-                            inserted_at: "".to_string(),
-                            updated_at: "".to_string(),
-                        }],
-                    },
-                ],
-            });
+            model.create_board("Other", &["Inbox"]).unwrap();
+            let other = model.repo.get_board_id_by_name("Other").unwrap();
 
-            model.selected.column_index = Some(1);
-            model.selected.card_index = Some(0);
+            mark_cards(&mut model, &mut terminal);
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            update(&mut model, Message::SendCardMode, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(titles(&model, 0), ["card 2"]);
+            assert_eq!(titles(&model, 1), ["card 6", "card 4"]);
 
-            update(&mut model, crate::Message::NavigateLeft, &mut terminal).unwrap();
+            let sent = model.repo.cards_for_column(other, "Inbox").unwrap();
 
-            assert_eq!(model.running_state, RunningState::Running);
             assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(0),
-                    card_index: None
-                }
+                sent.iter()
+                    .map(|card| (card.external_id, card.title.as_str()))
+                    .collect::<Vec<_>>(),
+                [
+                    (ExternalCardId(3), "card 3"),
+                    (ExternalCardId(2), "card 1"),
+                    (ExternalCardId(1), "card 5"),
+                ]
             );
         }
-    }
-
-    mod navigate_right {
-        use crate::{
-            Board, Card, Column, Model, Options, RunningState, SelectedState, update,
-            update_with_run_editor_fn,
-        };
 
         #[test]
-        fn when_right() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn nothing_marked() {
+            let (mut model, mut terminal, _board_id) = setup();
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            update(&mut model, Message::SelectCardsMode, &mut terminal).unwrap();
+            update(&mut model, Message::ToggleMark, &mut terminal).unwrap();
 
-            update_with_run_editor_fn(
+            let e = update(&mut model, Message::DeleteCard, &mut terminal).unwrap_err();
+            assert_eq!(model.mode, Mode::SelectingCards);
+
+            update(
                 &mut model,
-                crate::Message::NewBoard,
+                Message::SetError(Some(e.to_string())),
                 &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n- Doing\n".to_string()),
             )
             .unwrap();
+            assert_eq!(model.error, Some(e.to_string()));
+            assert_eq!(model.mode, Mode::SelectingCards);
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ViewingBoard);
+        }
+    }
 
-            update(&mut model, crate::Message::NavigateRight, &mut terminal).unwrap();
+    mod export {
+        use crate::{
+            CliCommand, ExportFormat, Message, Mode, Model, Options, Repo, run_command, update,
+        };
 
-            assert_eq!(model.running_state, RunningState::Running);
-            assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(1),
-                    card_index: None
-                }
-            );
+        /// "Todo" holds card 2, with a body, and card 1, which has a comma and a quote in it.
+        /// "Done" is empty
+        fn setup(repo: &mut Repo) {
+            repo.create_board("Board1", &["Todo", "Done"], &[]).unwrap();
+
+            for (title, body, tags, priority) in [
+                ("fix \"login\", again", "", vec![], None),
+                (
+                    "write docs",
+                    "first paragraph\n\nsecond paragraph",
+                    vec!["docs".to_string(), "ui".to_string()],
+                    Some("P1".parse().unwrap()),
+                ),
+            ] {
+                run_command(
+                    repo,
+                    CliCommand::Add {
+                        title: title.to_string(),
+                        body: body.to_string(),
+                        column: None,
+                        tags,
+                        due: None,
+                        priority,
+                        board: None,
+                    },
+                    &mut vec![],
+                )
+                .unwrap();
+            }
+        }
+
+        fn export(repo: &mut Repo, format: ExportFormat) -> String {
+            let mut out = vec![];
+            run_command(
+                repo,
+                CliCommand::Export {
+                    board: None,
+                    format,
+                    output: None,
+                },
+                &mut out,
+            )
+            .unwrap();
+            String::from_utf8(out).unwrap()
         }
 
         #[test]
-        fn when_left_with_card() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn markdown() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
 
-            model.board = Some(Board {
-                id: 1.into(),
-                name: "Board".to_string(),
-                columns: vec![
-                    Column {
-                        name: "Todo".to_string(),
-                        cards: vec![Card {
-                            id: 1.into(),
-                            external_id: 1.into(),
-                            title: "great card".to_string(),
-                            body: "great body".to_string(),
-                            inserted_at: "".to_string(),
-                            updated_at: "".to_string(),
-                        }],
-                    },
-                    Column {
-                        name: "Doing".to_string(),
-                        cards: vec![Card {
-                            id: 2.into(),
-                            external_id: 2.into(),
-                            title: "title 2".to_string(),
-                            body: "body 2".to_string(),
-                            inserted_at: "".to_string(),
-                            updated_at: "".to_string(),
-                        }],
-                    },
-                ],
-            });
+            assert_eq!(
+                export(&mut repo, ExportFormat::Markdown),
+                "# Board1
 
-            model.selected.column_index = Some(0);
-            model.selected.card_index = Some(0);
+## Todo
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+- 2 write docs (P1, tags: docs, ui)
+  first paragraph
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+  second paragraph
+- 1 fix \"login\", again
 
-            update(&mut model, crate::Message::NavigateRight, &mut terminal).unwrap();
+## Done
+"
+            );
+        }
+
+        #[test]
+        fn csv() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
+
+            let csv = export(&mut repo, ExportFormat::Csv);
+            let lines = csv.lines().collect::<Vec<_>>();
 
-            assert_eq!(model.running_state, RunningState::Running);
             assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(1),
-                    card_index: Some(0)
-                }
+                lines[0],
+                "external_id,title,status,priority,due_on,tags,inserted_at,updated_at,doing_at,done_at,body"
             );
+            assert!(lines[1].starts_with("2,write docs,Todo,P1,,\"docs, ui\","));
+            assert!(lines[1].ends_with(",,,\"first paragraph"));
+            assert_eq!(lines[2], "");
+            assert_eq!(lines[3], "second paragraph\"");
+            assert!(lines[4].starts_with("1,\"fix \"\"login\"\", again\",Todo,,,,"));
+            assert!(lines[4].ends_with(",,,"));
+            assert_eq!(lines.len(), 5);
         }
 
         #[test]
-        fn when_left_without_card() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn json() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
 
-            model.board = Some(Board {
-                id: 1.into(),
-                name: "Board".to_string(),
-                columns: vec![
-                    Column {
-                        name: "Todo".to_string(),
-                        cards: vec![Card {
-                            id: 2.into(),
-                            external_id: 1.into(),
-                            title: "title 2".to_string(),
-                            body: "body 2".to_string(),
-                            inserted_at: "".to_string(),
-                            updated_at: "".to_string(),
-                        }],
-                    },
-                    Column {
-                        name: "Doing".to_string(),
-                        cards: vec![],
-                    },
-                ],
-            });
-
-            model.selected.column_index = Some(1);
-            model.selected.card_index = Some(0);
+            let board: serde_json::Value =
+                serde_json::from_str(&export(&mut repo, ExportFormat::Json)).unwrap();
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            assert_eq!(board["name"], "Board1");
+            assert_eq!(board["columns"][0]["cards"][0]["title"], "write docs");
+            assert_eq!(board["columns"][1]["name"], "Done");
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            // database ids mean nothing outside of this database
+            assert!(board.get("id").is_none());
+            assert!(board["columns"][0]["cards"][0].get("id").is_none());
+            assert_eq!(board["columns"][0]["cards"][0]["external_id"], 2);
+        }
 
-            update(&mut model, crate::Message::NavigateRight, &mut terminal).unwrap();
+        #[test]
+        fn to_a_file() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
+
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("board.md");
+
+            let mut out = vec![];
+            run_command(
+                &mut repo,
+                CliCommand::Export {
+                    board: Some("Board1".to_string()),
+                    format: "md".parse().unwrap(),
+                    output: Some(path.clone()),
+                },
+                &mut out,
+            )
+            .unwrap();
 
-            assert_eq!(model.running_state, RunningState::Running);
+            assert!(out.is_empty());
             assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(1),
-                    card_index: Some(0)
-                }
+                std::fs::read_to_string(&path).unwrap(),
+                export(&mut repo, ExportFormat::Markdown)
             );
         }
-    }
-
-    mod navigate_down {
-        use crate::{
-            Board, Card, Column, Model, Options, RunningState, SelectedState, update,
-            update_with_run_editor_fn,
-        };
 
         #[test]
-        fn when_length_is_one() {
+        fn from_the_board_keeps_earlier_exports() {
             let mut model = Model::new(Options::test_options()).unwrap();
-
-            model.board = Some(Board {
-                id: 1.into(),
-                name: "Board".to_string(),
-                columns: vec![Column {
-                    name: "Todo".to_string(),
-                    cards: vec![Card {
-                        id: 2.into(),
-                        external_id: 1.into(),
-                        title: "title 2".to_string(),
-                        body: "body 2".to_string(),
-                        inserted_at: "".to_string(),
-                        updated_at: "".to_string(),
-                    }],
-                }],
-            });
-
-            model.selected.column_index = Some(0);
-            model.selected.card_index = Some(0);
-
             let mut terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+            setup(&mut model.repo);
+            let board_id = model.repo.get_board_id_by_name("Board1").unwrap();
+            model.open_board(board_id, None).unwrap();
+
+            let dir = tempfile::tempdir().unwrap();
+            model.export_dir = Some(dir.path().to_path_buf());
+
+            for message in [
+                Message::ExportBoardMode,
+                Message::NavigateDown,
+                Message::NavigateDown,
+                Message::NavigateDown,
+                Message::NavigateDown,
+                Message::NavigateUp,
+                Message::NavigateUp,
+            ] {
+                update(&mut model, message, &mut terminal).unwrap();
+            }
+            assert_eq!(model.mode, Mode::ExportingBoard);
+            assert_eq!(model.export_format, Some(1));
 
-            assert_eq!(model.running_state, RunningState::Running);
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ViewingBoard);
+
+            let first = dir.path().join("Board1.json");
             assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(0),
-                    card_index: Some(0)
-                }
+                model.notice,
+                Some(format!("exported to {}", first.display()))
+            );
+            assert_eq!(
+                std::fs::read_to_string(&first).unwrap(),
+                export(&mut model.repo, ExportFormat::Json)
             );
+
+            update(&mut model, Message::ExportBoardMode, &mut terminal).unwrap();
+            assert_eq!(model.notice, None);
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
+
+            assert!(dir.path().join("Board1-2.json").exists());
+            assert!(first.exists());
         }
+    }
 
-        #[test]
-        fn when_length_is_greater_than_one() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+    mod import {
+        use crate::{
+            CardEventKind, CliCommand, ExportFormat, ImportFormat, Repo, Stage, parse_csv,
+            run_command,
+        };
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+        fn run(repo: &mut Repo, command: CliCommand) -> anyhow::Result<String> {
+            let mut out = vec![];
+            run_command(repo, command, &mut out)?;
+            Ok(String::from_utf8(out).unwrap())
+        }
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+        fn add(repo: &mut Repo, title: &str, body: &str, tags: &[&str]) {
+            run(
+                repo,
+                CliCommand::Add {
+                    title: title.to_string(),
+                    body: body.to_string(),
+                    column: None,
+                    tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                    due: None,
+                    priority: Some("P2".parse().unwrap()),
+                    board: Some("Board1".to_string()),
+                },
             )
             .unwrap();
+        }
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
-
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                |_terminal, _template| Ok("card1\n=====\n\n- body1\n".to_string()),
+        /// "Todo" has cards 3 and 1, "Done" is empty, and card 2 was deleted
+        fn setup(repo: &mut Repo) {
+            repo.create_board("Board1", &["Todo", "Done"], &[]).unwrap();
+            add(
+                repo,
+                "one",
+                "a body, with a comma\nand a \"quote\"",
+                &["bug"],
+            );
+            add(repo, "two", "", &[]);
+            add(repo, "three", "", &["bug", "ui"]);
+            run(
+                repo,
+                CliCommand::Rm {
+                    id: 2,
+                    board: Some("Board1".to_string()),
+                },
             )
             .unwrap();
+        }
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                |_terminal, _template| Ok("card2\n=====\n\n- body2\n".to_string()),
+        fn export(repo: &mut Repo, format: ExportFormat, path: &std::path::Path) {
+            run(
+                repo,
+                CliCommand::Export {
+                    board: Some("Board1".to_string()),
+                    format,
+                    output: Some(path.to_path_buf()),
+                },
             )
             .unwrap();
+        }
 
-            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+        fn import(
+            repo: &mut Repo,
+            path: &std::path::Path,
+            board: Option<&str>,
+            merge: bool,
+        ) -> anyhow::Result<String> {
+            run(
+                repo,
+                CliCommand::Import {
+                    file: path.to_path_buf(),
+                    format: None,
+                    board: board.map(|board| board.to_string()),
+                    merge,
+                },
+            )
+        }
+
+        fn list(repo: &mut Repo, board: &str) -> String {
+            run(
+                repo,
+                CliCommand::List {
+                    board: Some(board.to_string()),
+                    json: false,
+                },
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn json_keeps_numbers() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
+
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("board.json");
+            export(&mut repo, ExportFormat::Json, &path);
 
-            assert_eq!(model.running_state, RunningState::Running);
             assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(0),
-                    card_index: Some(1)
-                }
+                import(&mut repo, &path, Some("Copy"), false).unwrap(),
+                "Copy\n"
             );
-        }
-    }
 
-    mod navigate_up {
-        use crate::{
-            Board, Card, Column, Model, Options, RunningState, SelectedState, update,
-            update_with_run_editor_fn,
-        };
+            assert_eq!(list(&mut repo, "Copy"), list(&mut repo, "Board1"));
 
-        #[test]
-        fn when_length_is_one() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+            let board1_id = repo.get_board_id_by_name("Board1").unwrap();
+            let copy_id = repo.get_board_id_by_name("Copy").unwrap();
+            let original = repo.get_board(board1_id).unwrap();
+            let copy = repo.get_board(copy_id).unwrap();
 
-            model.board = Some(Board {
-                id: 1.into(),
-                name: "Board".to_string(),
-                columns: vec![Column {
-                    name: "Todo".to_string(),
-                    cards: vec![Card {
-                        id: 2.into(),
-                        external_id: 1.into(),
-                        title: "title 2".to_string(),
-                        body: "body 2".to_string(),
-                        inserted_at: "".to_string(),
-                        updated_at: "".to_string(),
-                    }],
-                }],
-            });
+            for (original, copy) in original.columns[0].cards.iter().zip(&copy.columns[0].cards) {
+                assert_eq!(copy.external_id, original.external_id);
+                assert_eq!(copy.body, original.body);
+                assert_eq!(copy.tags, original.tags);
+                assert_eq!(copy.priority, original.priority);
+                assert_eq!(copy.inserted_at, original.inserted_at);
+            }
 
-            model.selected.column_index = Some(0);
-            model.selected.card_index = Some(0);
+            let events = repo.get_card_events(copy.columns[0].cards[0].id).unwrap();
+            assert_eq!(events[0].kind, CardEventKind::Imported);
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            // the next card on the copy comes after the numbers it was given
+            let card = repo.insert_card(copy_id, "four", "").unwrap();
+            assert_eq!(card.external_id.0, 4);
+        }
 
-            update(&mut model, crate::Message::NavigateUp, &mut terminal).unwrap();
+        #[test]
+        fn json_keeps_column_stages() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            repo.create_board(
+                "Board1",
+                &["Todo", "Doing", "Done"],
+                &[None, Some(Stage::Doing), Some(Stage::Done)],
+            )
+            .unwrap();
 
-            assert_eq!(model.running_state, RunningState::Running);
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("board.json");
+            export(&mut repo, ExportFormat::Json, &path);
+
+            let exported: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(exported["columns"][1]["stage"], "doing");
+
+            import(&mut repo, &path, Some("Copy"), false).unwrap();
+
+            let copy_id = repo.get_board_id_by_name("Copy").unwrap();
+            let copy = repo.get_board(copy_id).unwrap();
             assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(0),
-                    card_index: Some(0)
-                }
+                copy.columns
+                    .iter()
+                    .map(|column| column.stage)
+                    .collect::<Vec<_>>(),
+                vec![None, Some(Stage::Doing), Some(Stage::Done)]
             );
         }
 
         #[test]
-        fn when_length_is_greater_than_one() {
-            let mut model = Model::new(Options::test_options()).unwrap();
-
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+        fn refuses_or_merges_a_board_with_the_same_name() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("board.json");
+            export(&mut repo, ExportFormat::Json, &path);
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            assert!(import(&mut repo, &path, None, false).is_err());
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                |_terminal, _template| Ok("card1\n=====\n\n- body1\n".to_string()),
-            )
-            .unwrap();
+            // card 1 is edited, and a new column has a card without a number
+            let mut board: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            board["columns"][0]["cards"][1]["title"] = "one, edited".into();
+            board["columns"]
+                .as_array_mut()
+                .unwrap()
+                .push(serde_json::json!({"name": "Later", "cards": [{"title": "new"}]}));
+            std::fs::write(&path, board.to_string()).unwrap();
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                |_terminal, _template| Ok("card2\n=====\n\n- body2\n".to_string()),
-            )
-            .unwrap();
+            import(&mut repo, &path, None, true).unwrap();
 
-            update(&mut model, crate::Message::NavigateUp, &mut terminal).unwrap();
+            assert_eq!(
+                list(&mut repo, "Board1"),
+                "Todo\n  3\tthree\n  1\tone, edited\nDone\nLater\n  4\tnew\n"
+            );
 
-            assert_eq!(model.running_state, RunningState::Running);
+            // an archived board is not merged into
+            let board1_id = repo.get_board_id_by_name("Board1").unwrap();
+            repo.set_board_archived(board1_id, true).unwrap();
+            assert!(
+                import(&mut repo, &path, None, true)
+                    .unwrap_err()
+                    .to_string()
+                    .contains("archived")
+            );
+            repo.set_board_archived(board1_id, false).unwrap();
             assert_eq!(
-                model.selected,
-                SelectedState {
-                    board_index: None,
-                    column_index: Some(0),
-                    card_index: Some(0)
-                }
+                list(&mut repo, "Board1"),
+                "Todo\n  3\tthree\n  1\tone, edited\nDone\nLater\n  4\tnew\n"
             );
         }
-    }
 
-    mod switch_to_moving_mode {
-        use crate::{
-            Message, Mode, Model, Options, RunningState, update, update_with_run_editor_fn,
-        };
+        #[test]
+        fn bad_cards_import_nothing() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("board.json");
+
+            for card in [
+                serde_json::json!({"title": "bad date", "due_on": "tomorrow"}),
+                serde_json::json!({"title": "bad tag", "tags": ["a,b"]}),
+            ] {
+                let board = serde_json::json!({
+                    "name": "Board1",
+                    "columns": [{"name": "Todo", "cards": [{"title": "fine"}, card]}]
+                });
+                std::fs::write(&path, board.to_string()).unwrap();
+
+                assert!(import(&mut repo, &path, None, false).is_err());
+                assert!(repo.get_board_id_by_name("Board1").is_err());
+            }
+        }
 
         #[test]
-        fn switches_with_card() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn csv() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("Copy.csv");
+            export(&mut repo, ExportFormat::Csv, &path);
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            assert_eq!(import(&mut repo, &path, None, false).unwrap(), "Copy\n");
 
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            // there's no row to say an empty column is there
+            assert_eq!(list(&mut repo, "Copy"), "Todo\n  3\tthree\n  1\tone\n");
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                |_terminal, _template| Ok("card1\n=====\n\n- Todo\n".to_string()),
+            let copy_id = repo.get_board_id_by_name("Copy").unwrap();
+            let copy = repo.get_board(copy_id).unwrap();
+            assert_eq!(
+                copy.columns[0].cards[1].body,
+                "a body, with a comma\nand a \"quote\""
+            );
+            assert_eq!(copy.columns[0].cards[0].tags, ["bug", "ui"]);
+
+            // blank lines at the end of a file are not rows
+            let mut text = std::fs::read_to_string(&path).unwrap();
+            text.push_str("\n\n");
+            std::fs::write(dir.path().join("Blank.csv"), text).unwrap();
+            import(&mut repo, &dir.path().join("Blank.csv"), None, false).unwrap();
+            assert_eq!(list(&mut repo, "Blank"), "Todo\n  3\tthree\n  1\tone\n");
+        }
+
+        #[test]
+        fn trello() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("trello.json");
+            std::fs::write(
+                &path,
+                serde_json::json!({
+                    "name": "Roadmap",
+                    "lists": [
+                        {"id": "l2", "name": "Doing", "closed": false, "pos": 2.0},
+                        {"id": "l1", "name": "To Do", "closed": false, "pos": 1.0},
+                        {"id": "l3", "name": "Old", "closed": true, "pos": 3.0},
+                    ],
+                    "cards": [
+                        {
+                            "name": "second", "desc": "", "idList": "l1", "closed": false,
+                            "pos": 20.0, "labels": [], "due": null, "idShort": 2,
+                        },
+                        {
+                            "name": "first", "desc": "the details", "idList": "l1",
+                            "closed": false, "pos": 10.0,
+                            "labels": [
+                                {"name": "bug", "color": "red"},
+                                {"name": "", "color": "blue"},
+                                {"name": "Bug, urgent", "color": "orange"},
+                            ],
+                            "due": "2024-12-31T12:00:00.000Z",
+                            "dateLastActivity": "2024-06-01T08:30:00.000Z",
+                            "idShort": 1,
+                        },
+                        {
+                            "name": "in progress", "desc": "", "idList": "l2", "closed": false,
+                            "pos": 1.0, "labels": [], "idShort": 5,
+                        },
+                        {
+                            "name": "closed card", "desc": "", "idList": "l2", "closed": true,
+                            "pos": 2.0, "labels": [], "idShort": 3,
+                        },
+                        {
+                            "name": "in a closed list", "desc": "", "idList": "l3",
+                            "closed": false, "pos": 1.0, "labels": [], "idShort": 4,
+                        },
+                    ],
+                })
+                .to_string(),
             )
             .unwrap();
 
-            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(
+                run(
+                    &mut repo,
+                    CliCommand::Import {
+                        file: path,
+                        format: Some(ImportFormat::Trello),
+                        board: None,
+                        merge: false,
+                    },
+                )
+                .unwrap(),
+                "Roadmap\n"
+            );
 
-            update(&mut model, crate::Message::MoveCardMode, &mut terminal).unwrap();
+            assert_eq!(
+                list(&mut repo, "Roadmap"),
+                "To Do\n  1\tfirst\n  2\tsecond\nDoing\n  5\tin progress\nArchive\n  4\tin a closed list\n  3\tclosed card\n"
+            );
 
-            assert_eq!(model.running_state, RunningState::Running);
-            assert_eq!(model.mode, Mode::MovingCard);
+            let board_id = repo.get_board_id_by_name("Roadmap").unwrap();
+            let board = repo.get_board(board_id).unwrap();
+            let first = &board.columns[0].cards[0];
+            assert_eq!(first.body, "the details");
+            assert_eq!(first.tags, ["Bug; urgent", "blue", "bug"]);
+            assert_eq!(first.due_on.as_deref(), Some("2024-12-31"));
+            assert_eq!(first.updated_at, "2024-06-01 08:30:00");
+
+            let card = repo.insert_card(board_id, "new", "").unwrap();
+            assert_eq!(card.external_id.0, 6);
         }
 
         #[test]
-        fn does_not_switch_without_card() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn trello_archive_is_its_own_column() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("trello.json");
+            std::fs::write(
+                &path,
+                serde_json::json!({
+                    "name": "Roadmap",
+                    "lists": [{"id": "l1", "name": "Archive", "closed": false, "pos": 1.0}],
+                    "cards": [
+                        {
+                            "name": "kept", "idList": "l1", "pos": 1.0,
+                            "due": "2024-12-31T23:30:00.000Z", "idShort": 1,
+                        },
+                        {"name": "closed", "idList": "l1", "closed": true, "pos": 2.0, "idShort": 2},
+                    ],
+                })
+                .to_string(),
+            )
+            .unwrap();
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            // the file is json, but not kk's json
+            assert!(
+                run(
+                    &mut repo,
+                    CliCommand::Import {
+                        file: path.clone(),
+                        format: None,
+                        board: None,
+                        merge: false,
+                    },
+                )
+                .unwrap_err()
+                .to_string()
+                .contains("--format trello")
+            );
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+            run(
+                &mut repo,
+                CliCommand::Import {
+                    file: path,
+                    format: Some(ImportFormat::Trello),
+                    board: None,
+                    merge: false,
+                },
             )
             .unwrap();
 
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
-
-            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(
+                list(&mut repo, "Roadmap"),
+                "Archive\n  1\tkept\nArchive 2\n  2\tclosed\n"
+            );
 
-            update(&mut model, crate::Message::MoveCardMode, &mut terminal).unwrap();
+            // the day it is due where kk runs, not in UTC
+            let local_date: String = rusqlite::Connection::open_in_memory()
+                .unwrap()
+                .query_one(
+                    "select date('2024-12-31 23:30:00', 'localtime')",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            let board = repo
+                .get_board(repo.get_board_id_by_name("Roadmap").unwrap())
+                .unwrap();
+            assert_eq!(board.columns[0].cards[0].due_on, Some(local_date));
+        }
 
-            assert_eq!(model.running_state, RunningState::Running);
-            assert_eq!(model.mode, Mode::ViewingBoard);
+        #[test]
+        fn csv_fields() {
+            assert_eq!(
+                parse_csv("a,\"b,\"\"c\"\"\",\r\n\"d\ne\",f\n").unwrap(),
+                [vec!["a", "b,\"c\"", ""], vec!["d\ne", "f"]]
+            );
+            assert!(parse_csv("a,\"b").is_err());
         }
     }
 
-    mod switch_to_view_card_detail_mode {
+    mod obsidian_sync {
         use crate::{
-            Message, Mode, Model, Options, RunningState, update, update_with_run_editor_fn,
+            CardEventKind, CliCommand, ExportFormat, ImportedBoard, Repo, Stage, run_command,
         };
 
-        #[test]
-        fn switches_when_column_is_not_empty() {
-            let mut model = Model::new(Options::test_options()).unwrap();
-
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
-
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+        fn sync(repo: &mut Repo, path: &std::path::Path) {
+            run_command(
+                repo,
+                CliCommand::Sync {
+                    file: path.to_path_buf(),
+                    board: Some("Board1".to_string()),
+                    watch: false,
+                },
+                &mut vec![],
             )
             .unwrap();
+        }
 
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+        fn list(repo: &mut Repo) -> String {
+            let mut out = vec![];
+            run_command(
+                repo,
+                CliCommand::List {
+                    board: Some("Board1".to_string()),
+                    json: false,
+                },
+                &mut out,
+            )
+            .unwrap();
+            String::from_utf8(out).unwrap()
+        }
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewCard,
-                &mut terminal,
-                |_terminal, _template| Ok("card1\n=====\n\n- Todo\n".to_string()),
+        /// "Todo" has card 2, tagged and due with a body, and card 1. "Done" has card 3
+        fn setup(repo: &mut Repo) {
+            repo.create_board("Board1", &["Todo", "Done"], &[None, Some(Stage::Done)])
+                .unwrap();
+            let board_id = repo.get_board_id_by_name("Board1").unwrap();
+
+            repo.insert_card(board_id, "plain", "").unwrap();
+            let card = repo
+                .insert_card(board_id, "detailed", "a body\n\nin two paragraphs")
+                .unwrap();
+            repo.set_card_fields(
+                board_id,
+                card.id,
+                &crate::CardText {
+                    title: card.title,
+                    body: card.body,
+                    tags: vec!["bug".to_string(), "needs review".to_string()],
+                    due_on: Some("2024-12-31".to_string()),
+                    priority: None,
+                },
             )
             .unwrap();
+            let card = repo.insert_card(board_id, "finished", "").unwrap();
+            repo.set_card_status(board_id, card.id, "Done").unwrap();
+        }
 
-            assert_eq!(model.mode, Mode::ViewingBoard);
+        const EXPORTED: &str = "---
 
-            update(
-                &mut model,
-                crate::Message::ViewCardDetailMode,
-                &mut terminal,
+kanban-plugin: basic
+
+---
+
+## Todo
+
+- [ ] detailed #bug #needs%20review @{2024-12-31} ^kk-2
+\ta body
+
+\tin two paragraphs
+- [ ] plain ^kk-1
+
+## Done
+
+- [x] finished ^kk-3
+";
+
+        #[test]
+        fn export() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
+
+            let mut out = vec![];
+            run_command(
+                &mut repo,
+                CliCommand::Export {
+                    board: None,
+                    format: ExportFormat::Obsidian,
+                    output: None,
+                },
+                &mut out,
             )
             .unwrap();
 
-            assert_eq!(model.running_state, RunningState::Running);
-            assert_eq!(model.mode, Mode::ViewingCardDetail);
+            assert_eq!(String::from_utf8(out).unwrap(), EXPORTED);
         }
 
         #[test]
-        fn does_not_switch_when_column_is_empty() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn reads_what_the_plugin_writes() {
+            let board = ImportedBoard::from_obsidian(
+                "Plans",
+                "---
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+kanban-plugin: basic
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+---
 
-            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+## To Do
 
-            assert_eq!(model.mode, Mode::ViewingBoard);
+- [ ] first<br>more about it #idea @{2025-01-02}
+- [ ] about #2 things
 
-            update(
-                &mut model,
-                crate::Message::ViewCardDetailMode,
-                &mut terminal,
-            )
-            .unwrap();
+## Done
 
-            assert_eq!(model.running_state, RunningState::Running);
-            assert_eq!(model.mode, Mode::ViewingBoard);
-        }
-    }
+**Complete**
+- [x] shipped
 
-    mod switch_to_viewing_board_mode {
-        use crate::{Mode, Model, Options, RunningState, update};
+
+%% kanban:settings
+```
+{\"kanban-plugin\":\"basic\"}
+```
+%%
+",
+            );
+
+            assert_eq!(board.name, "Plans");
+            assert_eq!(board.columns.len(), 2);
+            assert_eq!(board.columns[0].name, "To Do");
+
+            let first = &board.columns[0].cards[0];
+            assert_eq!(first.title, "first");
+            assert_eq!(first.body, "more about it #idea @{2025-01-02}");
+            assert_eq!(first.external_id, None);
+
+            let second = &board.columns[0].cards[1];
+            assert_eq!(second.title, "about #2 things");
+
+            assert_eq!(board.columns[1].cards.len(), 1);
+            assert_eq!(board.columns[1].cards[0].title, "shipped");
+
+            let card = &ImportedBoard::from_obsidian("Board1", EXPORTED).columns[0].cards[0];
+            assert_eq!(card.title, "detailed");
+            assert_eq!(card.body, "a body\n\nin two paragraphs");
+            assert_eq!(card.tags, ["bug", "needs review"]);
+            assert_eq!(card.due_on.as_deref(), Some("2024-12-31"));
+            assert_eq!(card.external_id.map(|id| id.0), Some(2));
+        }
 
         #[test]
-        fn switches() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn changes_go_both_ways() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
+            let board_id = repo.get_board_id_by_name("Board1").unwrap();
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("board.md");
 
-            model.mode = Mode::ViewingCardDetail;
+            // there's no file yet, so the board is written out
+            sync(&mut repo, &path);
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), EXPORTED);
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            // in the file, card 1 is edited and moved, card 3 is deleted,
+            // and a card is added, while the board gets a card of its own
+            std::fs::write(
+                &path,
+                "---
 
-            assert_eq!(model.running_state, RunningState::Running);
-            assert_eq!(model.mode, Mode::ViewingBoard);
+kanban-plugin: basic
 
-            model.mode = Mode::MovingCard;
+---
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+## Todo
 
-            assert_eq!(model.running_state, RunningState::Running);
-            assert_eq!(model.mode, Mode::ViewingBoard);
-        }
-    }
+- [ ] written in the file
+- [ ] detailed #bug #needs%20review @{2024-12-31} ^kk-2
+\ta body
 
-    mod boards_view {
-        use crate::{Mode, Model, Options, update, update_with_run_editor_fn};
+\tin two paragraphs
 
-        #[test]
-        fn navigate_down_with_one_board() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+## Done
 
-            model.create_board("Board1", &["Todo"]).unwrap();
+- [ ] plain, edited ^kk-1
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            assert_eq!(model.mode, Mode::ViewingBoards);
+%% kanban:settings
+```
+{}
+```
+%%
+",
+            )
+            .unwrap();
+            repo.insert_card(board_id, "added in kk", "").unwrap();
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            sync(&mut repo, &path);
 
-            update(&mut model, crate::Message::ViewBoardsMode, &mut terminal).unwrap();
+            // the card added in kk stays where kk put it, above the card the file didn't move
+            assert_eq!(
+                list(&mut repo),
+                "Todo\n  5\twritten in the file\n  4\tadded in kk\n  2\tdetailed\nDone\n  1\tplain, edited\n"
+            );
 
-            assert_eq!(model.mode, Mode::ViewingBoards);
+            let (card, _) = repo
+                .get_card_by_external_id(board_id, crate::ExternalCardId(1))
+                .unwrap();
+            assert!(card.done_at.is_some());
+            let events = repo.get_card_events(card.id).unwrap();
+            assert!(
+                events
+                    .iter()
+                    .any(|event| event.kind == CardEventKind::Moved)
+            );
 
-            assert_eq!(model.selected.board_index, Some(0));
+            let text = std::fs::read_to_string(&path).unwrap();
+            assert!(text.contains("- [ ] written in the file ^kk-5\n"));
+            assert!(text.contains("- [ ] added in kk ^kk-4\n"));
+            assert!(text.contains("- [x] plain, edited ^kk-1\n"));
+            assert!(text.ends_with("%% kanban:settings\n```\n{}\n```\n%%\n"));
 
-            assert!(model.board.is_none());
+            // a card deleted in kk since the last sync doesn't come back
+            let (card, _) = repo
+                .get_card_by_external_id(board_id, crate::ExternalCardId(4))
+                .unwrap();
+            repo.delete_card(card.id).unwrap();
+            std::fs::write(&path, text.replace("plain, edited", "plain, edited again")).unwrap();
 
-            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+            sync(&mut repo, &path);
 
-            assert_eq!(model.selected.board_index, Some(0));
+            assert_eq!(
+                list(&mut repo),
+                "Todo\n  5\twritten in the file\n  2\tdetailed\nDone\n  1\tplain, edited again\n"
+            );
+            assert!(
+                !std::fs::read_to_string(&path)
+                    .unwrap()
+                    .contains("added in kk")
+            );
+
+            // nothing changed, so nothing is written
+            let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+            sync(&mut repo, &path);
+            assert_eq!(
+                std::fs::metadata(&path).unwrap().modified().unwrap(),
+                modified
+            );
         }
 
         #[test]
-        fn navigate_down_with_two_boards() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn only_changes_in_the_file_are_merged() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            setup(&mut repo);
+            let board_id = repo.get_board_id_by_name("Board1").unwrap();
+
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("board.md");
+            sync(&mut repo, &path);
+
+            run_command(
+                &mut repo,
+                CliCommand::Edit {
+                    id: 2,
+                    title: Some("detailed, edited in kk".to_string()),
+                    body: None,
+                    board: None,
+                },
+                &mut vec![],
+            )
+            .unwrap();
+            repo.conn
+                .execute("update cards set updated_at = '2000-01-01 00:00:00'", [])
+                .unwrap();
+            let ranks = |repo: &Repo| {
+                repo.conn
+                    .prepare("select external_id, rank from cards order by external_id")
+                    .unwrap()
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .unwrap()
+                    .collect::<Result<Vec<(i64, i64)>, _>>()
+                    .unwrap()
+            };
+            let ranks_before = ranks(&repo);
 
-            assert_eq!(model.mode, Mode::ViewingBoards);
+            // only card 1 changed in the file
+            let text = std::fs::read_to_string(&path).unwrap();
+            std::fs::write(&path, text.replace("plain ^kk-1", "plain, edited ^kk-1")).unwrap();
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            sync(&mut repo, &path);
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            assert_eq!(
+                list(&mut repo),
+                "Todo\n  2\tdetailed, edited in kk\n  1\tplain, edited\nDone\n  3\tfinished\n"
+            );
+            assert!(
+                std::fs::read_to_string(&path)
+                    .unwrap()
+                    .contains("- [ ] detailed, edited in kk #bug")
+            );
+            assert_eq!(ranks(&repo), ranks_before);
 
-            assert_eq!(model.selected.board_index, Some(0));
+            for (external_id, updated) in [(1, true), (2, false), (3, false)] {
+                let (card, _) = repo
+                    .get_card_by_external_id(board_id, crate::ExternalCardId(external_id))
+                    .unwrap();
+                assert_eq!(card.updated_at != "2000-01-01 00:00:00", updated);
+            }
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board2\n=====\n\n- Todo\n".to_string()),
+            // swapping two cards in the file reorders them without updating either
+            let text = std::fs::read_to_string(&path).unwrap();
+            let (todo, done) = text.split_once("## Done").unwrap();
+            let (heading, cards) = todo.split_once("- [ ] detailed").unwrap();
+            let (detailed, plain) = cards.split_once("- [ ] plain").unwrap();
+            repo.conn
+                .execute("update cards set updated_at = '2000-01-01 00:00:00'", [])
+                .unwrap();
+            std::fs::write(
+                &path,
+                format!(
+                    "{}- [ ] plain{}\n- [ ] detailed{}\n## Done{}",
+                    heading,
+                    plain.trim_end(),
+                    detailed.trim_end(),
+                    done
+                ),
             )
             .unwrap();
 
-            assert_eq!(model.selected.board_index, Some(0));
+            sync(&mut repo, &path);
 
-            assert!(model.board.is_none());
+            assert_eq!(
+                list(&mut repo),
+                "Todo\n  1\tplain, edited\n  2\tdetailed, edited in kk\nDone\n  3\tfinished\n"
+            );
+            let board = repo.get_board(board_id).unwrap();
+            assert!(
+                board.columns[0]
+                    .cards
+                    .iter()
+                    .all(|card| card.updated_at == "2000-01-01 00:00:00")
+            );
+        }
 
-            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
+        #[test]
+        fn titles_and_tags_round_trip() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
+            repo.create_board("Board1", &["Todo"], &[]).unwrap();
+            let board_id = repo.get_board_id_by_name("Board1").unwrap();
+
+            let titles = [
+                "ship it #v2",
+                "#urgent",
+                "about ^kk-9",
+                "due @{2024-12-31}",
+                "not escaped \\#twice",
+                "a #tag in the middle",
+            ];
+            for title in titles.iter().rev() {
+                let card = repo.insert_card(board_id, title, "").unwrap();
+                repo.set_card_fields(
+                    board_id,
+                    card.id,
+                    &crate::CardText {
+                        title: card.title,
+                        body: card.body,
+                        tags: vec!["100%".to_string(), "needs review".to_string()],
+                        due_on: None,
+                        priority: None,
+                    },
+                )
+                .unwrap();
+            }
 
-            assert_eq!(model.selected.board_index, Some(1));
+            let mut out = vec![];
+            repo.get_board(board_id)
+                .unwrap()
+                .write_obsidian(&mut out)
+                .unwrap();
+            let board = ImportedBoard::from_obsidian("Board1", &String::from_utf8(out).unwrap());
+
+            assert_eq!(
+                board.columns[0]
+                    .cards
+                    .iter()
+                    .map(|card| card.title.as_str())
+                    .collect::<Vec<_>>(),
+                titles
+            );
+            for card in &board.columns[0].cards {
+                assert_eq!(card.tags, ["100%", "needs review"]);
+            }
         }
 
         #[test]
-        fn navigate_up_with_one_board() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        fn creates_the_board_from_the_file() {
+            let mut repo = Repo::new(Some(":memory:".into())).unwrap();
 
-            assert_eq!(model.mode, Mode::ViewingBoards);
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("board.md");
+            std::fs::write(&path, "## Todo\n\n- [ ] one\n- [ ] two\n\n## Done\n").unwrap();
 
-            model.create_board("Board1", &["Todo"]).unwrap();
+            sync(&mut repo, &path);
 
-            let mut terminal =
+            assert_eq!(list(&mut repo), "Todo\n  1\tone\n  2\ttwo\nDone\n");
+            assert_eq!(
+                std::fs::read_to_string(&path).unwrap(),
+                "---\n\nkanban-plugin: basic\n\n---\n\n## Todo\n\n- [ ] one ^kk-1\n- [ ] two ^kk-2\n\n## Done\n\n"
+            );
+        }
+    }
+
+    mod live_reload {
+        use super::helpers::titles;
+        use crate::{Message, Mode, Model, Options, Repo, update};
+
+        /// a model and a second connection to the same database, like another kk would have
+        fn setup(
+            dir: &tempfile::TempDir,
+        ) -> (
+            Model,
+            ratatui::Terminal<ratatui::backend::TestBackend>,
+            Repo,
+        ) {
+            let database_path = dir.path().join("kk.db");
+
+            let mut other = Repo::new(Some(database_path.clone())).unwrap();
+            other
+                .create_board("Board1", &["Todo", "Done"], &[])
+                .unwrap();
+            let board_id = other.get_board_id_by_name("Board1").unwrap();
+            other.insert_card(board_id, "one", "").unwrap();
+            other.insert_card(board_id, "two", "").unwrap();
+
+            let model = Model::new(Options {
+                database_path: Some(database_path),
+                ..Options::test_options()
+            })
+            .unwrap();
+
+            let terminal =
                 ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
 
-            update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            (model, terminal, other)
+        }
 
-            update(&mut model, crate::Message::ViewBoardsMode, &mut terminal).unwrap();
+        #[test]
+        fn keeps_the_same_card_selected() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, mut other) = setup(&dir);
+            let board_id = model.board.as_ref().unwrap().id;
 
-            assert_eq!(model.mode, Mode::ViewingBoards);
+            // "one", below "two"
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
+            let selected = model.selected_card_id();
 
-            assert_eq!(model.selected.board_index, Some(0));
+            // what the model does itself doesn't count as a change from elsewhere
+            model.repo.insert_card(board_id, "three", "").unwrap();
+            assert_eq!(model.repo.data_version().unwrap(), model.data_version);
 
-            assert!(model.board.is_none());
+            other.insert_card(board_id, "four", "").unwrap();
+            assert_ne!(model.repo.data_version().unwrap(), model.data_version);
 
-            update(&mut model, crate::Message::NavigateUp, &mut terminal).unwrap();
+            update(&mut model, Message::ReloadChangedData, &mut terminal).unwrap();
 
-            assert_eq!(model.selected.board_index, Some(0));
+            assert_eq!(model.repo.data_version().unwrap(), model.data_version);
+            assert_eq!(titles(&model, 0), ["four", "three", "two", "one"]);
+            assert_eq!(model.selected_card_id(), selected);
+            assert_eq!(model.selected.card_index, Some(3));
         }
 
         #[test]
-        fn navigate_up_with_two_boards() {
-            let mut model = Model::new(Options::test_options()).unwrap();
-
-            assert_eq!(model.mode, Mode::ViewingBoards);
+        fn in_any_mode() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, mut other) = setup(&dir);
+            let board_id = model.board.as_ref().unwrap().id;
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
-
-            assert_eq!(model.selected.board_index, None);
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            let (card, _) = other
+                .get_card_by_external_id(board_id, crate::ExternalCardId(2))
+                .unwrap();
+            other.set_card_status(board_id, card.id, "Done").unwrap();
 
-            assert_eq!(model.selected.board_index, Some(0));
+            update(&mut model, Message::ReloadChangedData, &mut terminal).unwrap();
 
-            update_with_run_editor_fn(
-                &mut model,
-                crate::Message::NewBoard,
-                &mut terminal,
-                |_terminal, _template| Ok("Board2\n=====\n\n- Todo\n".to_string()),
-            )
-            .unwrap();
+            assert_eq!(model.mode, Mode::MovingCard);
+            assert_eq!(model.selected.column_index, Some(1));
+            assert_eq!(model.selected_card_id(), Some(card.id));
+        }
 
-            assert_eq!(model.mode, Mode::ViewingBoards);
+        #[test]
+        fn the_card_being_deleted_was_deleted() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, mut other) = setup(&dir);
+            let board_id = model.board.as_ref().unwrap().id;
+
+            // "two" is selected
+            update(&mut model, Message::DeleteCard, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ConfirmCardDeletion);
+
+            let (card, _) = other
+                .get_card_by_external_id(board_id, crate::ExternalCardId(2))
+                .unwrap();
+            other.delete_card(card.id).unwrap();
+
+            // "one" is selected now, but isn't what was going to be deleted
+            assert!(update(&mut model, Message::ReloadChangedData, &mut terminal).is_err());
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert!(
+                other
+                    .get_card_by_external_id(board_id, crate::ExternalCardId(1))
+                    .is_ok()
+            );
 
-            assert_eq!(model.selected.board_index, Some(0));
+            // the same goes for marked cards
+            let three = other.insert_card(board_id, "three", "").unwrap();
+            update(&mut model, Message::ReloadChangedData, &mut terminal).unwrap();
+            model.marked = vec![three.id];
+            update(&mut model, Message::DeleteCard, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ConfirmCardDeletion);
 
-            assert!(model.board.is_none());
+            other.delete_card(three.id).unwrap();
 
-            update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
-            assert_eq!(model.selected.board_index, Some(1));
-            update(&mut model, crate::Message::NavigateUp, &mut terminal).unwrap();
-            assert_eq!(model.selected.board_index, Some(0));
+            assert!(update(&mut model, Message::ReloadChangedData, &mut terminal).is_err());
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert!(model.marked.is_empty());
+            // only "one" is left
+            assert_eq!(model.board.as_ref().unwrap().columns[0].cards.len(), 1);
         }
-    }
 
-    #[test]
-    fn delete_card() {
-        let mut model = Model::new(Options::test_options()).unwrap();
+        #[test]
+        fn undoing_a_move_of_a_card_deleted_elsewhere() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, mut other) = setup(&dir);
 
-        let mut terminal =
-            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+            update(&mut model, Message::MoveCardMode, &mut terminal).unwrap();
+            update(&mut model, Message::MoveCardRight, &mut terminal).unwrap();
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+            let card_id = model.selected_card_id().unwrap();
 
-        update_with_run_editor_fn(
-            &mut model,
-            crate::Message::NewBoard,
-            &mut terminal,
-            |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-        )
-        .unwrap();
+            other.delete_card(card_id).unwrap();
 
-        update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            let error = update(&mut model, Message::Undo, &mut terminal).unwrap_err();
 
-        let update_result = update_with_run_editor_fn(
-            &mut model,
-            crate::Message::NewCard,
-            &mut terminal,
-            // replace default run_editor_fn with a stub that returns valid data
-            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                Ok("Valid Title\n==========\n\nValid card body".to_string())
-            },
-        );
+            assert_eq!(error.to_string(), "card 2 no longer exists");
+        }
+
+        #[test]
+        fn the_board_was_deleted() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, other) = setup(&dir);
+            let board_id = model.board.as_ref().unwrap().id;
 
-        assert!(update_result.is_ok());
+            other.delete_board(board_id).unwrap();
 
-        let card = model.selected_card().unwrap();
+            assert!(update(&mut model, Message::ReloadChangedData, &mut terminal).is_err());
+            assert_eq!(model.mode, Mode::ViewingBoards);
+            assert!(model.board.is_none());
+            assert!(model.board_metas.is_empty());
+        }
+    }
 
-        assert_eq!(
-            &Card {
-                id: 1.into(),
-                external_id: 1.into(),
-                title: "Valid Title".to_string(),
-                body: "Valid card body".to_string(),
-                inserted_at: "".to_string(),
-                updated_at: "".to_string()
-            },
-            card
-        );
+    mod edit_conflict {
+        use std::cell::RefCell;
 
-        let column = model.selected_column().unwrap();
-        assert!(!column.cards.is_empty());
+        use ratatui::{Terminal, backend::TestBackend};
 
-        update(&mut model, crate::Message::DeleteCard, &mut terminal).unwrap();
-        assert_eq!(model.confirmation_state, ConfirmationState::No);
-        assert_eq!(model.mode, Mode::ConfirmCardDeletion);
+        use super::helpers::selected_title;
+        use crate::{Message, Mode, Model, Options, Repo, update, update_with_run_editor_fn};
 
-        let column = model.selected_column().unwrap();
-        assert!(!column.cards.is_empty());
+        /// a model and a second connection to the same database, like another kk would have
+        fn setup(dir: &tempfile::TempDir) -> (Model, Terminal<TestBackend>, RefCell<Repo>) {
+            let database_path = dir.path().join("kk.db");
 
-        update(&mut model, crate::Message::NavigateLeft, &mut terminal).unwrap();
-        assert_eq!(model.confirmation_state, ConfirmationState::Yes);
+            let mut other = Repo::new(Some(database_path.clone())).unwrap();
+            other
+                .create_board("Board1", &["Todo", "Done"], &[])
+                .unwrap();
+            let board_id = other.get_board_id_by_name("Board1").unwrap();
+            other.insert_card(board_id, "one", "").unwrap();
 
-        update(&mut model, crate::Message::ConfirmChoice, &mut terminal).unwrap();
-        let column = model.selected_column().unwrap();
-        assert!(column.cards.is_empty());
-    }
+            let model = Model::new(Options {
+                database_path: Some(database_path),
+                ..Options::test_options()
+            })
+            .unwrap();
 
-    #[test]
-    fn card_ids_are_unique_and_increment_per_board() {
-        let mut model = Model::new(Options::test_options()).unwrap();
+            let terminal = Terminal::new(TestBackend::new(80, 80)).unwrap();
 
-        assert_eq!(model.selected.column_index, None);
-        assert_eq!(model.selected.card_index, None);
+            (model, terminal, RefCell::new(other))
+        }
 
-        let mut terminal =
-            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+        /// edits the selected card, while `other` changes its title to "theirs"
+        fn edit_during_their_edit(
+            model: &mut Model,
+            terminal: &mut Terminal<TestBackend>,
+            other: &RefCell<Repo>,
+        ) {
+            let card_id = model.selected_card_id().unwrap();
 
-        update_with_run_editor_fn(
-            &mut model,
-            crate::Message::NewBoard,
-            &mut terminal,
-            |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
-        )
-        .unwrap();
+            update_with_run_editor_fn(
+                model,
+                Message::EditCard,
+                terminal,
+                |_terminal: &mut Terminal<TestBackend>, _template: &str| {
+                    other
+                        .borrow_mut()
+                        .update_card(card_id, "theirs", "their body")
+                        .unwrap();
+                    Ok("mine\n==========\n\nmy body".to_string())
+                },
+            )
+            .unwrap();
+        }
 
-        assert_eq!(model.selected.board_index, Some(0));
+        #[test]
+        fn keep_mine() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, other) = setup(&dir);
 
-        update_with_run_editor_fn(
-            &mut model,
-            crate::Message::NewBoard,
-            &mut terminal,
-            |_terminal, _template| Ok("Board2\n=====\n\n- Todo\n".to_string()),
-        )
-        .unwrap();
+            edit_during_their_edit(&mut model, &mut terminal, &other);
 
-        update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+            assert_eq!(model.mode, Mode::ResolvingConflict);
+            // nothing is written until there's a choice
+            assert_eq!(
+                model
+                    .repo
+                    .get_card(model.selected_card_id().unwrap())
+                    .unwrap()
+                    .unwrap()
+                    .title,
+                "theirs"
+            );
 
-        let update_result1 = update_with_run_editor_fn(
-            &mut model,
-            crate::Message::NewCard,
-            &mut terminal,
-            // replace default run_editor_fn with a stub that returns valid data
-            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                Ok("Valid Title\n==========\n\nValid card body".to_string())
-            },
-        );
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-        assert!(&update_result1.is_ok());
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert!(model.conflict.is_none());
+            assert_eq!(selected_title(&model), "mine");
+            let card = model
+                .repo
+                .get_card(model.selected_card_id().unwrap())
+                .unwrap()
+                .unwrap();
+            assert_eq!(card.title, "mine");
+            assert_eq!(card.body, "my body");
+        }
 
-        let update_result2 = update_with_run_editor_fn(
-            &mut model,
-            crate::Message::NewCard,
-            &mut terminal,
-            // replace default run_editor_fn with a stub that returns valid data
-            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                Ok("Valid Title\n==========\n\nValid card body".to_string())
-            },
-        );
+        #[test]
+        fn keep_theirs() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, other) = setup(&dir);
 
-        assert!(&update_result2.is_ok());
+            edit_during_their_edit(&mut model, &mut terminal, &other);
 
-        assert_eq!(
-            model.board.as_ref().unwrap().columns[0].cards,
-            vec![
-                Card {
-                    id: 2.into(),
-                    external_id: 2.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
-                },
-                Card {
-                    id: 1.into(),
-                    external_id: 1.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
-                },
-            ]
-        );
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
+            update(&mut model, Message::ConfirmChoice, &mut terminal).unwrap();
 
-        assert_eq!(model.selected.column_index, Some(0));
-        assert_eq!(model.selected.card_index, Some(0));
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(selected_title(&model), "theirs");
+            assert_eq!(model.selected_card().unwrap().body, "their body");
+        }
 
-        update(&mut model, crate::Message::ViewBoardsMode, &mut terminal).unwrap();
-        update(&mut model, crate::Message::NavigateDown, &mut terminal).unwrap();
-        update(&mut model, crate::Message::ViewBoardMode, &mut terminal).unwrap();
+        #[test]
+        fn edit_both() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, other) = setup(&dir);
 
-        let update_result1 = update_with_run_editor_fn(
-            &mut model,
-            crate::Message::NewCard,
-            &mut terminal,
-            // replace default run_editor_fn with a stub that returns valid data
-            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                Ok("Valid Title\n==========\n\nValid card body".to_string())
-            },
-        );
+            update(&mut model, Message::ViewCardDetailMode, &mut terminal).unwrap();
 
-        assert!(&update_result1.is_ok());
+            edit_during_their_edit(&mut model, &mut terminal, &other);
 
-        let update_result2 = update_with_run_editor_fn(
-            &mut model,
-            crate::Message::NewCard,
-            &mut terminal,
-            // replace default run_editor_fn with a stub that returns valid data
-            |_terminal: &mut Terminal<ratatui::backend::TestBackend>, _template: &str| {
-                Ok("Valid Title\n==========\n\nValid card body".to_string())
-            },
-        );
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
+            update(&mut model, Message::NavigateDown, &mut terminal).unwrap();
 
-        assert!(&update_result2.is_ok());
+            update_with_run_editor_fn(
+                &mut model,
+                Message::ConfirmChoice,
+                &mut terminal,
+                |_terminal: &mut Terminal<TestBackend>, template: &str| {
+                    assert!(template.starts_with("mine\n==========\n"));
+                    assert!(template.contains("my body"));
+                    assert!(template.contains("theirs\n==========\n"));
+                    assert!(template.contains("their body"));
 
-        // ids here are 4 and 3, but external ids are 2 and 1,
-        // because we are creating ids specific to the other board,
-        // so they start from 1.
-        assert_eq!(
-            model.board.as_ref().unwrap().columns[0].cards,
-            vec![
-                Card {
-                    id: 4.into(),
-                    external_id: 2.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
+                    Ok(template.replace("my body", "my body and their body"))
                 },
-                Card {
-                    id: 3.into(),
-                    external_id: 1.into(),
-                    title: "Valid Title".to_string(),
-                    body: "Valid card body".to_string(),
-                    inserted_at: "".to_string(),
-                    updated_at: "".to_string(),
+            )
+            .unwrap();
+
+            assert_eq!(model.mode, Mode::ViewingCardDetail);
+            assert_eq!(selected_title(&model), "mine");
+            assert_eq!(
+                model.selected_card().unwrap().body,
+                "my body and their body"
+            );
+        }
+
+        #[test]
+        fn a_move_is_not_a_conflict() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, other) = setup(&dir);
+            let board_id = model.board.as_ref().unwrap().id;
+            let card_id = model.selected_card_id().unwrap();
+
+            update_with_run_editor_fn(
+                &mut model,
+                Message::EditCard,
+                &mut terminal,
+                |_terminal: &mut Terminal<TestBackend>, _template: &str| {
+                    other
+                        .borrow_mut()
+                        .set_card_status(board_id, card_id, "Done")
+                        .unwrap();
+                    Ok("mine\n==========\n\nmy body".to_string())
                 },
-            ]
-        );
+            )
+            .unwrap();
 
-        assert_eq!(model.running_state, RunningState::Running);
+            assert_eq!(model.mode, Mode::ViewingBoard);
+            assert_eq!(model.selected.column_index, Some(1));
+            assert_eq!(selected_title(&model), "mine");
+        }
+
+        #[test]
+        fn deleted_while_editing() {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut model, mut terminal, other) = setup(&dir);
+            let card_id = model.selected_card_id().unwrap();
+
+            let result = update_with_run_editor_fn(
+                &mut model,
+                Message::EditCard,
+                &mut terminal,
+                |_terminal: &mut Terminal<TestBackend>, _template: &str| {
+                    other.borrow_mut().delete_card(card_id).unwrap();
+                    Ok("mine\n==========\n\nmy body".to_string())
+                },
+            );
+
+            assert!(result.is_err());
+            assert_eq!(model.mode, Mode::ViewingBoard);
+        }
     }
 
-    mod db_constraits {
-        use crate::{Model, Options, update_with_run_editor_fn};
+    mod editor_drafts {
+        use std::cell::RefCell;
 
-        #[test]
-        fn board_names_are_unique() {
-            let mut model = Model::new(Options::test_options()).unwrap();
+        use ratatui::{Terminal, backend::TestBackend};
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+        use crate::{
+            Message, Model, Options, PARSE_ERROR_PREFIX, edit_until_parsed, parse_raw_card_text,
+            remove_parse_error, update, update_with_run_editor_fn,
+        };
+
+        const LONG_BODY: &str = "a body that took a long time to write";
+
+        fn setup() -> (Model, Terminal<TestBackend>) {
+            let mut model = Model::new(Options::test_options()).unwrap();
+            let mut terminal = Terminal::new(TestBackend::new(80, 80)).unwrap();
 
             update_with_run_editor_fn(
                 &mut model,
-                crate::Message::NewBoard,
+                Message::NewBoard,
                 &mut terminal,
                 |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
             )
             .unwrap();
 
-            let when_inserting_a_second_board_with_duplicate_name = update_with_run_editor_fn(
+            update(&mut model, Message::ViewBoardMode, &mut terminal).unwrap();
+
+            (model, terminal)
+        }
+
+        #[test]
+        fn reopens_the_editor_with_the_error() {
+            let (mut model, mut terminal) = setup();
+
+            let templates = RefCell::new(vec![]);
+
+            update_with_run_editor_fn(
                 &mut model,
-                crate::Message::NewBoard,
+                Message::NewCard,
                 &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n".to_string()),
+                |_terminal: &mut Terminal<TestBackend>, template: &str| {
+                    templates.borrow_mut().push(template.to_string());
+
+                    if templates.borrow().len() == 1 {
+                        Ok(format!("Title\n=====\ndue: tomorrow\n\n{LONG_BODY}"))
+                    } else {
+                        Ok(template.replace("due: tomorrow", "due: 2024-12-31"))
+                    }
+                },
+            )
+            .unwrap();
+
+            let templates = templates.into_inner();
+            assert_eq!(templates.len(), 2);
+            assert!(templates[1].starts_with(&format!(
+                "{PARSE_ERROR_PREFIX}could not parse raw card text: due dates look like"
+            )));
+            assert!(
+                templates[1].ends_with(&format!("\n\nTitle\n=====\ndue: tomorrow\n\n{LONG_BODY}"))
             );
 
-            assert!(when_inserting_a_second_board_with_duplicate_name.is_err());
+            let card = model.selected_card().unwrap();
+            assert_eq!(card.title, "Title");
+            assert_eq!(card.body, LONG_BODY);
+            assert_eq!(card.due_on.as_deref(), Some("2024-12-31"));
         }
-        #[test]
-        fn columns_are_unique_per_board() {
-            let mut model = Model::new(Options::test_options()).unwrap();
 
-            let mut terminal =
-                ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 80)).unwrap();
+        #[test]
+        fn gives_up_when_nothing_changes() {
+            let (mut model, mut terminal) = setup();
 
-            let when_creating_duplicate_columns_on_board = update_with_run_editor_fn(
+            let result = update_with_run_editor_fn(
                 &mut model,
-                crate::Message::NewBoard,
+                Message::NewCard,
                 &mut terminal,
-                |_terminal, _template| Ok("Board1\n=====\n\n- Todo\n- Todo\n".to_string()),
+                |_terminal: &mut Terminal<TestBackend>, template: &str| {
+                    if template.starts_with(PARSE_ERROR_PREFIX) {
+                        Ok(template.to_string())
+                    } else {
+                        Ok(format!("Title\n=====\ncolour: red\n\n{LONG_BODY}"))
+                    }
+                },
             );
 
-            assert!(when_creating_duplicate_columns_on_board.is_err());
+            let e = result.unwrap_err().to_string();
+            let (e, path) = e.split_once(". your draft is in ").unwrap();
+            assert_eq!(e, "could not parse raw card text: bad field: colour: red");
+            assert!(model.board.as_ref().unwrap().columns[0].cards.is_empty());
+
+            // nothing that was written is lost
+            assert_eq!(
+                std::fs::read_to_string(path).unwrap(),
+                format!("Title\n=====\ncolour: red\n\n{LONG_BODY}")
+            );
+            std::fs::remove_file(path).unwrap();
+        }
+
+        #[test]
+        fn a_first_draft_is_kept_as_it_is() {
+            let text = edit_until_parsed(
+                "Title\n=====\n\nbody",
+                |_template| Ok(format!("{PARSE_ERROR_PREFIX}title\n=====\n\nbody")),
+                parse_raw_card_text,
+            )
+            .unwrap();
+
+            assert_eq!(text.title, format!("{PARSE_ERROR_PREFIX}title"));
+        }
+
+        #[test]
+        fn removes_only_its_own_lines() {
+            let draft = "# Title\n=====\n\nbody";
+
+            assert_eq!(remove_parse_error(draft), draft);
+            assert_eq!(
+                remove_parse_error(&format!(
+                    "{PARSE_ERROR_PREFIX}one\n{PARSE_ERROR_PREFIX}two\n\n{draft}"
+                )),
+                draft
+            );
+        }
+
+        #[test]
+        fn outside_the_tui() {
+            let mut edits = 0;
+
+            let text = edit_until_parsed(
+                "Title\n=====\n\nbody",
+                |template| {
+                    edits += 1;
+                    Ok(template.replace("body", "edited body"))
+                },
+                parse_raw_card_text,
+            )
+            .unwrap();
+
+            assert_eq!(edits, 1);
+            assert_eq!(text.title, "Title");
+            assert_eq!(text.body, "edited body");
         }
     }
 }